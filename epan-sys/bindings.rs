@@ -1838,6 +1838,38 @@ pub const MPTCP_CAPABLE_CRYPTO_MASK: u32 = 63;
 pub const MPTCP_CHECKSUM_MASK: u32 = 128;
 pub const TCP_MAX_UNACKED_SEGMENTS: u32 = 10000;
 pub const TCP_FLOW_REASSEMBLE_UNTIL_FIN: u32 = 1;
+pub const EXP_PDU_TAG_END_OF_OPT: u32 = 0;
+pub const EXP_PDU_TAG_OPTIONS_LENGTH: u32 = 10;
+pub const EXP_PDU_TAG_LINKTYPE: u32 = 11;
+pub const EXP_PDU_TAG_DISSECTOR_NAME: u32 = 12;
+pub const EXP_PDU_TAG_HEUR_DISSECTOR_NAME: u32 = 13;
+pub const EXP_PDU_TAG_DISSECTOR_TABLE_NAME: u32 = 14;
+pub const EXP_PDU_TAG_IPV4_SRC: u32 = 20;
+pub const EXP_PDU_TAG_IPV4_DST: u32 = 21;
+pub const EXP_PDU_TAG_IPV6_SRC: u32 = 22;
+pub const EXP_PDU_TAG_IPV6_DST: u32 = 23;
+pub const EXP_PDU_TAG_PORT_TYPE: u32 = 24;
+pub const EXP_PDU_TAG_SRC_PORT: u32 = 25;
+pub const EXP_PDU_TAG_DST_PORT: u32 = 26;
+pub const EXP_PDU_TAG_SS7_OPC: u32 = 28;
+pub const EXP_PDU_TAG_SS7_DPC: u32 = 29;
+pub const EXP_PDU_TAG_ORIG_FNO: u32 = 30;
+pub const EXP_PDU_TAG_DVBCI_EVT: u32 = 31;
+pub const EXP_PDU_TAG_DISSECTOR_TABLE_NAME_NUM_VAL: u32 = 32;
+pub const EXP_PDU_TAG_COL_PROT_TEXT: u32 = 33;
+pub const EXP_PDU_TAG_TCP_INFO_DATA: u32 = 34;
+pub const EXP_PDU_TAG_P2P_DIRECTION: u32 = 35;
+pub const EXP_PDU_TAG_COL_INFO_TEXT: u32 = 36;
+pub const EXP_PDU_TAG_USER_DATA_PDU: u32 = 37;
+pub const EXP_PDU_TAG_IPV4_LEN: u32 = 4;
+pub const EXP_PDU_TAG_IPV6_LEN: u32 = 16;
+pub const EXP_PDU_TAG_PORT_TYPE_LEN: u32 = 4;
+pub const EXP_PDU_TAG_PORT_LEN: u32 = 4;
+pub const EXP_PDU_TAG_SS7_OPC_LEN: u32 = 8;
+pub const EXP_PDU_TAG_SS7_DPC_LEN: u32 = 8;
+pub const EXP_PDU_TAG_ORIG_FNO_LEN: u32 = 4;
+pub const EXP_PDU_TAG_DVBCI_EVT_LEN: u32 = 1;
+pub const EXP_PDU_TAG_DISSECTOR_TABLE_NUM_VAL_LEN: u32 = 4;
 pub type __u_char = ::std::os::raw::c_uchar;
 pub type __u_short = ::std::os::raw::c_ushort;
 pub type __u_int = ::std::os::raw::c_uint;
@@ -28820,8 +28852,16 @@ unsafe extern "C" {
 #[repr(C)]
 #[derive(Debug, Copy, Clone)]
 pub struct expert_field {
-    _unused: [u8; 0],
+    pub ei: ::std::os::raw::c_int,
+    pub hf: ::std::os::raw::c_int,
 }
+#[allow(clippy::unnecessary_operation, clippy::identity_op)]
+const _: () = {
+    ["Size of expert_field"][::std::mem::size_of::<expert_field>() - 8usize];
+    ["Alignment of expert_field"][::std::mem::align_of::<expert_field>() - 4usize];
+    ["Offset of field: expert_field::ei"][::std::mem::offset_of!(expert_field, ei) - 0usize];
+    ["Offset of field: expert_field::hf"][::std::mem::offset_of!(expert_field, hf) - 4usize];
+};
 pub type custom_fmt_func_t =
     ::std::option::Option<unsafe extern "C" fn(arg1: *mut ::std::os::raw::c_char, arg2: u32)>;
 pub type custom_fmt_func_64_t =
@@ -31001,6 +31041,1027 @@ const _: () = {
 pub struct epan_uat {
     _unused: [u8; 0],
 }
+pub type uat_t = epan_uat;
+pub type uat_post_update_cb_t = ::std::option::Option<unsafe extern "C" fn()>;
+pub type uat_copy_cb_t = ::std::option::Option<
+    unsafe extern "C" fn(
+        dest: *mut ::std::os::raw::c_void,
+        source: *const ::std::os::raw::c_void,
+        len: usize,
+    ) -> *mut ::std::os::raw::c_void,
+>;
+pub type uat_free_cb_t =
+    ::std::option::Option<unsafe extern "C" fn(record: *mut ::std::os::raw::c_void)>;
+pub type uat_reset_cb_t = ::std::option::Option<unsafe extern "C" fn()>;
+pub type uat_update_cb_t = ::std::option::Option<
+    unsafe extern "C" fn(
+        record: *mut ::std::os::raw::c_void,
+        error: *mut *mut ::std::os::raw::c_char,
+    ) -> bool,
+>;
+pub type uat_fld_chk_cb_t = ::std::option::Option<
+    unsafe extern "C" fn(
+        record: *mut ::std::os::raw::c_void,
+        ptr: *const ::std::os::raw::c_char,
+        len: ::std::os::raw::c_uint,
+        chk_data: *const ::std::os::raw::c_void,
+        fld_data: *const ::std::os::raw::c_void,
+        error: *mut *mut ::std::os::raw::c_char,
+    ) -> bool,
+>;
+pub type uat_fld_set_cb_t = ::std::option::Option<
+    unsafe extern "C" fn(
+        record: *mut ::std::os::raw::c_void,
+        ptr: *const ::std::os::raw::c_char,
+        len: ::std::os::raw::c_uint,
+        set_data: *const ::std::os::raw::c_void,
+        fld_data: *const ::std::os::raw::c_void,
+    ),
+>;
+pub type uat_fld_tostr_cb_t = ::std::option::Option<
+    unsafe extern "C" fn(
+        record: *mut ::std::os::raw::c_void,
+        out_ptr: *mut *mut ::std::os::raw::c_char,
+        out_len: *mut ::std::os::raw::c_uint,
+        tostr_data: *const ::std::os::raw::c_void,
+        fld_data: *const ::std::os::raw::c_void,
+    ),
+>;
+pub const _uat_text_mode_t_PT_TXTMOD_NONE: _uat_text_mode_t = 0;
+pub const _uat_text_mode_t_PT_TXTMOD_STRING: _uat_text_mode_t = 1;
+pub const _uat_text_mode_t_PT_TXTMOD_HEXBYTES: _uat_text_mode_t = 2;
+pub const _uat_text_mode_t_PT_TXTMOD_ENUM: _uat_text_mode_t = 3;
+pub const _uat_text_mode_t_PT_TXTMOD_DISSECTOR: _uat_text_mode_t = 4;
+pub const _uat_text_mode_t_PT_TXTMOD_COLOR: _uat_text_mode_t = 5;
+pub const _uat_text_mode_t_PT_TXTMOD_FILENAME: _uat_text_mode_t = 6;
+pub const _uat_text_mode_t_PT_TXTMOD_DIRECTORYNAME: _uat_text_mode_t = 7;
+pub const _uat_text_mode_t_PT_TXTMOD_DISPLAY_FILTER: _uat_text_mode_t = 8;
+pub const _uat_text_mode_t_PT_TXTMOD_PROTO_FIELD: _uat_text_mode_t = 9;
+pub const _uat_text_mode_t_PT_TXTMOD_BOOL: _uat_text_mode_t = 10;
+pub type _uat_text_mode_t = ::std::os::raw::c_uint;
+pub use self::_uat_text_mode_t as uat_text_mode_t;
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct _fld_data_t {
+    _unused: [u8; 0],
+}
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct _uat_field_t {
+    pub name: *const ::std::os::raw::c_char,
+    pub title: *const ::std::os::raw::c_char,
+    pub mode: uat_text_mode_t,
+    pub cb: _uat_field_t__bindgen_ty_1,
+    pub cbdata: _uat_field_t__bindgen_ty_2,
+    pub fld_data: *const ::std::os::raw::c_void,
+    pub desc: *const ::std::os::raw::c_char,
+    pub priv_: *mut _fld_data_t,
+}
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct _uat_field_t__bindgen_ty_1 {
+    pub chk: uat_fld_chk_cb_t,
+    pub set: uat_fld_set_cb_t,
+    pub tostr: uat_fld_tostr_cb_t,
+}
+#[allow(clippy::unnecessary_operation, clippy::identity_op)]
+const _: () = {
+    ["Size of _uat_field_t__bindgen_ty_1"]
+        [::std::mem::size_of::<_uat_field_t__bindgen_ty_1>() - 24usize];
+    ["Alignment of _uat_field_t__bindgen_ty_1"]
+        [::std::mem::align_of::<_uat_field_t__bindgen_ty_1>() - 8usize];
+};
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct _uat_field_t__bindgen_ty_2 {
+    pub chk: *const ::std::os::raw::c_void,
+    pub set: *const ::std::os::raw::c_void,
+    pub tostr: *const ::std::os::raw::c_void,
+}
+#[allow(clippy::unnecessary_operation, clippy::identity_op)]
+const _: () = {
+    ["Size of _uat_field_t__bindgen_ty_2"]
+        [::std::mem::size_of::<_uat_field_t__bindgen_ty_2>() - 24usize];
+    ["Alignment of _uat_field_t__bindgen_ty_2"]
+        [::std::mem::align_of::<_uat_field_t__bindgen_ty_2>() - 8usize];
+};
+#[allow(clippy::unnecessary_operation, clippy::identity_op)]
+const _: () = {
+    ["Size of _uat_field_t"][::std::mem::size_of::<_uat_field_t>() - 96usize];
+    ["Alignment of _uat_field_t"][::std::mem::align_of::<_uat_field_t>() - 8usize];
+    ["Offset of field: _uat_field_t::mode"][::std::mem::offset_of!(_uat_field_t, mode) - 16usize];
+    ["Offset of field: _uat_field_t::cb"][::std::mem::offset_of!(_uat_field_t, cb) - 24usize];
+    ["Offset of field: _uat_field_t::cbdata"]
+        [::std::mem::offset_of!(_uat_field_t, cbdata) - 48usize];
+    ["Offset of field: _uat_field_t::fld_data"]
+        [::std::mem::offset_of!(_uat_field_t, fld_data) - 72usize];
+    ["Offset of field: _uat_field_t::priv_"][::std::mem::offset_of!(_uat_field_t, priv_) - 88usize];
+};
+pub type uat_field_t = _uat_field_t;
+pub const UAT_AFFECTS_DISSECTION: u32 = 1;
+pub const UAT_AFFECTS_FIELDS: u32 = 2;
+unsafe extern "C" {
+    pub fn uat_new(
+        name: *const ::std::os::raw::c_char,
+        size: usize,
+        filename: *const ::std::os::raw::c_char,
+        from_profile: bool,
+        data_ptr: *mut ::std::os::raw::c_void,
+        num_items_ptr: *mut ::std::os::raw::c_uint,
+        flags: ::std::os::raw::c_uint,
+        help: *const ::std::os::raw::c_char,
+        copy_cb: uat_copy_cb_t,
+        update_cb: uat_update_cb_t,
+        free_cb: uat_free_cb_t,
+        post_update_cb: uat_post_update_cb_t,
+        reset_cb: uat_reset_cb_t,
+        flds_array: *mut uat_field_t,
+    ) -> *mut uat_t;
+}
+pub type tap_flags_t = ::std::os::raw::c_uint;
+pub const tap_packet_status_TAP_PACKET_DONT_REDRAW: tap_packet_status = 0;
+pub const tap_packet_status_TAP_PACKET_REDRAW: tap_packet_status = 1;
+pub const tap_packet_status_TAP_PACKET_FAILED: tap_packet_status = 2;
+pub type tap_packet_status = ::std::os::raw::c_uint;
+pub type tap_reset_cb =
+    ::std::option::Option<unsafe extern "C" fn(tapdata: *mut ::std::os::raw::c_void)>;
+pub type tap_packet_cb = ::std::option::Option<
+    unsafe extern "C" fn(
+        tapdata: *mut ::std::os::raw::c_void,
+        pinfo: *mut packet_info,
+        edt: *mut epan_dissect_t,
+        data: *const ::std::os::raw::c_void,
+        flags: tap_flags_t,
+    ) -> tap_packet_status,
+>;
+pub type tap_draw_cb =
+    ::std::option::Option<unsafe extern "C" fn(tapdata: *mut ::std::os::raw::c_void)>;
+pub type tap_finish_cb =
+    ::std::option::Option<unsafe extern "C" fn(tapdata: *mut ::std::os::raw::c_void)>;
+pub const TL_REQUIRES_NOTHING: u32 = 0;
+pub const TL_REQUIRES_PROTO_TREE: u32 = 1;
+pub const TL_REQUIRES_COLUMNS: u32 = 2;
+pub const TL_REQUIRES_ERROR_PACKETS: u32 = 4;
+unsafe extern "C" {
+    pub fn register_tap(name: *const ::std::os::raw::c_char) -> ::std::os::raw::c_int;
+}
+unsafe extern "C" {
+    pub fn find_tap_id(name: *const ::std::os::raw::c_char) -> ::std::os::raw::c_int;
+}
+unsafe extern "C" {
+    pub fn tap_queue_packet(
+        tap_id: ::std::os::raw::c_int,
+        pinfo: *mut packet_info,
+        tap_specific_data: *const ::std::os::raw::c_void,
+    );
+}
+unsafe extern "C" {
+    pub fn have_tap_listener(tap_id: ::std::os::raw::c_int) -> bool;
+}
+unsafe extern "C" {
+    pub fn register_tap_listener(
+        tapname: *const ::std::os::raw::c_char,
+        tapdata: *mut ::std::os::raw::c_void,
+        fstring: *const ::std::os::raw::c_char,
+        flags: ::std::os::raw::c_uint,
+        tap_reset: tap_reset_cb,
+        tap_packet: tap_packet_cb,
+        tap_draw: tap_draw_cb,
+        tap_finish: tap_finish_cb,
+    ) -> *mut GString;
+}
+unsafe extern "C" {
+    pub fn remove_tap_listener(tapdata: *mut ::std::os::raw::c_void);
+}
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct tap_credential_t {
+    pub num: ::std::os::raw::c_uint,
+    pub username_num: ::std::os::raw::c_uint,
+    pub password_hf_id: ::std::os::raw::c_uint,
+    pub username: *mut ::std::os::raw::c_char,
+    pub proto: *const ::std::os::raw::c_char,
+    pub info: *mut ::std::os::raw::c_char,
+}
+#[allow(clippy::unnecessary_operation, clippy::identity_op)]
+const _: () = {
+    ["Size of tap_credential_t"][::std::mem::size_of::<tap_credential_t>() - 40usize];
+    ["Alignment of tap_credential_t"][::std::mem::align_of::<tap_credential_t>() - 8usize];
+    ["Offset of field: tap_credential_t::num"]
+        [::std::mem::offset_of!(tap_credential_t, num) - 0usize];
+    ["Offset of field: tap_credential_t::username_num"]
+        [::std::mem::offset_of!(tap_credential_t, username_num) - 4usize];
+    ["Offset of field: tap_credential_t::password_hf_id"]
+        [::std::mem::offset_of!(tap_credential_t, password_hf_id) - 8usize];
+    ["Offset of field: tap_credential_t::username"]
+        [::std::mem::offset_of!(tap_credential_t, username) - 16usize];
+    ["Offset of field: tap_credential_t::proto"]
+        [::std::mem::offset_of!(tap_credential_t, proto) - 24usize];
+    ["Offset of field: tap_credential_t::info"]
+        [::std::mem::offset_of!(tap_credential_t, info) - 32usize];
+};
+pub const register_stat_group_t_REGISTER_PACKET_ANALYZE_GROUP_UNSORTED: register_stat_group_t = 0;
+pub const register_stat_group_t_REGISTER_PACKET_STAT_GROUP_UNSORTED: register_stat_group_t = 1;
+pub const register_stat_group_t_REGISTER_STAT_GROUP_GENERIC: register_stat_group_t = 2;
+pub const register_stat_group_t_REGISTER_STAT_GROUP_CONVERSATION_LIST: register_stat_group_t = 3;
+pub const register_stat_group_t_REGISTER_STAT_GROUP_ENDPOINT_LIST: register_stat_group_t = 4;
+pub const register_stat_group_t_REGISTER_STAT_GROUP_RESPONSE_TIME: register_stat_group_t = 5;
+pub const register_stat_group_t_REGISTER_STAT_GROUP_RSERPOOL: register_stat_group_t = 6;
+pub const register_stat_group_t_REGISTER_STAT_GROUP_TELEPHONY: register_stat_group_t = 7;
+pub const register_stat_group_t_REGISTER_STAT_GROUP_TELEPHONY_ANSI: register_stat_group_t = 8;
+pub const register_stat_group_t_REGISTER_STAT_GROUP_TELEPHONY_GSM: register_stat_group_t = 9;
+pub const register_stat_group_t_REGISTER_STAT_GROUP_TELEPHONY_LTE: register_stat_group_t = 10;
+pub const register_stat_group_t_REGISTER_STAT_GROUP_TELEPHONY_MTP3: register_stat_group_t = 11;
+pub const register_stat_group_t_REGISTER_STAT_GROUP_TELEPHONY_SCTP: register_stat_group_t = 12;
+pub const register_stat_group_t_REGISTER_TOOLS_GROUP_UNSORTED: register_stat_group_t = 13;
+pub const register_stat_group_t_REGISTER_LOG_ANALYZE_GROUP_UNSORTED: register_stat_group_t = 14;
+pub const register_stat_group_t_REGISTER_LOG_STAT_GROUP_UNSORTED: register_stat_group_t = 15;
+pub type register_stat_group_t = ::std::os::raw::c_uint;
+pub const param_type_PARAM_UINT: param_type = 0;
+pub const param_type_PARAM_STRING: param_type = 1;
+pub const param_type_PARAM_ENUM: param_type = 2;
+pub const param_type_PARAM_UUID: param_type = 3;
+pub const param_type_PARAM_FILTER: param_type = 4;
+pub type param_type = ::std::os::raw::c_uint;
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct _tap_param {
+    pub type_: param_type,
+    pub name: *const ::std::os::raw::c_char,
+    pub title: *const ::std::os::raw::c_char,
+    pub enum_vals: *const enum_val_t,
+    pub optional: bool,
+}
+#[allow(clippy::unnecessary_operation, clippy::identity_op)]
+const _: () = {
+    ["Size of _tap_param"][::std::mem::size_of::<_tap_param>() - 40usize];
+    ["Alignment of _tap_param"][::std::mem::align_of::<_tap_param>() - 8usize];
+    ["Offset of field: _tap_param::type_"][::std::mem::offset_of!(_tap_param, type_) - 0usize];
+    ["Offset of field: _tap_param::name"][::std::mem::offset_of!(_tap_param, name) - 8usize];
+    ["Offset of field: _tap_param::title"][::std::mem::offset_of!(_tap_param, title) - 16usize];
+    ["Offset of field: _tap_param::enum_vals"]
+        [::std::mem::offset_of!(_tap_param, enum_vals) - 24usize];
+    ["Offset of field: _tap_param::optional"]
+        [::std::mem::offset_of!(_tap_param, optional) - 32usize];
+};
+pub type tap_param = _tap_param;
+pub type stat_tap_init_cb = ::std::option::Option<
+    unsafe extern "C" fn(
+        opt_arg: *const ::std::os::raw::c_char,
+        userdata: *mut ::std::os::raw::c_void,
+    ),
+>;
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct _stat_tap_ui {
+    pub group: register_stat_group_t,
+    pub title: *const ::std::os::raw::c_char,
+    pub cli_string: *const ::std::os::raw::c_char,
+    pub tap_init_cb: stat_tap_init_cb,
+    pub nparams: usize,
+    pub params: *mut tap_param,
+}
+#[allow(clippy::unnecessary_operation, clippy::identity_op)]
+const _: () = {
+    ["Size of _stat_tap_ui"][::std::mem::size_of::<_stat_tap_ui>() - 48usize];
+    ["Alignment of _stat_tap_ui"][::std::mem::align_of::<_stat_tap_ui>() - 8usize];
+    ["Offset of field: _stat_tap_ui::group"][::std::mem::offset_of!(_stat_tap_ui, group) - 0usize];
+    ["Offset of field: _stat_tap_ui::title"][::std::mem::offset_of!(_stat_tap_ui, title) - 8usize];
+    ["Offset of field: _stat_tap_ui::cli_string"]
+        [::std::mem::offset_of!(_stat_tap_ui, cli_string) - 16usize];
+    ["Offset of field: _stat_tap_ui::tap_init_cb"]
+        [::std::mem::offset_of!(_stat_tap_ui, tap_init_cb) - 24usize];
+    ["Offset of field: _stat_tap_ui::nparams"]
+        [::std::mem::offset_of!(_stat_tap_ui, nparams) - 32usize];
+    ["Offset of field: _stat_tap_ui::params"]
+        [::std::mem::offset_of!(_stat_tap_ui, params) - 40usize];
+};
+pub type stat_tap_ui = _stat_tap_ui;
+unsafe extern "C" {
+    pub fn register_stat_tap_ui(ui: *mut stat_tap_ui, userdata: *mut ::std::os::raw::c_void);
+}
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct _stats_tree {
+    _unused: [u8; 0],
+}
+pub type stats_tree = _stats_tree;
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct _stats_tree_cfg {
+    _unused: [u8; 0],
+}
+pub type stats_tree_cfg = _stats_tree_cfg;
+pub type stat_tree_packet_cb = ::std::option::Option<
+    unsafe extern "C" fn(
+        arg1: *mut stats_tree,
+        arg2: *mut packet_info,
+        arg3: *mut epan_dissect_t,
+        arg4: *const ::std::os::raw::c_void,
+        flags: tap_flags_t,
+    ) -> tap_packet_status,
+>;
+pub type stat_tree_init_cb = ::std::option::Option<unsafe extern "C" fn(arg1: *mut stats_tree)>;
+pub type stat_tree_cleanup_cb = ::std::option::Option<unsafe extern "C" fn(arg1: *mut stats_tree)>;
+pub const _stat_node_datatype_STAT_DT_INT: _stat_node_datatype = 0;
+pub const _stat_node_datatype_STAT_DT_FLOAT: _stat_node_datatype = 1;
+pub type _stat_node_datatype = ::std::os::raw::c_uint;
+pub use self::_stat_node_datatype as stat_node_datatype;
+pub const _manip_node_mode_MN_INCREASE: _manip_node_mode = 0;
+pub const _manip_node_mode_MN_SET: _manip_node_mode = 1;
+pub const _manip_node_mode_MN_AVERAGE: _manip_node_mode = 2;
+pub const _manip_node_mode_MN_AVERAGE_NOTICK: _manip_node_mode = 3;
+pub const _manip_node_mode_MN_SET_FLAGS: _manip_node_mode = 4;
+pub const _manip_node_mode_MN_CLEAR_FLAGS: _manip_node_mode = 5;
+pub type _manip_node_mode = ::std::os::raw::c_uint;
+pub use self::_manip_node_mode as manip_node_mode;
+unsafe extern "C" {
+    pub fn stats_tree_register_plugin(
+        tapname: *const ::std::os::raw::c_char,
+        abbr: *const ::std::os::raw::c_char,
+        path: *const ::std::os::raw::c_char,
+        flags: ::std::os::raw::c_uint,
+        packet: stat_tree_packet_cb,
+        init: stat_tree_init_cb,
+        cleanup: stat_tree_cleanup_cb,
+    ) -> *mut stats_tree_cfg;
+}
+unsafe extern "C" {
+    pub fn stats_tree_set_group(st_config: *mut stats_tree_cfg, stat_group: register_stat_group_t);
+}
+unsafe extern "C" {
+    pub fn stats_tree_create_node(
+        st: *mut stats_tree,
+        name: *const ::std::os::raw::c_char,
+        parent_id: ::std::os::raw::c_int,
+        datatype: stat_node_datatype,
+        with_children: bool,
+    ) -> ::std::os::raw::c_int;
+}
+unsafe extern "C" {
+    pub fn stats_tree_manip_node_int(
+        mode: manip_node_mode,
+        st: *mut stats_tree,
+        name: *const ::std::os::raw::c_char,
+        parent_id: ::std::os::raw::c_int,
+        with_children: bool,
+        value: ::std::os::raw::c_int,
+    ) -> ::std::os::raw::c_int;
+}
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct _timestat_t {
+    pub num: u32,
+    pub min_num: u32,
+    pub max_num: u32,
+    pub min: nstime_t,
+    pub max: nstime_t,
+    pub tot: nstime_t,
+    pub variance: f64,
+}
+#[allow(clippy::unnecessary_operation, clippy::identity_op)]
+const _: () = {
+    ["Size of _timestat_t"][::std::mem::size_of::<_timestat_t>() - 72usize];
+    ["Alignment of _timestat_t"][::std::mem::align_of::<_timestat_t>() - 8usize];
+    ["Offset of field: _timestat_t::num"][::std::mem::offset_of!(_timestat_t, num) - 0usize];
+    ["Offset of field: _timestat_t::min_num"]
+        [::std::mem::offset_of!(_timestat_t, min_num) - 4usize];
+    ["Offset of field: _timestat_t::max_num"]
+        [::std::mem::offset_of!(_timestat_t, max_num) - 8usize];
+    ["Offset of field: _timestat_t::min"][::std::mem::offset_of!(_timestat_t, min) - 16usize];
+    ["Offset of field: _timestat_t::max"][::std::mem::offset_of!(_timestat_t, max) - 32usize];
+    ["Offset of field: _timestat_t::tot"][::std::mem::offset_of!(_timestat_t, tot) - 48usize];
+    ["Offset of field: _timestat_t::variance"]
+        [::std::mem::offset_of!(_timestat_t, variance) - 64usize];
+};
+pub type timestat_t = _timestat_t;
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct _srt_procedure_t {
+    pub proc_index: ::std::os::raw::c_int,
+    pub stats: timestat_t,
+    pub procedure: *mut ::std::os::raw::c_char,
+}
+#[allow(clippy::unnecessary_operation, clippy::identity_op)]
+const _: () = {
+    ["Size of _srt_procedure_t"][::std::mem::size_of::<_srt_procedure_t>() - 88usize];
+    ["Alignment of _srt_procedure_t"][::std::mem::align_of::<_srt_procedure_t>() - 8usize];
+    ["Offset of field: _srt_procedure_t::proc_index"]
+        [::std::mem::offset_of!(_srt_procedure_t, proc_index) - 0usize];
+    ["Offset of field: _srt_procedure_t::stats"]
+        [::std::mem::offset_of!(_srt_procedure_t, stats) - 8usize];
+    ["Offset of field: _srt_procedure_t::procedure"]
+        [::std::mem::offset_of!(_srt_procedure_t, procedure) - 80usize];
+};
+pub type srt_procedure_t = _srt_procedure_t;
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct _srt_stat_table {
+    pub name: *mut ::std::os::raw::c_char,
+    pub short_name: *mut ::std::os::raw::c_char,
+    pub filter_string: *mut ::std::os::raw::c_char,
+    pub num_procs: ::std::os::raw::c_int,
+    pub proc_column_name: *mut ::std::os::raw::c_char,
+    pub procedures: *mut srt_procedure_t,
+    pub table_specific_data: *mut ::std::os::raw::c_void,
+}
+#[allow(clippy::unnecessary_operation, clippy::identity_op)]
+const _: () = {
+    ["Size of _srt_stat_table"][::std::mem::size_of::<_srt_stat_table>() - 56usize];
+    ["Alignment of _srt_stat_table"][::std::mem::align_of::<_srt_stat_table>() - 8usize];
+    ["Offset of field: _srt_stat_table::name"]
+        [::std::mem::offset_of!(_srt_stat_table, name) - 0usize];
+    ["Offset of field: _srt_stat_table::short_name"]
+        [::std::mem::offset_of!(_srt_stat_table, short_name) - 8usize];
+    ["Offset of field: _srt_stat_table::filter_string"]
+        [::std::mem::offset_of!(_srt_stat_table, filter_string) - 16usize];
+    ["Offset of field: _srt_stat_table::num_procs"]
+        [::std::mem::offset_of!(_srt_stat_table, num_procs) - 24usize];
+    ["Offset of field: _srt_stat_table::proc_column_name"]
+        [::std::mem::offset_of!(_srt_stat_table, proc_column_name) - 32usize];
+    ["Offset of field: _srt_stat_table::procedures"]
+        [::std::mem::offset_of!(_srt_stat_table, procedures) - 40usize];
+    ["Offset of field: _srt_stat_table::table_specific_data"]
+        [::std::mem::offset_of!(_srt_stat_table, table_specific_data) - 48usize];
+};
+pub type srt_stat_table = _srt_stat_table;
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct _srt_data_t {
+    pub srt_array: *mut GArray,
+    pub user_data: *mut ::std::os::raw::c_void,
+}
+#[allow(clippy::unnecessary_operation, clippy::identity_op)]
+const _: () = {
+    ["Size of _srt_data_t"][::std::mem::size_of::<_srt_data_t>() - 16usize];
+    ["Alignment of _srt_data_t"][::std::mem::align_of::<_srt_data_t>() - 8usize];
+    ["Offset of field: _srt_data_t::srt_array"]
+        [::std::mem::offset_of!(_srt_data_t, srt_array) - 0usize];
+    ["Offset of field: _srt_data_t::user_data"]
+        [::std::mem::offset_of!(_srt_data_t, user_data) - 8usize];
+};
+pub type srt_data_t = _srt_data_t;
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct register_srt {
+    _unused: [u8; 0],
+}
+pub type register_srt_t = register_srt;
+pub type srt_init_cb =
+    ::std::option::Option<unsafe extern "C" fn(srt: *mut register_srt, srt_array: *mut GArray)>;
+pub type srt_param_handler_cb = ::std::option::Option<
+    unsafe extern "C" fn(
+        srt: *mut register_srt_t,
+        opt_arg: *const ::std::os::raw::c_char,
+        err: *mut *mut ::std::os::raw::c_char,
+    ) -> ::std::os::raw::c_uint,
+>;
+unsafe extern "C" {
+    pub fn register_srt_table(
+        proto_id: ::std::os::raw::c_int,
+        tap_listener: *const ::std::os::raw::c_char,
+        max_tables: ::std::os::raw::c_int,
+        srt_packet_func: tap_packet_cb,
+        init_cb: srt_init_cb,
+        param_cb: srt_param_handler_cb,
+    );
+}
+unsafe extern "C" {
+    pub fn init_srt_table(
+        name: *const ::std::os::raw::c_char,
+        short_name: *const ::std::os::raw::c_char,
+        srt_array: *mut GArray,
+        num_procs: ::std::os::raw::c_int,
+        proc_column_name: *const ::std::os::raw::c_char,
+        filter_string: *const ::std::os::raw::c_char,
+        table_specific_data: *mut ::std::os::raw::c_void,
+    ) -> *mut srt_stat_table;
+}
+unsafe extern "C" {
+    pub fn init_srt_table_row(
+        rst: *mut srt_stat_table,
+        proc_index: ::std::os::raw::c_int,
+        procedure: *const ::std::os::raw::c_char,
+    );
+}
+unsafe extern "C" {
+    pub fn add_srt_table_data(
+        rst: *mut srt_stat_table,
+        index: ::std::os::raw::c_int,
+        req_time: *const nstime_t,
+        pinfo: *mut packet_info,
+    );
+}
+pub const CONV_FILTER_INVALID: &[u8; 8] = b"INVALID\0";
+pub const conv_filter_type_e_CONV_FT_SRC_ADDRESS: conv_filter_type_e = 0;
+pub const conv_filter_type_e_CONV_FT_DST_ADDRESS: conv_filter_type_e = 1;
+pub const conv_filter_type_e_CONV_FT_ANY_ADDRESS: conv_filter_type_e = 2;
+pub const conv_filter_type_e_CONV_FT_SRC_PORT: conv_filter_type_e = 3;
+pub const conv_filter_type_e_CONV_FT_DST_PORT: conv_filter_type_e = 4;
+pub const conv_filter_type_e_CONV_FT_ANY_PORT: conv_filter_type_e = 5;
+pub type conv_filter_type_e = ::std::os::raw::c_uint;
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct _conversation_item_t {
+    _unused: [u8; 0],
+}
+pub type conv_item_t = _conversation_item_t;
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct _endpoint_item_t {
+    _unused: [u8; 0],
+}
+pub type endpoint_item_t = _endpoint_item_t;
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct _ct_dissector_info {
+    pub get_filter_type: ::std::option::Option<
+        unsafe extern "C" fn(
+            item: *mut _conversation_item_t,
+            filter: conv_filter_type_e,
+        ) -> *const ::std::os::raw::c_char,
+    >,
+}
+#[allow(clippy::unnecessary_operation, clippy::identity_op)]
+const _: () = {
+    ["Size of _ct_dissector_info"][::std::mem::size_of::<_ct_dissector_info>() - 8usize];
+    ["Alignment of _ct_dissector_info"][::std::mem::align_of::<_ct_dissector_info>() - 8usize];
+    ["Offset of field: _ct_dissector_info::get_filter_type"]
+        [::std::mem::offset_of!(_ct_dissector_info, get_filter_type) - 0usize];
+};
+pub type ct_dissector_info_t = _ct_dissector_info;
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct _et_dissector_info {
+    pub get_filter_type: ::std::option::Option<
+        unsafe extern "C" fn(
+            item: *mut _endpoint_item_t,
+            filter_type: conv_filter_type_e,
+        ) -> *const ::std::os::raw::c_char,
+    >,
+}
+#[allow(clippy::unnecessary_operation, clippy::identity_op)]
+const _: () = {
+    ["Size of _et_dissector_info"][::std::mem::size_of::<_et_dissector_info>() - 8usize];
+    ["Alignment of _et_dissector_info"][::std::mem::align_of::<_et_dissector_info>() - 8usize];
+    ["Offset of field: _et_dissector_info::get_filter_type"]
+        [::std::mem::offset_of!(_et_dissector_info, get_filter_type) - 0usize];
+};
+pub type et_dissector_info_t = _et_dissector_info;
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct _conversation_hash_t {
+    pub hashtable: *mut GHashTable,
+    pub conv_array: *mut GArray,
+    pub user_data: *mut ::std::os::raw::c_void,
+    pub flags: ::std::os::raw::c_uint,
+}
+#[allow(clippy::unnecessary_operation, clippy::identity_op)]
+const _: () = {
+    ["Size of _conversation_hash_t"][::std::mem::size_of::<_conversation_hash_t>() - 32usize];
+    ["Alignment of _conversation_hash_t"]
+        [::std::mem::align_of::<_conversation_hash_t>() - 8usize];
+    ["Offset of field: _conversation_hash_t::hashtable"]
+        [::std::mem::offset_of!(_conversation_hash_t, hashtable) - 0usize];
+    ["Offset of field: _conversation_hash_t::conv_array"]
+        [::std::mem::offset_of!(_conversation_hash_t, conv_array) - 8usize];
+    ["Offset of field: _conversation_hash_t::user_data"]
+        [::std::mem::offset_of!(_conversation_hash_t, user_data) - 16usize];
+    ["Offset of field: _conversation_hash_t::flags"]
+        [::std::mem::offset_of!(_conversation_hash_t, flags) - 24usize];
+};
+pub type conv_hash_t = _conversation_hash_t;
+unsafe extern "C" {
+    pub fn register_conversation_table(
+        proto_id: ::std::os::raw::c_int,
+        hide_ports: bool,
+        conv_packet_func: tap_packet_cb,
+        endpoint_packet_func: tap_packet_cb,
+    );
+}
+unsafe extern "C" {
+    pub fn add_conversation_table_data(
+        ch: *mut conv_hash_t,
+        src: *const address,
+        dst: *const address,
+        src_port: u32,
+        dst_port: u32,
+        num_frames: ::std::os::raw::c_int,
+        num_bytes: ::std::os::raw::c_int,
+        ts: *mut nstime_t,
+        abs_ts: *mut nstime_t,
+        ct_info: *mut ct_dissector_info_t,
+        ctype: conversation_type,
+    );
+}
+unsafe extern "C" {
+    pub fn add_endpoint_table_data(
+        ch: *mut conv_hash_t,
+        addr: *const address,
+        port: u32,
+        sender: bool,
+        num_frames: ::std::os::raw::c_int,
+        num_bytes: ::std::os::raw::c_int,
+        et_info: *mut et_dissector_info_t,
+        etype: endpoint_type,
+    );
+}
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct _export_object_entry_t {
+    pub pkt_num: u32,
+    pub hostname: *mut ::std::os::raw::c_char,
+    pub content_type: *mut ::std::os::raw::c_char,
+    pub filename: *mut ::std::os::raw::c_char,
+    pub payload_len: usize,
+    pub payload_data: *mut u8,
+}
+#[allow(clippy::unnecessary_operation, clippy::identity_op)]
+const _: () = {
+    ["Size of _export_object_entry_t"][::std::mem::size_of::<_export_object_entry_t>() - 48usize];
+    ["Alignment of _export_object_entry_t"]
+        [::std::mem::align_of::<_export_object_entry_t>() - 8usize];
+    ["Offset of field: _export_object_entry_t::pkt_num"]
+        [::std::mem::offset_of!(_export_object_entry_t, pkt_num) - 0usize];
+    ["Offset of field: _export_object_entry_t::hostname"]
+        [::std::mem::offset_of!(_export_object_entry_t, hostname) - 8usize];
+    ["Offset of field: _export_object_entry_t::content_type"]
+        [::std::mem::offset_of!(_export_object_entry_t, content_type) - 16usize];
+    ["Offset of field: _export_object_entry_t::filename"]
+        [::std::mem::offset_of!(_export_object_entry_t, filename) - 24usize];
+    ["Offset of field: _export_object_entry_t::payload_len"]
+        [::std::mem::offset_of!(_export_object_entry_t, payload_len) - 32usize];
+    ["Offset of field: _export_object_entry_t::payload_data"]
+        [::std::mem::offset_of!(_export_object_entry_t, payload_data) - 40usize];
+};
+pub type export_object_entry_t = _export_object_entry_t;
+pub type export_object_object_list_add_entry_cb = ::std::option::Option<
+    unsafe extern "C" fn(gui_data: *mut ::std::os::raw::c_void, entry: *mut _export_object_entry_t),
+>;
+pub type export_object_object_list_get_entry_cb = ::std::option::Option<
+    unsafe extern "C" fn(
+        gui_data: *mut ::std::os::raw::c_void,
+        row: ::std::os::raw::c_int,
+    ) -> *mut export_object_entry_t,
+>;
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct _export_object_list_t {
+    pub add_entry: export_object_object_list_add_entry_cb,
+    pub get_entry: export_object_object_list_get_entry_cb,
+    pub gui_data: *mut ::std::os::raw::c_void,
+}
+#[allow(clippy::unnecessary_operation, clippy::identity_op)]
+const _: () = {
+    ["Size of _export_object_list_t"][::std::mem::size_of::<_export_object_list_t>() - 24usize];
+    ["Alignment of _export_object_list_t"]
+        [::std::mem::align_of::<_export_object_list_t>() - 8usize];
+    ["Offset of field: _export_object_list_t::add_entry"]
+        [::std::mem::offset_of!(_export_object_list_t, add_entry) - 0usize];
+    ["Offset of field: _export_object_list_t::get_entry"]
+        [::std::mem::offset_of!(_export_object_list_t, get_entry) - 8usize];
+    ["Offset of field: _export_object_list_t::gui_data"]
+        [::std::mem::offset_of!(_export_object_list_t, gui_data) - 16usize];
+};
+pub type export_object_list_t = _export_object_list_t;
+pub type export_object_gui_reset_cb = ::std::option::Option<unsafe extern "C" fn()>;
+unsafe extern "C" {
+    pub fn register_export_object(
+        proto_id: ::std::os::raw::c_int,
+        export_packet_func: tap_packet_cb,
+        reset_cb: export_object_gui_reset_cb,
+    ) -> ::std::os::raw::c_int;
+}
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct _exp_pdu_data_t {
+    pub tlv_buffer_len: ::std::os::raw::c_uint,
+    pub tlv_buffer: *mut u8,
+    pub tvb_captured_length: ::std::os::raw::c_uint,
+    pub tvb_reported_length: ::std::os::raw::c_uint,
+    pub pdu_tvb: *mut tvbuff_t,
+}
+#[allow(clippy::unnecessary_operation, clippy::identity_op)]
+const _: () = {
+    ["Size of _exp_pdu_data_t"][::std::mem::size_of::<_exp_pdu_data_t>() - 32usize];
+    ["Alignment of _exp_pdu_data_t"][::std::mem::align_of::<_exp_pdu_data_t>() - 8usize];
+    ["Offset of field: _exp_pdu_data_t::tlv_buffer_len"]
+        [::std::mem::offset_of!(_exp_pdu_data_t, tlv_buffer_len) - 0usize];
+    ["Offset of field: _exp_pdu_data_t::tlv_buffer"]
+        [::std::mem::offset_of!(_exp_pdu_data_t, tlv_buffer) - 8usize];
+    ["Offset of field: _exp_pdu_data_t::tvb_captured_length"]
+        [::std::mem::offset_of!(_exp_pdu_data_t, tvb_captured_length) - 16usize];
+    ["Offset of field: _exp_pdu_data_t::tvb_reported_length"]
+        [::std::mem::offset_of!(_exp_pdu_data_t, tvb_reported_length) - 20usize];
+    ["Offset of field: _exp_pdu_data_t::pdu_tvb"]
+        [::std::mem::offset_of!(_exp_pdu_data_t, pdu_tvb) - 24usize];
+};
+pub type exp_pdu_data_t = _exp_pdu_data_t;
+unsafe extern "C" {
+    pub fn register_export_pdu_tap(name: *const ::std::os::raw::c_char) -> ::std::os::raw::c_int;
+}
+unsafe extern "C" {
+    pub fn register_export_pdu_tap_with_encap(
+        name: *const ::std::os::raw::c_char,
+        encap: ::std::os::raw::c_int,
+    ) -> ::std::os::raw::c_int;
+}
+unsafe extern "C" {
+    pub fn get_export_pdu_tap_list() -> *mut GSList;
+}
+unsafe extern "C" {
+    pub fn export_pdu_tap_get_encap(name: *const ::std::os::raw::c_char) -> ::std::os::raw::c_int;
+}
+pub type exp_pdu_get_size = ::std::option::Option<
+    unsafe extern "C" fn(
+        pinfo: *mut packet_info,
+        data: *mut ::std::os::raw::c_void,
+    ) -> ::std::os::raw::c_int,
+>;
+pub type exp_pdu_populate_data = ::std::option::Option<
+    unsafe extern "C" fn(
+        pinfo: *mut packet_info,
+        data: *mut ::std::os::raw::c_void,
+        tlv_buffer: *mut u8,
+        tlv_buffer_size: u32,
+    ) -> ::std::os::raw::c_int,
+>;
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct exp_pdu_data_item {
+    pub size_func: exp_pdu_get_size,
+    pub populate_data: exp_pdu_populate_data,
+    pub data: *mut ::std::os::raw::c_void,
+}
+#[allow(clippy::unnecessary_operation, clippy::identity_op)]
+const _: () = {
+    ["Size of exp_pdu_data_item"][::std::mem::size_of::<exp_pdu_data_item>() - 24usize];
+    ["Alignment of exp_pdu_data_item"][::std::mem::align_of::<exp_pdu_data_item>() - 8usize];
+    ["Offset of field: exp_pdu_data_item::size_func"]
+        [::std::mem::offset_of!(exp_pdu_data_item, size_func) - 0usize];
+    ["Offset of field: exp_pdu_data_item::populate_data"]
+        [::std::mem::offset_of!(exp_pdu_data_item, populate_data) - 8usize];
+    ["Offset of field: exp_pdu_data_item::data"]
+        [::std::mem::offset_of!(exp_pdu_data_item, data) - 16usize];
+};
+pub type exp_pdu_data_item_t = exp_pdu_data_item;
+unsafe extern "C" {
+    pub fn export_pdu_create_tags(
+        pinfo: *mut packet_info,
+        proto_name: *const ::std::os::raw::c_char,
+        tag_type: u16,
+        items_list: *mut *const exp_pdu_data_item_t,
+    ) -> *mut exp_pdu_data_t;
+}
+unsafe extern "C" {
+    pub fn export_pdu_create_common_tags(
+        pinfo: *mut packet_info,
+        proto_name: *const ::std::os::raw::c_char,
+        tag_type: u16,
+    ) -> *mut exp_pdu_data_t;
+}
+unsafe extern "C" {
+    pub fn exp_pdu_data_dissector_table_num_value_size(
+        pinfo: *mut packet_info,
+        data: *mut ::std::os::raw::c_void,
+    ) -> ::std::os::raw::c_int;
+}
+unsafe extern "C" {
+    pub fn exp_pdu_data_dissector_table_num_value_populate_data(
+        pinfo: *mut packet_info,
+        data: *mut ::std::os::raw::c_void,
+        tlv_buffer: *mut u8,
+        buffer_size: u32,
+    ) -> ::std::os::raw::c_int;
+}
+unsafe extern "C" {
+    pub static mut exp_pdu_data_src_ip: exp_pdu_data_item_t;
+}
+unsafe extern "C" {
+    pub static mut exp_pdu_data_dst_ip: exp_pdu_data_item_t;
+}
+unsafe extern "C" {
+    pub static mut exp_pdu_data_port_type: exp_pdu_data_item_t;
+}
+unsafe extern "C" {
+    pub static mut exp_pdu_data_src_port: exp_pdu_data_item_t;
+}
+unsafe extern "C" {
+    pub static mut exp_pdu_data_dst_port: exp_pdu_data_item_t;
+}
+unsafe extern "C" {
+    pub static mut exp_pdu_data_orig_frame_num: exp_pdu_data_item_t;
+}
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct dfilter_plugin {
+    pub init: ::std::option::Option<unsafe extern "C" fn()>,
+    pub cleanup: ::std::option::Option<unsafe extern "C" fn()>,
+}
+#[allow(clippy::unnecessary_operation, clippy::identity_op)]
+const _: () = {
+    ["Size of dfilter_plugin"][::std::mem::size_of::<dfilter_plugin>() - 16usize];
+    ["Alignment of dfilter_plugin"][::std::mem::align_of::<dfilter_plugin>() - 8usize];
+    ["Offset of field: dfilter_plugin::init"][::std::mem::offset_of!(dfilter_plugin, init) - 0usize];
+    ["Offset of field: dfilter_plugin::cleanup"]
+        [::std::mem::offset_of!(dfilter_plugin, cleanup) - 8usize];
+};
+unsafe extern "C" {
+    pub fn dfilter_plugins_register(plug: *const dfilter_plugin);
+}
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct _dfwork_t {
+    _unused: [u8; 0],
+}
+pub type dfwork_t = _dfwork_t;
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct stnode {
+    _unused: [u8; 0],
+}
+pub type stnode_t = stnode;
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct df_loc_t {
+    pub col_start: ::std::os::raw::c_long,
+    pub col_len: usize,
+}
+#[allow(clippy::unnecessary_operation, clippy::identity_op)]
+const _: () = {
+    ["Size of df_loc_t"][::std::mem::size_of::<df_loc_t>() - 16usize];
+    ["Alignment of df_loc_t"][::std::mem::align_of::<df_loc_t>() - 8usize];
+    ["Offset of field: df_loc_t::col_start"][::std::mem::offset_of!(df_loc_t, col_start) - 0usize];
+    ["Offset of field: df_loc_t::col_len"][::std::mem::offset_of!(df_loc_t, col_len) - 8usize];
+};
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct df_cell_t {
+    _unused: [u8; 0],
+}
+unsafe extern "C" {
+    pub fn df_cell_append(rp: *mut df_cell_t, fv: *mut fvalue_t);
+}
+unsafe extern "C" {
+    pub fn df_cell_ref(rp: *mut df_cell_t) -> *mut GPtrArray;
+}
+unsafe extern "C" {
+    pub fn df_cell_size(rp: *const df_cell_t) -> usize;
+}
+unsafe extern "C" {
+    pub fn df_cell_is_empty(rp: *const df_cell_t) -> bool;
+}
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct df_cell_iter_t {
+    pub ptr: *mut GPtrArray,
+    pub idx: ::std::os::raw::c_uint,
+}
+#[allow(clippy::unnecessary_operation, clippy::identity_op)]
+const _: () = {
+    ["Size of df_cell_iter_t"][::std::mem::size_of::<df_cell_iter_t>() - 16usize];
+    ["Alignment of df_cell_iter_t"][::std::mem::align_of::<df_cell_iter_t>() - 8usize];
+    ["Offset of field: df_cell_iter_t::ptr"][::std::mem::offset_of!(df_cell_iter_t, ptr) - 0usize];
+    ["Offset of field: df_cell_iter_t::idx"][::std::mem::offset_of!(df_cell_iter_t, idx) - 8usize];
+};
+unsafe extern "C" {
+    pub fn df_cell_iter_init(rp: *mut df_cell_t, iter: *mut df_cell_iter_t);
+}
+unsafe extern "C" {
+    pub fn df_cell_iter_next(iter: *mut df_cell_iter_t) -> *mut fvalue_t;
+}
+pub type DFFuncType = ::std::option::Option<
+    unsafe extern "C" fn(stack: *mut GSList, arg_count: u32, retval: *mut df_cell_t) -> bool,
+>;
+pub type DFSemCheckType = ::std::option::Option<
+    unsafe extern "C" fn(
+        dfw: *mut dfwork_t,
+        func_name: *const ::std::os::raw::c_char,
+        lhs_ftype: ftenum_t,
+        param_list: *mut GSList,
+        func_loc: df_loc_t,
+    ) -> ftenum_t,
+>;
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct df_func_def_t {
+    pub name: *const ::std::os::raw::c_char,
+    pub function: DFFuncType,
+    pub min_nargs: ::std::os::raw::c_uint,
+    pub max_nargs: ::std::os::raw::c_uint,
+    pub return_ftype: ftenum_t,
+    pub semcheck_param_function: DFSemCheckType,
+}
+#[allow(clippy::unnecessary_operation, clippy::identity_op)]
+const _: () = {
+    ["Size of df_func_def_t"][::std::mem::size_of::<df_func_def_t>() - 40usize];
+    ["Alignment of df_func_def_t"][::std::mem::align_of::<df_func_def_t>() - 8usize];
+    ["Offset of field: df_func_def_t::name"][::std::mem::offset_of!(df_func_def_t, name) - 0usize];
+    ["Offset of field: df_func_def_t::function"]
+        [::std::mem::offset_of!(df_func_def_t, function) - 8usize];
+    ["Offset of field: df_func_def_t::min_nargs"]
+        [::std::mem::offset_of!(df_func_def_t, min_nargs) - 16usize];
+    ["Offset of field: df_func_def_t::max_nargs"]
+        [::std::mem::offset_of!(df_func_def_t, max_nargs) - 20usize];
+    ["Offset of field: df_func_def_t::return_ftype"]
+        [::std::mem::offset_of!(df_func_def_t, return_ftype) - 24usize];
+    ["Offset of field: df_func_def_t::semcheck_param_function"]
+        [::std::mem::offset_of!(df_func_def_t, semcheck_param_function) - 32usize];
+};
+unsafe extern "C" {
+    pub fn df_func_register(func: *mut df_func_def_t) -> bool;
+}
+unsafe extern "C" {
+    pub fn df_func_deregister(func: *mut df_func_def_t) -> bool;
+}
+unsafe extern "C" {
+    pub fn df_func_lookup(name: *const ::std::os::raw::c_char) -> *const df_func_def_t;
+}
+unsafe extern "C" {
+    pub fn df_semcheck_param(
+        dfw: *mut dfwork_t,
+        func_name: *const ::std::os::raw::c_char,
+        logical_ftype: ftenum_t,
+        param: *mut stnode_t,
+        func_loc: df_loc_t,
+    ) -> ftenum_t;
+}
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct expert_field_info {
+    pub name: *const ::std::os::raw::c_char,
+    pub group: ::std::os::raw::c_int,
+    pub severity: ::std::os::raw::c_int,
+    pub summary: *const ::std::os::raw::c_char,
+    pub id: ::std::os::raw::c_int,
+    pub protocol: *const ::std::os::raw::c_char,
+    pub orig_severity: ::std::os::raw::c_int,
+    pub hf_info: hf_register_info,
+}
+#[allow(clippy::unnecessary_operation, clippy::identity_op)]
+const _: () = {
+    ["Size of expert_field_info"][::std::mem::size_of::<expert_field_info>() - 128usize];
+    ["Alignment of expert_field_info"][::std::mem::align_of::<expert_field_info>() - 8usize];
+    ["Offset of field: expert_field_info::name"]
+        [::std::mem::offset_of!(expert_field_info, name) - 0usize];
+    ["Offset of field: expert_field_info::group"]
+        [::std::mem::offset_of!(expert_field_info, group) - 8usize];
+    ["Offset of field: expert_field_info::severity"]
+        [::std::mem::offset_of!(expert_field_info, severity) - 12usize];
+    ["Offset of field: expert_field_info::summary"]
+        [::std::mem::offset_of!(expert_field_info, summary) - 16usize];
+    ["Offset of field: expert_field_info::id"]
+        [::std::mem::offset_of!(expert_field_info, id) - 24usize];
+    ["Offset of field: expert_field_info::protocol"]
+        [::std::mem::offset_of!(expert_field_info, protocol) - 32usize];
+    ["Offset of field: expert_field_info::orig_severity"]
+        [::std::mem::offset_of!(expert_field_info, orig_severity) - 40usize];
+    ["Offset of field: expert_field_info::hf_info"]
+        [::std::mem::offset_of!(expert_field_info, hf_info) - 48usize];
+};
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct ei_register_info {
+    pub ids: *mut expert_field,
+    pub eiinfo: expert_field_info,
+}
+#[allow(clippy::unnecessary_operation, clippy::identity_op)]
+const _: () = {
+    ["Size of ei_register_info"][::std::mem::size_of::<ei_register_info>() - 136usize];
+    ["Alignment of ei_register_info"][::std::mem::align_of::<ei_register_info>() - 8usize];
+    ["Offset of field: ei_register_info::ids"]
+        [::std::mem::offset_of!(ei_register_info, ids) - 0usize];
+    ["Offset of field: ei_register_info::eiinfo"]
+        [::std::mem::offset_of!(ei_register_info, eiinfo) - 8usize];
+};
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct expert_module {
+    _unused: [u8; 0],
+}
+pub type expert_module_t = expert_module;
+unsafe extern "C" {
+    pub fn expert_add_info(pinfo: *mut packet_info, pi: *mut proto_item, eiindex: *mut expert_field);
+}
+unsafe extern "C" {
+    pub fn expert_add_info_format(
+        pinfo: *mut packet_info,
+        pi: *mut proto_item,
+        eiindex: *mut expert_field,
+        format: *const ::std::os::raw::c_char,
+        ...
+    );
+}
+unsafe extern "C" {
+    pub fn expert_register_protocol(id: ::std::os::raw::c_int) -> *mut expert_module_t;
+}
+unsafe extern "C" {
+    pub fn expert_register_field_array(
+        module: *mut expert_module_t,
+        ei: *mut ei_register_info,
+        num_records: ::std::os::raw::c_int,
+    );
+}
 #[repr(C)]
 #[derive(Debug, Copy, Clone)]
 pub struct _e_addr_resolve {
@@ -7,5 +7,16 @@
 #include <epan/conversation.h>
 #include <epan/dissectors/packet-tcp.h>
 #include <epan/proto_data.h>
+#include <epan/uat.h>
+#include <epan/expert.h>
+#include <epan/tap.h>
+#include <epan/stat_tap_ui.h>
+#include <epan/stats_tree.h>
+#include <epan/srt_table.h>
+#include <epan/conversation_table.h>
+#include <epan/export_object.h>
+#include <epan/exported_pdu.h>
+#include <epan/dfilter/dfilter-plugin.h>
+#include <epan/dfilter/dfunctions.h>
 
 #endif // EPAN_SYS
@@ -0,0 +1,276 @@
+//! Friendly names of the nodes, shown instead of their hex ZIDs.
+//!
+//! Names come from the `zid_aliases` table of the preferences and, unless disabled, from the
+//! `@/<zid>/<whatami>` admin space replies of the capture, whose JSON carries the `name` of the
+//! node metadata. The table takes precedence over the names learned from the capture.
+
+use std::{cell::RefCell, collections::HashMap, ffi::c_uint, str::FromStr};
+
+use zenoh_buffers::buffer::SplitBuffer;
+use zenoh_protocol::{core::ZenohIdProto, transport::TransportMessage};
+
+use crate::{key_expr, payload::Payload, uat, utils::network_messages};
+
+/// The name and role of a node.
+#[derive(Debug, Clone)]
+pub(crate) struct Alias {
+    pub name: String,
+    /// E.g. `router`, or the role given in the table.
+    pub role: Option<String>,
+}
+
+impl Alias {
+    /// Returns the name followed by the role, e.g. `edge-router-2 (router)`.
+    pub fn label(&self) -> String {
+        match &self.role {
+            Some(role) => format!("{} ({role})", self.name),
+            None => self.name.clone(),
+        }
+    }
+}
+
+// Records of the ZID aliases table: ZID, name and role.
+static mut ZID_ALIASES: *mut uat::StringRecord<3> = std::ptr::null_mut();
+static mut NUM_ZID_ALIASES: c_uint = 0;
+static mut ADMIN_SPACE_ALIASES: bool = true;
+
+thread_local! {
+    /// Aliases of the table, keyed by ZID.
+    static CONFIGURED: RefCell<HashMap<String, Alias>> = RefCell::default();
+    /// Aliases of the admin space replies of the capture, keyed by ZID.
+    static LEARNED: RefCell<HashMap<String, Alias>> = RefCell::default();
+}
+
+/// Register the ZID aliases table and the admin space preference of the zenoh module.
+pub unsafe fn register(module: *mut epan_sys::module_t) {
+    uat::register(
+        module,
+        c"zid_aliases",
+        c"ZID Aliases",
+        c"Names of the nodes shown instead of their ZIDs",
+        c"zenoh_zid_aliases",
+        [
+            uat::Column {
+                name: c"zid",
+                title: c"ZID",
+                desc: c"ZID of the node, in hexadecimal",
+                mode: epan_sys::_uat_text_mode_t_PT_TXTMOD_STRING,
+                check: Some(check_zid),
+            },
+            uat::Column {
+                name: c"name",
+                title: c"Name",
+                desc: c"Name of the node, e.g. lidar-bridge",
+                mode: epan_sys::_uat_text_mode_t_PT_TXTMOD_STRING,
+                check: Some(check_name),
+            },
+            uat::Column {
+                name: c"role",
+                title: c"Role",
+                desc: c"Optional role of the node, e.g. router",
+                mode: epan_sys::_uat_text_mode_t_PT_TXTMOD_STRING,
+                check: None,
+            },
+        ],
+        &raw mut ZID_ALIASES,
+        &raw mut NUM_ZID_ALIASES,
+        Some(update_zid_aliases),
+    );
+    epan_sys::prefs_register_bool_preference(
+        module,
+        c"admin_space_aliases".as_ptr(),
+        c"Name Nodes from the Admin Space".as_ptr(),
+        c"Name the nodes without alias after the metadata of their @/<zid>/<whatami> admin \
+          space replies seen in the capture"
+            .as_ptr(),
+        &raw mut ADMIN_SPACE_ALIASES as _,
+    );
+}
+
+/// Parse a ZID of the table, whose hexadecimal digits may be uppercase.
+fn parse_zid(zid: &str) -> Result<ZenohIdProto, <ZenohIdProto as FromStr>::Err> {
+    ZenohIdProto::from_str(&zid.trim().to_ascii_lowercase())
+}
+
+fn check_zid(zid: &str) -> Result<(), String> {
+    parse_zid(zid).map(|_| ()).map_err(|err| err.to_string())
+}
+
+fn check_name(name: &str) -> Result<(), String> {
+    if name.trim().is_empty() {
+        Err("Empty name".to_string())
+    } else {
+        Ok(())
+    }
+}
+
+unsafe extern "C" fn update_zid_aliases() {
+    let aliases = uat::records(ZID_ALIASES, NUM_ZID_ALIASES)
+        .into_iter()
+        .filter_map(|[zid, name, role]| {
+            let zid = parse_zid(&zid).ok()?.to_string();
+            let alias = Alias {
+                name: name.trim().to_string(),
+                role: (!role.trim().is_empty()).then(|| role.trim().to_string()),
+            };
+            Some((zid, alias))
+        })
+        .collect();
+    CONFIGURED.set(aliases);
+}
+
+/// Clear the names learned from the capture.
+pub(crate) fn init_aliases() {
+    LEARNED.with_borrow_mut(|learned| learned.clear());
+}
+
+/// Returns the alias of a ZID, from the table or else from the capture.
+pub(crate) fn lookup(zid: &str) -> Option<Alias> {
+    CONFIGURED
+        .with_borrow(|configured| configured.get(zid).cloned())
+        .or_else(|| {
+            if unsafe { ADMIN_SPACE_ALIASES } {
+                LEARNED.with_borrow(|learned| learned.get(zid).cloned())
+            } else {
+                None
+            }
+        })
+}
+
+/// Returns the name of a ZID, or the ZID itself if it has no alias.
+pub(crate) fn name(zid: &str) -> String {
+    lookup(zid).map_or_else(|| zid.to_string(), |alias| alias.name)
+}
+
+/// Learn the names of the nodes from the admin space replies of a transport message, including
+/// the network message reassembled from its fragment.
+pub(crate) unsafe fn update_state(pinfo: *mut epan_sys::_packet_info, msg: &TransportMessage) {
+    let msgs = network_messages(pinfo, msg);
+    for payload in msgs
+        .iter()
+        .filter_map(|msg| Payload::from_network_message(msg))
+        .filter(|payload| matches!(payload.kind, "Put" | "Reply"))
+    {
+        let Some((zid, whatami)) = key_expr::resolve(pinfo, payload.wire_expr)
+            .as_deref()
+            .and_then(admin_space_node)
+        else {
+            continue;
+        };
+        let Some(name) = metadata_name(&payload.bytes.contiguous()) else {
+            continue;
+        };
+        LEARNED.with_borrow_mut(|learned| {
+            learned.insert(
+                zid,
+                Alias {
+                    name,
+                    role: Some(whatami),
+                },
+            )
+        });
+    }
+}
+
+/// Returns the ZID and role of an `@/<zid>/<whatami>` admin space key expression.
+fn admin_space_node(key_expr: &str) -> Option<(String, String)> {
+    let mut chunks = key_expr.split('/');
+    if chunks.next()? != "@" {
+        return None;
+    }
+    let zid = ZenohIdProto::from_str(chunks.next()?).ok()?.to_string();
+    let whatami = chunks.next()?;
+    if chunks.next().is_some() || !matches!(whatami, "router" | "peer" | "client") {
+        return None;
+    }
+    Some((zid, whatami.to_string()))
+}
+
+/// Returns the `metadata.name` string of the JSON description of a node, if any.
+fn metadata_name(json: &[u8]) -> Option<String> {
+    let description: serde_json::Value = serde_json::from_slice(json).ok()?;
+    Some(
+        description
+            .get("metadata")?
+            .get("name")?
+            .as_str()?
+            .to_string(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ZID: &str = "1a2b3c";
+
+    #[test]
+    fn parse_admin_space_nodes() {
+        assert_eq!(
+            admin_space_node(&format!("@/{ZID}/router")),
+            Some((ZID.to_string(), "router".to_string()))
+        );
+        assert_eq!(
+            admin_space_node(&format!("@/{ZID}/client")),
+            Some((ZID.to_string(), "client".to_string()))
+        );
+    }
+
+    #[test]
+    fn parse_invalid_admin_space_nodes() {
+        for key_expr in [
+            "",
+            "@",
+            "@/1a2b3c",
+            "@/1a2b3c/robot",
+            "@/1a2b3c/router/linkstate/routers",
+            "@/not-a-zid/router",
+            "demo/1a2b3c/router",
+        ] {
+            assert_eq!(admin_space_node(key_expr), None, "{key_expr}");
+        }
+    }
+
+    #[test]
+    fn parse_metadata_names() {
+        assert_eq!(
+            metadata_name(br#"{"zid": "1a2b3c", "metadata": {"name": "lidar-bridge"}}"#),
+            Some("lidar-bridge".to_string())
+        );
+        assert_eq!(
+            metadata_name(r#"{"metadata": {"name": "café \"2\""}}"#.as_bytes()),
+            Some("café \"2\"".to_string())
+        );
+    }
+
+    #[test]
+    fn parse_invalid_metadata_names() {
+        for json in [
+            &b""[..],
+            b"{",
+            b"\xff",
+            br#"{"metadata": {"name": 42}}"#,
+            br#"{"metadata": null}"#,
+            br#"{"name": "lidar-bridge"}"#,
+            br#"[{"metadata": {"name": "lidar-bridge"}}]"#,
+            br#"{"metadata": {"name": "lidar-bridge"}} trailing"#,
+        ] {
+            assert_eq!(
+                metadata_name(json),
+                None,
+                "{}",
+                String::from_utf8_lossy(json)
+            );
+        }
+    }
+
+    #[test]
+    fn check_table_entries() {
+        assert!(check_zid(ZID).is_ok());
+        assert!(check_zid(" 1A2B3C ").is_ok());
+        assert!(check_zid("xyz").is_err());
+        assert!(check_zid("").is_err());
+        assert!(check_name("lidar-bridge").is_ok());
+        assert!(check_name(" ").is_err());
+    }
+}
@@ -0,0 +1,379 @@
+//! Decoding of the `ext_auth` extension of the handshake and its cleartext credentials.
+//!
+//! The extension is a list of sub-extensions, one per authentication method. With user/password,
+//! the `InitAck` sends a nonce and the `OpenSyn` answers with the username and an HMAC of the
+//! password keyed by that nonce. With public keys, the `InitSyn` and `InitAck` exchange RSA keys
+//! along with a nonce encrypted with the public key of the other side, which the `OpenSyn`
+//! sends back. See `zenoh_transport::unicast::establishment::ext::auth`.
+//!
+//! Usernames sent over plain TCP or UDP are queued on the `credentials` tap, listed in
+//! `Tools > Credentials`.
+
+use std::ffi::{c_int, c_void, CString};
+
+use anyhow::Result;
+use zenoh_buffers::{buffer::SplitBuffer, ZBuf};
+use zenoh_codec::{RCodec, Zenoh080};
+use zenoh_protocol::{
+    common::{iext, ZExtBody, ZExtUnknown},
+    transport::{TransportBody, TransportMessage},
+};
+
+use crate::{
+    expert,
+    header_field::{FieldKind, HeaderFieldMap, Registration},
+    tree::TreeArgs,
+    PROTOCOL_DATA,
+};
+
+pub const FIELD_AUTH: &str = "zenoh.auth";
+pub const FIELD_USRPWD: &str = "zenoh.auth.usrpwd";
+pub const FIELD_USER: &str = "zenoh.auth.usrpwd.user";
+pub const FIELD_NONCE: &str = "zenoh.auth.usrpwd.nonce";
+pub const FIELD_HMAC: &str = "zenoh.auth.usrpwd.hmac";
+pub const FIELD_PUBKEY: &str = "zenoh.auth.pubkey";
+pub const FIELD_MODULUS: &str = "zenoh.auth.pubkey.modulus";
+pub const FIELD_EXPONENT: &str = "zenoh.auth.pubkey.exponent";
+pub const FIELD_ENCRYPTED_NONCE: &str = "zenoh.auth.pubkey.encrypted_nonce";
+
+/// Ids of the authentication sub-extensions.
+const PUBKEY_ID: u8 = 0x1;
+const USRPWD_ID: u8 = 0x2;
+
+/// Protocol shown in `Tools > Credentials`.
+const CREDENTIALS_PROTO: &std::ffi::CStr = c"Zenoh user/password";
+
+static mut CREDENTIALS_TAP_ID: c_int = -1;
+
+/// The authentication header fields, added below handshake messages carrying `ext_auth`.
+pub struct Auth;
+
+impl Registration for Auth {
+    fn generate_hf_map(prefix: &str) -> HeaderFieldMap {
+        HeaderFieldMap::new()
+            .add(prefix.to_string(), "Authentication", FieldKind::Branch)
+            .add(
+                format!("{prefix}.usrpwd"),
+                "User/Password",
+                FieldKind::Branch,
+            )
+            .add(format!("{prefix}.usrpwd.user"), "Username", FieldKind::Text)
+            .add(format!("{prefix}.usrpwd.nonce"), "Nonce", FieldKind::Number)
+            .add(format!("{prefix}.usrpwd.hmac"), "HMAC", FieldKind::Bytes)
+            .add(format!("{prefix}.pubkey"), "Public Key", FieldKind::Branch)
+            .add(
+                format!("{prefix}.pubkey.modulus"),
+                "Modulus",
+                FieldKind::Bytes,
+            )
+            .add(
+                format!("{prefix}.pubkey.exponent"),
+                "Exponent",
+                FieldKind::Number,
+            )
+            .add(
+                format!("{prefix}.pubkey.encrypted_nonce"),
+                "Encrypted Nonce",
+                FieldKind::Bytes,
+            )
+    }
+
+    fn generate_subtree_names(prefix: &str) -> Vec<String> {
+        vec![
+            prefix.to_string(),
+            format!("{prefix}.usrpwd"),
+            format!("{prefix}.pubkey"),
+        ]
+    }
+}
+
+/// The user/password fields of a handshake message. The `InitSyn` and `OpenAck` carry none.
+#[derive(Debug, Default, PartialEq, Eq)]
+struct UsrPwd {
+    nonce: Option<u64>,
+    user: Option<Vec<u8>>,
+    hmac: Option<Vec<u8>>,
+}
+
+/// The public key fields of a handshake message. The `OpenAck` carries none.
+#[derive(Debug, Default, PartialEq, Eq)]
+struct PubKey {
+    /// Big-endian modulus and exponent of the RSA key.
+    key: Option<(Vec<u8>, Vec<u8>)>,
+    encrypted_nonce: Option<Vec<u8>>,
+}
+
+/// The decoded `ext_auth` of a handshake message.
+#[derive(Debug, Default, PartialEq, Eq)]
+struct AuthExt {
+    usrpwd: Option<UsrPwd>,
+    pubkey: Option<PubKey>,
+}
+
+/// Register the `credentials` tap, shared with the other dissectors.
+pub unsafe fn register() {
+    CREDENTIALS_TAP_ID = epan_sys::register_tap(c"credentials".as_ptr());
+}
+
+/// Decode the `ext_auth` of a handshake message, ignoring the sub-extensions that don't decode.
+fn decode(msg: &TransportMessage) -> Option<AuthExt> {
+    let (value, has_key, has_encrypted_nonce): (&ZBuf, bool, bool) = match &msg.body {
+        TransportBody::InitSyn(init_syn) => (&init_syn.ext_auth.as_ref()?.value, true, false),
+        TransportBody::InitAck(init_ack) => (&init_ack.ext_auth.as_ref()?.value, true, true),
+        TransportBody::OpenSyn(open_syn) => (&open_syn.ext_auth.as_ref()?.value, false, true),
+        TransportBody::OpenAck(open_ack) => (&open_ack.ext_auth.as_ref()?.value, false, false),
+        _ => return None,
+    };
+    decode_exts(&value.contiguous(), has_key, has_encrypted_nonce)
+}
+
+/// Decode the sub-extensions of an `ext_auth`, the keys being sent by the `InitSyn` and
+/// `InitAck` and the encrypted nonces by the `InitAck` and `OpenSyn`.
+fn decode_exts(bytes: &[u8], has_key: bool, has_encrypted_nonce: bool) -> Option<AuthExt> {
+    let codec = Zenoh080::new();
+    let exts: Vec<ZExtUnknown> = codec.read(&mut &bytes[..]).ok()?;
+
+    let mut auth = AuthExt::default();
+    for ext in exts {
+        match (iext::mid(ext.id), ext.body) {
+            (USRPWD_ID, ZExtBody::Unit) => auth.usrpwd = Some(UsrPwd::default()),
+            (USRPWD_ID, ZExtBody::Z64(nonce)) => {
+                auth.usrpwd = Some(UsrPwd {
+                    nonce: Some(nonce),
+                    ..Default::default()
+                })
+            }
+            (USRPWD_ID, ZExtBody::ZBuf(zbuf)) => {
+                let bytes = zbuf.contiguous();
+                let mut reader = &bytes[..];
+                let user: Option<Vec<u8>> = codec.read(&mut reader).ok();
+                let hmac: Option<Vec<u8>> = codec.read(&mut reader).ok();
+                auth.usrpwd = Some(UsrPwd {
+                    user,
+                    hmac,
+                    ..Default::default()
+                });
+            }
+            (PUBKEY_ID, ZExtBody::Unit) => auth.pubkey = Some(PubKey::default()),
+            (PUBKEY_ID, ZExtBody::ZBuf(zbuf)) => {
+                let bytes = zbuf.contiguous();
+                let mut reader = &bytes[..];
+                let mut pubkey = PubKey::default();
+                if has_key {
+                    // Both are encoded as little-endian big integers.
+                    let modulus: Option<Vec<u8>> = codec.read(&mut reader).ok();
+                    let exponent: Option<Vec<u8>> = codec.read(&mut reader).ok();
+                    pubkey.key = modulus.zip(exponent).map(|(mut modulus, mut exponent)| {
+                        modulus.reverse();
+                        exponent.reverse();
+                        (modulus, exponent)
+                    });
+                }
+                if has_encrypted_nonce {
+                    pubkey.encrypted_nonce = codec.read(&mut reader).ok();
+                }
+                auth.pubkey = Some(pubkey);
+            }
+            _ => {}
+        }
+    }
+    Some(auth)
+}
+
+/// Returns whether the frame was sent over plain TCP or UDP, rather than TLS, DTLS or QUIC.
+unsafe fn is_cleartext(pinfo: *mut epan_sys::_packet_info) -> bool {
+    let layers = (*pinfo).layers;
+    let over = |proto: &std::ffi::CStr| epan_sys::proto_is_frame_protocol(layers, proto.as_ptr());
+    (over(c"tcp") || over(c"udp")) && !(over(c"tls") || over(c"dtls") || over(c"quic"))
+}
+
+/// Add the authentication subtree below handshake messages carrying `ext_auth`, warning about
+/// usernames sent in cleartext.
+pub(crate) fn add_to_tree(msg: &TransportMessage, args: &TreeArgs) -> Result<()> {
+    let Some(auth) = decode(msg) else {
+        return Ok(());
+    };
+    let args = args.make_subtree(FIELD_AUTH, "Authentication")?;
+
+    if let Some(usrpwd) = &auth.usrpwd {
+        let user = usrpwd
+            .user
+            .as_ref()
+            .map(|user| String::from_utf8_lossy(user).into_owned());
+        let label = match &user {
+            Some(user) => format!("User/Password, Username: {user}"),
+            None => "User/Password".to_string(),
+        };
+        let args = args.make_subtree(FIELD_USRPWD, &label)?;
+        if let Some(nonce) = usrpwd.nonce {
+            args.add_number(FIELD_NONCE, nonce)?;
+        }
+        if let Some(user) = &user {
+            let item = args.add_text(FIELD_USER, user)?;
+            if unsafe { is_cleartext(args.pinfo) } {
+                expert::add(
+                    args.pinfo,
+                    item,
+                    expert::EI_CLEARTEXT_CREDENTIALS,
+                    &format!(
+                        "User/password authentication of {user} over an unencrypted transport, \
+                         its HMAC can be brute-forced offline"
+                    ),
+                )?;
+            }
+        }
+        if let Some(hmac) = &usrpwd.hmac {
+            args.add_bytes(FIELD_HMAC, hmac)?;
+        }
+    }
+
+    if let Some(pubkey) = &auth.pubkey {
+        let label = match &pubkey.key {
+            Some((modulus, _)) => format!("Public Key, RSA-{}", modulus.len() * 8),
+            None => "Public Key".to_string(),
+        };
+        let args = args.make_subtree(FIELD_PUBKEY, &label)?;
+        if let Some((modulus, exponent)) = &pubkey.key {
+            args.add_bytes(FIELD_MODULUS, modulus)?;
+            if exponent.len() <= size_of::<u64>() {
+                let exponent = exponent.iter().fold(0, |acc, &b| (acc << 8) | b as u64);
+                args.add_number(FIELD_EXPONENT, exponent)?;
+            }
+        }
+        if let Some(encrypted_nonce) = &pubkey.encrypted_nonce {
+            args.add_bytes(FIELD_ENCRYPTED_NONCE, encrypted_nonce)?;
+        }
+    }
+    Ok(())
+}
+
+/// Queue the username of a user/password `OpenSyn` sent in cleartext on the `credentials` tap.
+pub(crate) unsafe fn tap_credentials(pinfo: *mut epan_sys::_packet_info, msg: &TransportMessage) {
+    if !epan_sys::have_tap_listener(CREDENTIALS_TAP_ID) || !is_cleartext(pinfo) {
+        return;
+    }
+    let Some(user) = decode(msg)
+        .and_then(|auth| auth.usrpwd?.user)
+        .and_then(|user| CString::new(String::from_utf8_lossy(&user).into_owned()).ok())
+    else {
+        return;
+    };
+
+    let pool = (*pinfo).pool;
+    let credential = epan_sys::wmem_alloc0(pool, size_of::<epan_sys::tap_credential_t>())
+        as *mut epan_sys::tap_credential_t;
+    *credential = epan_sys::tap_credential_t {
+        num: (*pinfo).num,
+        username_num: (*pinfo).num,
+        password_hf_id: PROTOCOL_DATA.with_borrow(|d| d.hf_map[FIELD_HMAC]) as _,
+        username: epan_sys::wmem_strdup(pool, user.as_ptr()),
+        proto: CREDENTIALS_PROTO.as_ptr(),
+        info: epan_sys::wmem_strdup(pool, c"HMAC of the password keyed by a nonce".as_ptr()),
+    };
+    epan_sys::tap_queue_packet(CREDENTIALS_TAP_ID, pinfo, credential as *const c_void);
+}
+
+#[cfg(test)]
+mod tests {
+    use zenoh_buffers::writer::HasWriter;
+    use zenoh_codec::WCodec;
+
+    use super::*;
+
+    fn encode(exts: &[ZExtUnknown]) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        Zenoh080::new().write(&mut bytes.writer(), exts).unwrap();
+        bytes
+    }
+
+    fn zbuf(fields: &[&[u8]]) -> ZExtBody {
+        let mut bytes = Vec::new();
+        let mut writer = bytes.writer();
+        for field in fields {
+            Zenoh080::new().write(&mut writer, *field).unwrap();
+        }
+        ZExtBody::ZBuf(bytes.into())
+    }
+
+    #[test]
+    fn decode_usrpwd() {
+        let init_ack = encode(&[ZExtUnknown::new(USRPWD_ID, false, ZExtBody::Z64(42))]);
+        assert_eq!(
+            decode_exts(&init_ack, true, true).unwrap().usrpwd,
+            Some(UsrPwd {
+                nonce: Some(42),
+                ..Default::default()
+            })
+        );
+        let open_syn = encode(&[ZExtUnknown::new(
+            USRPWD_ID,
+            false,
+            zbuf(&[b"admin", &[0xaa; 32]]),
+        )]);
+        assert_eq!(
+            decode_exts(&open_syn, false, true).unwrap(),
+            AuthExt {
+                usrpwd: Some(UsrPwd {
+                    nonce: None,
+                    user: Some(b"admin".to_vec()),
+                    hmac: Some(vec![0xaa; 32]),
+                }),
+                pubkey: None,
+            }
+        );
+    }
+
+    #[test]
+    fn decode_pubkey() {
+        let init_ack = encode(&[
+            ZExtUnknown::new(
+                PUBKEY_ID,
+                false,
+                zbuf(&[&[0x01, 0x02], &[0x01, 0x00, 0x01], &[7; 4]]),
+            ),
+            ZExtUnknown::new(USRPWD_ID, false, ZExtBody::Unit),
+        ]);
+        assert_eq!(
+            decode_exts(&init_ack, true, true).unwrap(),
+            AuthExt {
+                usrpwd: Some(UsrPwd::default()),
+                pubkey: Some(PubKey {
+                    key: Some((vec![0x02, 0x01], vec![0x01, 0x00, 0x01])),
+                    encrypted_nonce: Some(vec![7; 4]),
+                }),
+            }
+        );
+    }
+
+    #[test]
+    fn decode_malformed() {
+        // Not a list of extensions
+        assert_eq!(decode_exts(&[0xff], true, true), None);
+        // Truncated username and HMAC
+        let open_syn = encode(&[ZExtUnknown::new(
+            USRPWD_ID,
+            false,
+            ZExtBody::ZBuf(vec![0x05, b'a'].into()),
+        )]);
+        assert_eq!(
+            decode_exts(&open_syn, false, true).unwrap().usrpwd,
+            Some(UsrPwd::default())
+        );
+        // Truncated exponent, and unknown methods ignored
+        let init_syn = encode(&[
+            ZExtUnknown::new(
+                PUBKEY_ID,
+                false,
+                ZExtBody::ZBuf(vec![0x01, 0x02, 0x03].into()),
+            ),
+            ZExtUnknown::new(0x7, false, ZExtBody::Z64(1)),
+        ]);
+        assert_eq!(
+            decode_exts(&init_syn, true, false).unwrap(),
+            AuthExt {
+                usrpwd: None,
+                pubkey: Some(PubKey::default()),
+            }
+        );
+    }
+}
@@ -0,0 +1,999 @@
+//! Decoding of CDR-serialized ROS 2 messages from their `.msg`, `.srv` and `.idl` definitions.
+//!
+//! Type names are normalized to `<package>/<msg|srv>/<Name>`, service requests and responses
+//! being `<package>/srv/<Name>_Request` and `<package>/srv/<Name>_Response`.
+
+use std::{
+    collections::{HashMap, HashSet},
+    path::{Path, PathBuf},
+};
+
+use anyhow::{bail, Context, Result};
+
+use crate::ws_log;
+
+/// Nesting limit of decoded messages, guarding against recursive definitions.
+const MAX_DEPTH: usize = 32;
+/// Length of the encapsulation header preceding the CDR data.
+const ENCAPSULATION_LEN: usize = 4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Primitive {
+    Bool,
+    Byte,
+    Char,
+    Int8,
+    UInt8,
+    Int16,
+    UInt16,
+    Int32,
+    UInt32,
+    Int64,
+    UInt64,
+    Float32,
+    Float64,
+    String,
+    WString,
+}
+
+impl Primitive {
+    fn from_msg(name: &str) -> Option<Self> {
+        Some(match name {
+            "bool" => Self::Bool,
+            "byte" => Self::Byte,
+            "char" => Self::Char,
+            "int8" => Self::Int8,
+            "uint8" => Self::UInt8,
+            "int16" => Self::Int16,
+            "uint16" => Self::UInt16,
+            "int32" => Self::Int32,
+            "uint32" => Self::UInt32,
+            "int64" => Self::Int64,
+            "uint64" => Self::UInt64,
+            "float32" => Self::Float32,
+            "float64" => Self::Float64,
+            "string" => Self::String,
+            "wstring" => Self::WString,
+            _ => return None,
+        })
+    }
+
+    fn from_idl(name: &str) -> Option<Self> {
+        Some(match name {
+            "boolean" => Self::Bool,
+            "octet" => Self::Byte,
+            "char" => Self::Char,
+            "wchar" => Self::UInt16,
+            "int8" => Self::Int8,
+            "uint8" => Self::UInt8,
+            "short" | "int16" => Self::Int16,
+            "unsigned short" | "uint16" => Self::UInt16,
+            "long" | "int32" => Self::Int32,
+            "unsigned long" | "uint32" => Self::UInt32,
+            "long long" | "int64" => Self::Int64,
+            "unsigned long long" | "uint64" => Self::UInt64,
+            "float" => Self::Float32,
+            "double" | "long double" => Self::Float64,
+            "string" => Self::String,
+            "wstring" => Self::WString,
+            _ => return None,
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FieldType {
+    Primitive(Primitive),
+    /// A nested message, by normalized type name.
+    Message(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Container {
+    Single,
+    Array(usize),
+    /// Bounded and unbounded sequences are encoded the same way.
+    Sequence,
+}
+
+#[derive(Debug, Clone)]
+pub struct FieldDef {
+    pub name: String,
+    pub ty: FieldType,
+    pub container: Container,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct MessageDef {
+    pub fields: Vec<FieldDef>,
+}
+
+/// A decoded value.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Scalar(String),
+    Bytes(Vec<u8>),
+    Array(Vec<Value>),
+    Message(Vec<(String, Value)>),
+}
+
+/// The message definitions loaded from a directory.
+#[derive(Debug, Default)]
+pub struct TypeRegistry {
+    /// The directory the definitions were loaded from.
+    pub dir: Option<PathBuf>,
+    types: HashMap<String, MessageDef>,
+}
+
+impl TypeRegistry {
+    /// Load every definition found below a directory, e.g. `/opt/ros/jazzy/share`.
+    ///
+    /// The package of a definition is the parent of its `msg` or `srv` folder, as laid out by
+    /// ROS 2 installs. Unparsable files are skipped. Symbolic links are followed, e.g. those of
+    /// `colcon build --symlink-install`, but each directory is only visited once.
+    pub fn load(dir: &Path) -> Self {
+        let mut registry = Self {
+            dir: Some(dir.to_path_buf()),
+            types: HashMap::new(),
+        };
+        let mut visited = HashSet::new();
+        let mut pending = vec![dir.to_path_buf()];
+        while let Some(dir) = pending.pop() {
+            let Ok(canonical) = dir.canonicalize() else {
+                continue;
+            };
+            if !visited.insert(canonical) {
+                continue;
+            }
+            let Ok(entries) = std::fs::read_dir(&dir) else {
+                continue;
+            };
+            for path in entries.flatten().map(|entry| entry.path()) {
+                if path.is_dir() {
+                    pending.push(path);
+                } else if let Err(err) = registry.load_file(&path) {
+                    ws_log::debug!("skipping {}: {err}", path.display());
+                }
+            }
+        }
+        registry
+    }
+
+    pub fn get(&self, name: &str) -> Option<&MessageDef> {
+        self.types.get(name)
+    }
+
+    pub fn len(&self) -> usize {
+        self.types.len()
+    }
+
+    fn load_file(&mut self, path: &Path) -> Result<()> {
+        let extension = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .unwrap_or_default();
+        if !matches!(extension, "msg" | "srv" | "idl") {
+            return Ok(());
+        }
+        let name = path
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .context("invalid file name")?;
+        let kind_dir = path.parent().context("no parent directory")?;
+        let kind = kind_dir
+            .file_name()
+            .and_then(|s| s.to_str())
+            .context("invalid directory name")?;
+        let package = kind_dir
+            .parent()
+            .and_then(|p| p.file_name())
+            .and_then(|s| s.to_str())
+            .context("no package directory")?;
+        let text = std::fs::read_to_string(path)?;
+
+        match extension {
+            "msg" if kind == "msg" => {
+                let def = parse_msg(package, &text)?;
+                self.types.insert(format!("{package}/msg/{name}"), def);
+            }
+            "srv" if kind == "srv" => {
+                let mut lines = text.lines();
+                let request = lines
+                    .by_ref()
+                    .take_while(|line| line.trim() != "---")
+                    .collect::<Vec<_>>()
+                    .join("\n");
+                let response = lines.collect::<Vec<_>>().join("\n");
+                self.types.insert(
+                    format!("{package}/srv/{name}_Request"),
+                    parse_msg(package, &request)?,
+                );
+                self.types.insert(
+                    format!("{package}/srv/{name}_Response"),
+                    parse_msg(package, &response)?,
+                );
+            }
+            "idl" => self.types.extend(parse_idl(&text)?),
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Decode a CDR-serialized message, including its encapsulation header.
+    pub fn decode(&self, type_name: &str, data: &[u8]) -> Result<Value> {
+        if data.len() < ENCAPSULATION_LEN {
+            bail!("missing encapsulation header");
+        }
+        // Representation identifier: CDR_BE = 0x0000, CDR_LE = 0x0001.
+        let little_endian = match data[1] {
+            0 => false,
+            1 => true,
+            id => bail!("unsupported encapsulation 0x{:02x}{id:02x}", data[0]),
+        };
+        let mut reader = CdrReader {
+            data: &data[ENCAPSULATION_LEN..],
+            pos: 0,
+            little_endian,
+        };
+        self.decode_message(type_name, &mut reader, 0)
+    }
+
+    fn decode_message(
+        &self,
+        type_name: &str,
+        reader: &mut CdrReader,
+        depth: usize,
+    ) -> Result<Value> {
+        if depth > MAX_DEPTH {
+            bail!("message nesting exceeds {MAX_DEPTH}");
+        }
+        let def = self
+            .get(type_name)
+            .with_context(|| format!("unknown type {type_name}"))?;
+        let mut fields = Vec::with_capacity(def.fields.len());
+        for field in &def.fields {
+            let value = match field.container {
+                Container::Single => self.decode_field(&field.ty, reader, depth)?,
+                Container::Array(len) => self.decode_array(&field.ty, len, reader, depth)?,
+                Container::Sequence => {
+                    let len = reader.read_u32()? as usize;
+                    self.decode_array(&field.ty, len, reader, depth)?
+                }
+            };
+            fields.push((field.name.clone(), value));
+        }
+        Ok(Value::Message(fields))
+    }
+
+    fn decode_array(
+        &self,
+        ty: &FieldType,
+        len: usize,
+        reader: &mut CdrReader,
+        depth: usize,
+    ) -> Result<Value> {
+        // Every element takes at least one byte, so this bounds corrupted lengths.
+        if len > reader.remaining() {
+            bail!("array of {len} elements exceeds the remaining data");
+        }
+        match ty {
+            FieldType::Primitive(Primitive::Byte | Primitive::UInt8) => {
+                Ok(Value::Bytes(reader.read_bytes(len)?.to_vec()))
+            }
+            _ => (0..len)
+                .map(|_| self.decode_field(ty, reader, depth))
+                .collect::<Result<_>>()
+                .map(Value::Array),
+        }
+    }
+
+    fn decode_field(&self, ty: &FieldType, reader: &mut CdrReader, depth: usize) -> Result<Value> {
+        let primitive = match ty {
+            FieldType::Message(name) => return self.decode_message(name, reader, depth + 1),
+            FieldType::Primitive(primitive) => primitive,
+        };
+        let scalar = match primitive {
+            Primitive::Bool => (reader.read::<1>()?[0] != 0).to_string(),
+            Primitive::Byte | Primitive::UInt8 | Primitive::Char => {
+                reader.read::<1>()?[0].to_string()
+            }
+            Primitive::Int8 => (reader.read::<1>()?[0] as i8).to_string(),
+            Primitive::Int16 => reader
+                .read_num(i16::from_le_bytes, i16::from_be_bytes)?
+                .to_string(),
+            Primitive::UInt16 => reader
+                .read_num(u16::from_le_bytes, u16::from_be_bytes)?
+                .to_string(),
+            Primitive::Int32 => reader
+                .read_num(i32::from_le_bytes, i32::from_be_bytes)?
+                .to_string(),
+            Primitive::UInt32 => reader.read_u32()?.to_string(),
+            Primitive::Int64 => reader
+                .read_num(i64::from_le_bytes, i64::from_be_bytes)?
+                .to_string(),
+            Primitive::UInt64 => reader
+                .read_num(u64::from_le_bytes, u64::from_be_bytes)?
+                .to_string(),
+            Primitive::Float32 => reader
+                .read_num(f32::from_le_bytes, f32::from_be_bytes)?
+                .to_string(),
+            Primitive::Float64 => reader
+                .read_num(f64::from_le_bytes, f64::from_be_bytes)?
+                .to_string(),
+            Primitive::String => {
+                // The length includes the terminating nul.
+                let len = reader.read_u32()? as usize;
+                let bytes = reader.read_bytes(len)?;
+                let bytes = bytes.strip_suffix(&[0]).unwrap_or(bytes);
+                format!("{:?}", String::from_utf8_lossy(bytes))
+            }
+            Primitive::WString => {
+                let len = reader.read_u32()? as usize;
+                if len > reader.remaining() / 4 {
+                    bail!("wstring of {len} characters exceeds the remaining data");
+                }
+                let s = (0..len)
+                    .map(|_| {
+                        reader
+                            .read_u32()
+                            .map(|c| char::from_u32(c).unwrap_or('\u{fffd}'))
+                    })
+                    .collect::<Result<String>>()?;
+                format!("{s:?}")
+            }
+        };
+        Ok(Value::Scalar(scalar))
+    }
+}
+
+struct CdrReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+    little_endian: bool,
+}
+
+impl<'a> CdrReader<'a> {
+    fn remaining(&self) -> usize {
+        self.data.len().saturating_sub(self.pos)
+    }
+
+    fn read_bytes(&mut self, len: usize) -> Result<&'a [u8]> {
+        let Some(bytes) = self.data.get(self.pos..self.pos + len) else {
+            bail!("unexpected end of data at offset {}", self.pos);
+        };
+        self.pos += len;
+        Ok(bytes)
+    }
+
+    /// Read a primitive of `N` bytes, aligned on its size.
+    fn read<const N: usize>(&mut self) -> Result<[u8; N]> {
+        self.pos = self.pos.next_multiple_of(N);
+        Ok(self.read_bytes(N)?.try_into()?)
+    }
+
+    fn read_num<const N: usize, T>(
+        &mut self,
+        from_le: fn([u8; N]) -> T,
+        from_be: fn([u8; N]) -> T,
+    ) -> Result<T> {
+        let bytes = self.read::<N>()?;
+        Ok(if self.little_endian {
+            from_le(bytes)
+        } else {
+            from_be(bytes)
+        })
+    }
+
+    fn read_u32(&mut self) -> Result<u32> {
+        self.read_num(u32::from_le_bytes, u32::from_be_bytes)
+    }
+}
+
+/// Parse a type of a `.msg` field, resolving unqualified message types to `package`.
+fn parse_msg_type(package: &str, spec: &str) -> Result<(FieldType, Container)> {
+    let (base, container) = match spec.split_once('[') {
+        Some((base, rest)) => {
+            let bound = rest.strip_suffix(']').context("unterminated array")?;
+            let container = if bound.is_empty() || bound.starts_with("<=") {
+                Container::Sequence
+            } else {
+                Container::Array(bound.parse()?)
+            };
+            (base, container)
+        }
+        None => (spec, Container::Single),
+    };
+    // Bounded strings, e.g. `string<=10`, are encoded as plain strings.
+    let base = base.split_once("<=").map_or(base, |(base, _)| base);
+    let ty = match Primitive::from_msg(base) {
+        Some(primitive) => FieldType::Primitive(primitive),
+        None => match base.split_once('/') {
+            Some((package, name)) => FieldType::Message(format!("{package}/msg/{name}")),
+            None => FieldType::Message(format!("{package}/msg/{base}")),
+        },
+    };
+    Ok((ty, container))
+}
+
+/// Parse a `.msg` definition, or one half of a `.srv` definition.
+fn parse_msg(package: &str, text: &str) -> Result<MessageDef> {
+    let mut fields = Vec::new();
+    for line in text.lines() {
+        let line = line.split('#').next().unwrap_or_default().trim();
+        let mut tokens = line.split_whitespace();
+        let (Some(spec), Some(name)) = (tokens.next(), tokens.next()) else {
+            continue;
+        };
+        // Constants, e.g. `uint8 FOO=1`, are not serialized.
+        if name.contains('=') || tokens.next().is_some_and(|t| t.starts_with('=')) {
+            continue;
+        }
+        let (ty, container) = parse_msg_type(package, spec)?;
+        fields.push(FieldDef {
+            name: name.to_string(),
+            ty,
+            container,
+        });
+    }
+    // Empty messages are serialized with a placeholder byte, as in their generated IDL.
+    if fields.is_empty() {
+        fields.push(FieldDef {
+            name: "structure_needs_at_least_one_member".to_string(),
+            ty: FieldType::Primitive(Primitive::UInt8),
+            container: Container::Single,
+        });
+    }
+    Ok(MessageDef { fields })
+}
+
+/// Split IDL source into tokens, dropping comments and preprocessor directives.
+fn tokenize_idl(text: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '/' if chars.peek() == Some(&'/') => {
+                chars.by_ref().find(|&c| c == '\n');
+            }
+            '/' if chars.peek() == Some(&'*') => {
+                chars.next();
+                let mut prev = ' ';
+                for c in chars.by_ref() {
+                    if prev == '*' && c == '/' {
+                        break;
+                    }
+                    prev = c;
+                }
+            }
+            '#' => {
+                chars.by_ref().find(|&c| c == '\n');
+            }
+            '"' => {
+                let mut s = String::from('"');
+                let mut escaped = false;
+                for c in chars.by_ref() {
+                    s.push(c);
+                    match c {
+                        '\\' if !escaped => escaped = true,
+                        '"' if !escaped => break,
+                        _ => escaped = false,
+                    }
+                }
+                tokens.push(s);
+            }
+            c if c.is_alphanumeric() || c == '_' => {
+                let mut s = String::from(c);
+                s.extend(std::iter::from_fn(|| {
+                    chars.next_if(|c| c.is_alphanumeric() || *c == '_' || *c == '.')
+                }));
+                // Consume any following scope separators, e.g. `pkg::msg::Type`.
+                while chars.peek() == Some(&':') {
+                    let mut lookahead = chars.clone();
+                    lookahead.next();
+                    if lookahead.next() != Some(':') {
+                        break;
+                    }
+                    chars.next();
+                    chars.next();
+                    s.push_str("::");
+                    s.extend(std::iter::from_fn(|| {
+                        chars.next_if(|c| c.is_alphanumeric() || *c == '_')
+                    }));
+                }
+                tokens.push(s);
+            }
+            c if c.is_whitespace() => {}
+            c => tokens.push(c.to_string()),
+        }
+    }
+    tokens
+}
+
+struct IdlParser {
+    tokens: Vec<String>,
+    pos: usize,
+    scope: Vec<String>,
+    typedefs: HashMap<String, (FieldType, Container)>,
+    types: Vec<(String, MessageDef)>,
+}
+
+impl IdlParser {
+    fn peek(&self) -> Option<&str> {
+        self.tokens.get(self.pos).map(String::as_str)
+    }
+
+    fn next(&mut self) -> Result<String> {
+        let token = self
+            .tokens
+            .get(self.pos)
+            .cloned()
+            .context("unexpected end of IDL")?;
+        self.pos += 1;
+        Ok(token)
+    }
+
+    fn expect(&mut self, expected: &str) -> Result<()> {
+        let token = self.next()?;
+        if token != expected {
+            bail!("expected {expected:?}, found {token:?}");
+        }
+        Ok(())
+    }
+
+    /// Skip tokens up to the end of the current statement.
+    fn skip_statement(&mut self) -> Result<()> {
+        let mut depth = 0;
+        loop {
+            match self.next()?.as_str() {
+                "{" | "(" => depth += 1,
+                "}" | ")" => depth -= 1,
+                ";" if depth == 0 => return Ok(()),
+                _ => {}
+            }
+        }
+    }
+
+    /// Skip an annotation, e.g. `@verbatim (language="comment", text="...")`.
+    fn skip_annotation(&mut self) -> Result<()> {
+        self.expect("@")?;
+        self.next()?;
+        if self.peek() == Some("(") {
+            let mut depth = 0;
+            loop {
+                match self.next()?.as_str() {
+                    "(" => depth += 1,
+                    ")" if depth == 1 => return Ok(()),
+                    ")" => depth -= 1,
+                    _ => {}
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn parse_definitions(&mut self) -> Result<()> {
+        while let Some(token) = self.peek() {
+            match token {
+                "}" => return Ok(()),
+                "@" => self.skip_annotation()?,
+                "module" => {
+                    self.next()?;
+                    let name = self.next()?;
+                    self.expect("{")?;
+                    self.scope.push(name);
+                    self.parse_definitions()?;
+                    self.scope.pop();
+                    self.expect("}")?;
+                    self.expect(";")?;
+                }
+                "struct" => {
+                    self.next()?;
+                    let name = self.next()?;
+                    self.expect("{")?;
+                    let def = self.parse_members()?;
+                    self.expect(";")?;
+                    let mut path = self.scope.clone();
+                    path.push(name);
+                    self.types.push((path.join("/"), def));
+                }
+                "typedef" => {
+                    self.next()?;
+                    let (ty, _) = self.parse_type()?;
+                    let name = self.next()?;
+                    let container = self.parse_array()?.unwrap_or(Container::Single);
+                    self.expect(";")?;
+                    self.typedefs.insert(name, (ty, container));
+                }
+                _ => self.skip_statement()?,
+            }
+        }
+        Ok(())
+    }
+
+    fn parse_members(&mut self) -> Result<MessageDef> {
+        let mut fields = Vec::new();
+        loop {
+            match self.peek() {
+                Some("}") => {
+                    self.next()?;
+                    return Ok(MessageDef { fields });
+                }
+                Some("@") => self.skip_annotation()?,
+                Some(_) => {
+                    let (ty, container) = self.parse_type()?;
+                    let name = self.next()?;
+                    let container = self.parse_array()?.unwrap_or(container);
+                    self.expect(";")?;
+                    fields.push(FieldDef {
+                        name,
+                        ty,
+                        container,
+                    });
+                }
+                None => bail!("unterminated struct"),
+            }
+        }
+    }
+
+    fn parse_array(&mut self) -> Result<Option<Container>> {
+        if self.peek() != Some("[") {
+            return Ok(None);
+        }
+        self.next()?;
+        let len = self.next()?.parse()?;
+        self.expect("]")?;
+        Ok(Some(Container::Array(len)))
+    }
+
+    fn parse_type(&mut self) -> Result<(FieldType, Container)> {
+        let mut name = self.next()?;
+        match name.as_str() {
+            "sequence" => {
+                self.expect("<")?;
+                let (ty, _) = self.parse_type()?;
+                if self.peek() == Some(",") {
+                    self.next()?;
+                    self.next()?;
+                }
+                self.expect(">")?;
+                return Ok((ty, Container::Sequence));
+            }
+            "unsigned" => name = format!("unsigned {}", self.next()?),
+            _ => {}
+        }
+        if matches!(name.as_str(), "long" | "unsigned long")
+            && matches!(self.peek(), Some("long" | "double"))
+        {
+            name = format!("{name} {}", self.next()?);
+        }
+        // Bounded strings, e.g. `string<10>`, are encoded as plain strings.
+        if matches!(name.as_str(), "string" | "wstring") && self.peek() == Some("<") {
+            self.next()?;
+            self.next()?;
+            self.expect(">")?;
+        }
+
+        if let Some(primitive) = Primitive::from_idl(&name) {
+            return Ok((FieldType::Primitive(primitive), Container::Single));
+        }
+        let simple_name = name.rsplit("::").next().unwrap_or(&name);
+        if let Some(typedef) = self.typedefs.get(simple_name) {
+            return Ok(typedef.clone());
+        }
+        Ok((
+            FieldType::Message(name.replace("::", "/")),
+            Container::Single,
+        ))
+    }
+}
+
+/// Parse the structs of an `.idl` definition, e.g. as generated by `rosidl`.
+fn parse_idl(text: &str) -> Result<Vec<(String, MessageDef)>> {
+    let mut parser = IdlParser {
+        tokens: tokenize_idl(text),
+        pos: 0,
+        scope: Vec::new(),
+        typedefs: HashMap::new(),
+        types: Vec::new(),
+    };
+    parser.parse_definitions()?;
+    Ok(parser.types)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scalar(s: &str) -> Value {
+        Value::Scalar(s.to_string())
+    }
+
+    fn registry(types: &[(&str, &str)]) -> TypeRegistry {
+        TypeRegistry {
+            dir: None,
+            types: types
+                .iter()
+                .map(|(name, text)| {
+                    let package = name.split('/').next().unwrap();
+                    (name.to_string(), parse_msg(package, text).unwrap())
+                })
+                .collect(),
+        }
+    }
+
+    fn fields(def: &MessageDef) -> Vec<(&str, &FieldType, Container)> {
+        def.fields
+            .iter()
+            .map(|field| (field.name.as_str(), &field.ty, field.container))
+            .collect()
+    }
+
+    #[test]
+    fn parse_msg_definitions() {
+        let def = parse_msg(
+            "pkg",
+            "# A comment\n\
+             uint8 FOO=1\n\
+             string NAME = \"bar\"\n\
+             int32 x  # trailing comment\n\
+             float64[3] position\n\
+             uint8[] data\n\
+             string<=10[<=5] names\n\
+             Point point\n\
+             geometry_msgs/Pose pose\n",
+        )
+        .unwrap();
+        assert_eq!(
+            fields(&def),
+            [
+                (
+                    "x",
+                    &FieldType::Primitive(Primitive::Int32),
+                    Container::Single
+                ),
+                (
+                    "position",
+                    &FieldType::Primitive(Primitive::Float64),
+                    Container::Array(3)
+                ),
+                (
+                    "data",
+                    &FieldType::Primitive(Primitive::UInt8),
+                    Container::Sequence
+                ),
+                (
+                    "names",
+                    &FieldType::Primitive(Primitive::String),
+                    Container::Sequence
+                ),
+                (
+                    "point",
+                    &FieldType::Message("pkg/msg/Point".to_string()),
+                    Container::Single
+                ),
+                (
+                    "pose",
+                    &FieldType::Message("geometry_msgs/msg/Pose".to_string()),
+                    Container::Single
+                ),
+            ]
+        );
+        // Empty messages have a placeholder byte.
+        let def = parse_msg("pkg", "# Nothing\n").unwrap();
+        assert_eq!(
+            fields(&def),
+            [(
+                "structure_needs_at_least_one_member",
+                &FieldType::Primitive(Primitive::UInt8),
+                Container::Single
+            )]
+        );
+        assert!(parse_msg("pkg", "int32[3 x").is_err());
+        assert!(parse_msg("pkg", "int32[x] x").is_err());
+    }
+
+    #[test]
+    fn parse_idl_definitions() {
+        let types = parse_idl(
+            "// generated from rosidl_adapter\n\
+             #include \"std_msgs/msg/Header.idl\"\n\
+             module pkg {\n\
+               module msg {\n\
+                 typedef double double__9[9];\n\
+                 module Scan_Constants { const uint8 FOO = 1; };\n\
+                 @verbatim (language=\"comment\", text=\"A scan, see \\\"docs\\\".\")\n\
+                 struct Scan {\n\
+                   std_msgs::msg::Header header;\n\
+                   /* covariance */ double__9 covariance;\n\
+                   @default (value=0)\n\
+                   unsigned long long stamp;\n\
+                   sequence<float, 100> ranges;\n\
+                   string<10> frame;\n\
+                   octet raw[4];\n\
+                 };\n\
+               };\n\
+             };\n",
+        )
+        .unwrap();
+        assert_eq!(types.len(), 1);
+        let (name, def) = &types[0];
+        assert_eq!(name, "pkg/msg/Scan");
+        assert_eq!(
+            fields(def),
+            [
+                (
+                    "header",
+                    &FieldType::Message("std_msgs/msg/Header".to_string()),
+                    Container::Single
+                ),
+                (
+                    "covariance",
+                    &FieldType::Primitive(Primitive::Float64),
+                    Container::Array(9)
+                ),
+                (
+                    "stamp",
+                    &FieldType::Primitive(Primitive::UInt64),
+                    Container::Single
+                ),
+                (
+                    "ranges",
+                    &FieldType::Primitive(Primitive::Float32),
+                    Container::Sequence
+                ),
+                (
+                    "frame",
+                    &FieldType::Primitive(Primitive::String),
+                    Container::Single
+                ),
+                (
+                    "raw",
+                    &FieldType::Primitive(Primitive::Byte),
+                    Container::Array(4)
+                ),
+            ]
+        );
+        assert!(parse_idl("module pkg { struct Scan { double x; ").is_err());
+    }
+
+    #[test]
+    fn load_definitions() {
+        let dir = std::env::temp_dir().join(format!("zenoh-dissector-cdr-{}", std::process::id()));
+        for (path, text) in [
+            ("pkg/msg/Point.msg", "float64 x\nfloat64 y\n"),
+            ("pkg/srv/AddTwoInts.srv", "int64 a\nint64 b\n---\nint64 sum\n"),
+            (
+                "pkg/msg/Empty.idl",
+                "module pkg { module msg { struct Empty { uint8 structure_needs_at_least_one_member; }; }; };",
+            ),
+            ("pkg/msg/Broken.msg", "int32[x] x\n"),
+            ("pkg/README.md", "not a definition"),
+        ] {
+            let path = dir.join(path);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, text).unwrap();
+        }
+        let registry = TypeRegistry::load(&dir);
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let mut names = registry
+            .types
+            .keys()
+            .map(String::as_str)
+            .collect::<Vec<_>>();
+        names.sort();
+        assert_eq!(
+            names,
+            [
+                "pkg/msg/Empty",
+                "pkg/msg/Point",
+                "pkg/srv/AddTwoInts_Request",
+                "pkg/srv/AddTwoInts_Response",
+            ]
+        );
+        let response = registry.get("pkg/srv/AddTwoInts_Response").unwrap();
+        assert_eq!(
+            fields(response),
+            [(
+                "sum",
+                &FieldType::Primitive(Primitive::Int64),
+                Container::Single
+            )]
+        );
+    }
+
+    #[test]
+    fn decode_aligned_fields() {
+        let registry = registry(&[
+            ("pkg/msg/Point", "float64 x\nfloat64 y\n"),
+            (
+                "pkg/msg/Sample",
+                "uint8 flag\nuint32 count\nstring name\nint16 small\nPoint point\nuint8[] data\n",
+            ),
+        ]);
+        let data = [
+            &[0x00, 0x01, 0x00, 0x00][..],
+            // flag, padded to the 4 bytes alignment of count
+            &[0x07, 0, 0, 0],
+            &[0x2a, 0, 0, 0],
+            // name, with its terminating nul
+            &[3, 0, 0, 0],
+            b"ab\0",
+            // padded to the 2 bytes alignment of small
+            &[0],
+            &[0xfe, 0xff],
+            // padded to the 8 bytes alignment of point.x
+            &[0, 0, 0, 0, 0, 0],
+            &1.5f64.to_le_bytes(),
+            &(-2f64).to_le_bytes(),
+            &[2, 0, 0, 0, 0xaa, 0xbb],
+        ]
+        .concat();
+        assert_eq!(
+            registry.decode("pkg/msg/Sample", &data).unwrap(),
+            Value::Message(vec![
+                ("flag".to_string(), scalar("7")),
+                ("count".to_string(), scalar("42")),
+                ("name".to_string(), scalar("\"ab\"")),
+                ("small".to_string(), scalar("-2")),
+                (
+                    "point".to_string(),
+                    Value::Message(vec![
+                        ("x".to_string(), scalar("1.5")),
+                        ("y".to_string(), scalar("-2")),
+                    ])
+                ),
+                ("data".to_string(), Value::Bytes(vec![0xaa, 0xbb])),
+            ])
+        );
+    }
+
+    #[test]
+    fn decode_big_endian() {
+        let registry = registry(&[("pkg/msg/Sample", "uint16[2] values\nwstring name\n")]);
+        let data = [
+            &[0x00, 0x00, 0x00, 0x00][..],
+            &[0x12, 0x34, 0x00, 0x01],
+            &[0, 0, 0, 2],
+            &[0, 0, 0, 0x68, 0, 0, 0, 0x69],
+        ]
+        .concat();
+        assert_eq!(
+            registry.decode("pkg/msg/Sample", &data).unwrap(),
+            Value::Message(vec![
+                (
+                    "values".to_string(),
+                    Value::Array(vec![scalar("4660"), scalar("1")])
+                ),
+                ("name".to_string(), scalar("\"hi\"")),
+            ])
+        );
+    }
+
+    #[test]
+    fn decode_malformed() {
+        let registry = registry(&[
+            ("pkg/msg/Sample", "uint32 count\nint32[] values\n"),
+            ("pkg/msg/Names", "wstring name\n"),
+            ("pkg/msg/Loop", "Loop next\n"),
+        ]);
+        let header = [0x00, 0x01, 0x00, 0x00];
+        let decode = |name: &str, data: &[u8]| registry.decode(name, &[&header, data].concat());
+        // Missing and unsupported encapsulation, unknown type, truncated data
+        assert!(registry.decode("pkg/msg/Sample", &[0x00, 0x01]).is_err());
+        assert!(registry
+            .decode(
+                "pkg/msg/Sample",
+                &[0x00, 0x02, 0x00, 0x00, 1, 0, 0, 0, 0, 0, 0, 0]
+            )
+            .is_err());
+        assert!(decode("pkg/msg/Unknown", &[]).is_err());
+        assert!(decode("pkg/msg/Sample", &[1, 0]).is_err());
+        assert!(decode("pkg/msg/Sample", &[1, 0, 0, 0, 2, 0, 0, 0, 1, 0, 0, 0]).is_err());
+        // Lengths exceeding the remaining data
+        assert!(decode("pkg/msg/Sample", &[1, 0, 0, 0, 0xff, 0xff, 0xff, 0xff]).is_err());
+        assert!(decode("pkg/msg/Names", &[0xff, 0xff, 0xff, 0x0f, 0, 0, 0, 0]).is_err());
+        // Recursive definitions
+        assert!(decode("pkg/msg/Loop", &[]).is_err());
+    }
+}
@@ -5,10 +5,19 @@ use std::{
 
 use zenoh_protocol::transport::{TransportBody, TransportMessage};
 
-use crate::{ws_log, PROTOCOL_DATA};
+use crate::{
+    alias, session,
+    tree::{set_generated, set_hidden},
+    utils::lossy_c_string,
+    ws_log, PROTOCOL_DATA,
+};
 
 pub const FIELD_SRCZID: &str = "zenoh.srczid";
 pub const FIELD_DSTZID: &str = "zenoh.dstzid";
+pub const FIELD_SRCNAME: &str = "zenoh.srcname";
+pub const FIELD_DSTNAME: &str = "zenoh.dstname";
+/// Either name, hidden like `ip.addr` to filter on both directions of a conversation.
+pub const FIELD_NAME: &str = "zenoh.name";
 
 #[derive(Debug)]
 #[repr(C)]
@@ -138,9 +147,101 @@ pub(crate) unsafe fn update_state(pinfo: *mut epan_sys::_packet_info, msg: &Tran
     }
 }
 
+/// Record the ZIDs of the sender and receiver of this packet, e.g. restored from the tags of an
+/// exported PDU, unless the handshake of its conversation already did.
+pub(crate) unsafe fn record_zids(
+    pinfo: *mut epan_sys::_packet_info,
+    src_zid: Option<&str>,
+    dst_zid: Option<&str>,
+) {
+    let conv_state = ConversationState::with_pinfo(pinfo);
+    if conv_state.is_null() {
+        return;
+    }
+
+    for (zid, port) in [(src_zid, (*pinfo).srcport), (dst_zid, (*pinfo).destport)] {
+        let Some(zid) = zid.and_then(|zid| CString::new(zid).ok()) else {
+            continue;
+        };
+        let state = &mut *conv_state;
+        let known = (!state.a_zid.is_null() && state.a_port as u32 == port)
+            || (!state.b_zid.is_null() && state.b_port as u32 == port);
+        if known {
+            continue;
+        }
+        let zid = epan_sys::wmem_strdup(epan_sys::wmem_file_scope(), zid.as_ptr());
+        if state.a_zid.is_null() {
+            state.a_zid = zid;
+            state.a_port = port as u16;
+        } else if state.b_zid.is_null() {
+            state.b_zid = zid;
+            state.b_port = port as u16;
+        }
+    }
+}
+
+/// Returns the ZID of the sender of this packet, if the handshake of its conversation was captured.
+pub(crate) unsafe fn source_zid(pinfo: *mut epan_sys::_packet_info) -> Option<String> {
+    let conv_state = ConversationState::with_pinfo(pinfo);
+    if conv_state.is_null() {
+        return None;
+    }
+    let src = (*conv_state).source(pinfo)?;
+    Some(CStr::from_ptr(src).to_string_lossy().into_owned())
+}
+
+/// Returns the ZID of the receiver of this packet, if the handshake of its conversation was
+/// captured.
+pub(crate) unsafe fn destination_zid(pinfo: *mut epan_sys::_packet_info) -> Option<String> {
+    let conv_state = ConversationState::with_pinfo(pinfo);
+    if conv_state.is_null() {
+        return None;
+    }
+    let dst = (*conv_state).destination(pinfo)?;
+    Some(CStr::from_ptr(dst).to_string_lossy().into_owned())
+}
+
+/// Returns the direction of this packet within its conversation, `A→B` if sent by the sender
+/// of the InitSyn and `B→A` otherwise, if the handshake of its conversation was captured.
+pub(crate) unsafe fn direction(pinfo: *mut epan_sys::_packet_info) -> Option<&'static str> {
+    let conv_state = ConversationState::with_pinfo(pinfo);
+    if conv_state.is_null() {
+        return None;
+    }
+    let state = &*conv_state;
+    if !state.a_zid.is_null() && (*pinfo).srcport == state.a_port as u32 {
+        Some("A→B")
+    } else if !state.b_zid.is_null() && (*pinfo).srcport == state.b_port as u32 {
+        Some("B→A")
+    } else {
+        None
+    }
+}
+
+/// Returns the names of the sender and receiver of this packet, e.g. "lidar-bridge → 1a2b3c",
+/// if at least one of them has an alias.
+pub(crate) unsafe fn info_names(pinfo: *mut epan_sys::_packet_info) -> Option<String> {
+    let src = source_zid(pinfo);
+    let dst = destination_zid(pinfo);
+    if !src
+        .iter()
+        .chain(&dst)
+        .any(|zid| alias::lookup(zid).is_some())
+    {
+        return None;
+    }
+    let name = |zid: Option<String>| zid.map_or("?".to_string(), |zid| alias::name(&zid));
+    Some(format!("{} → {}", name(src), name(dst)))
+}
+
 /// Add Source/Destination ZID fields to the protocol subtree and update the
 /// protocol item text to include them (e.g. "Zenoh Protocol, Src ZID: …, Dst ZID: …").
 ///
+/// Nodes with an alias are shown by their name and role instead (e.g. "Zenoh Protocol, Src:
+/// lidar-bridge (peer), …"), along with Source/Destination Name fields.
+///
+/// The index of the session of the frame is added as well.
+///
 /// Called once per frame from `dissect_zenoh_tcp`, after all batches have been processed
 /// so that InitSyn/InitAck in any batch have had a chance to update the conversation state.
 pub(crate) unsafe fn update_tree(
@@ -154,37 +255,64 @@ pub(crate) unsafe fn update_tree(
         return;
     }
 
-    if let Some(src) = (*conv_state).source(pinfo) {
+    let zids = [
+        (
+            (*conv_state).source(pinfo),
+            FIELD_SRCZID,
+            FIELD_SRCNAME,
+            "Src",
+        ),
+        (
+            (*conv_state).destination(pinfo),
+            FIELD_DSTZID,
+            FIELD_DSTNAME,
+            "Dst",
+        ),
+    ];
+    for (zid, zid_field, name_field, direction) in zids {
+        let Some(zid) = zid else {
+            continue;
+        };
         epan_sys::proto_tree_add_string(
             tree,
-            PROTOCOL_DATA.with_borrow(|d| d.hf_map[FIELD_SRCZID]),
+            PROTOCOL_DATA.with_borrow(|d| d.hf_map[zid_field]),
             tvb,
             0,
             0,
-            src,
+            zid,
         );
-        let text = CString::new(format!(
-            ", Src ZID: {}",
-            CStr::from_ptr(src).to_str().unwrap()
-        ))
-        .unwrap();
-        epan_sys::proto_item_append_text(proto_item, text.as_ptr());
-    }
+        let zid = CStr::from_ptr(zid).to_str().unwrap();
 
-    if let Some(dst) = (*conv_state).destination(pinfo) {
-        epan_sys::proto_tree_add_string(
+        // Nodes without alias are named after their ZID, hidden from the tree but filterable,
+        // e.g. from the conversation table.
+        let alias = alias::lookup(zid);
+        let name = lossy_c_string(alias::name(zid));
+        let item = epan_sys::proto_tree_add_string(
             tree,
-            PROTOCOL_DATA.with_borrow(|d| d.hf_map[FIELD_DSTZID]),
+            PROTOCOL_DATA.with_borrow(|d| d.hf_map[name_field]),
             tvb,
             0,
             0,
-            dst,
+            name.as_ptr(),
         );
-        let text = CString::new(format!(
-            ", Dst ZID: {}",
-            CStr::from_ptr(dst).to_str().unwrap()
-        ))
-        .unwrap();
-        epan_sys::proto_item_append_text(proto_item, text.as_ptr());
+        set_generated(item);
+        set_hidden(epan_sys::proto_tree_add_string(
+            tree,
+            PROTOCOL_DATA.with_borrow(|d| d.hf_map[FIELD_NAME]),
+            tvb,
+            0,
+            0,
+            name.as_ptr(),
+        ));
+        let text = match alias {
+            Some(alias) => format!(", {direction}: {}", alias.label()),
+            None => {
+                set_hidden(item);
+                format!(", {direction} ZID: {zid}")
+            }
+        };
+        let text = lossy_c_string(text);
+        epan_sys::proto_item_append_text(proto_item, c"%s".as_ptr(), text.as_ptr());
     }
+    session::add_index(tvb, pinfo, tree);
 }
@@ -0,0 +1,223 @@
+//! Display filter functions matching key expressions with the Zenoh wildcard semantics.
+//!
+//! `zenoh_ke_intersects(a, b)` is true if some key matches both `a` and `b`, and
+//! `zenoh_ke_includes(a, b)` if every key matching `b` also matches `a`, as in
+//! `keyexpr::intersects` and `keyexpr::includes`. Either argument may be a field such as
+//! `zenoh.key_expr` or a string, e.g. `zenoh_ke_includes("robot/*/sensors/**", zenoh.key_expr)`.
+//! Fields occurring several times in a packet match if any of their values does.
+//!
+//! The functions are registered through a display filter plugin, whose initialization runs once
+//! wireshark set up its own functions.
+
+use std::ffi::{c_char, CStr};
+
+use zenoh_protocol::core::key_expr::keyexpr;
+
+static mut FUNCTIONS: [epan_sys::df_func_def_t; 2] = [
+    epan_sys::df_func_def_t {
+        name: c"zenoh_ke_intersects".as_ptr(),
+        function: Some(df_func_intersects),
+        min_nargs: 2,
+        max_nargs: 2,
+        return_ftype: epan_sys::ftenum_FT_BOOLEAN,
+        semcheck_param_function: Some(semcheck_key_exprs),
+    },
+    epan_sys::df_func_def_t {
+        name: c"zenoh_ke_includes".as_ptr(),
+        function: Some(df_func_includes),
+        min_nargs: 2,
+        max_nargs: 2,
+        return_ftype: epan_sys::ftenum_FT_BOOLEAN,
+        semcheck_param_function: Some(semcheck_key_exprs),
+    },
+];
+
+static PLUGIN: epan_sys::dfilter_plugin = epan_sys::dfilter_plugin {
+    init: Some(init_functions),
+    cleanup: Some(cleanup_functions),
+};
+
+/// Register the display filter plugin of the key expression functions.
+///
+/// # Safety
+///
+/// Must be called while registering the plugin, before wireshark initializes display filters.
+pub unsafe fn register() {
+    epan_sys::dfilter_plugins_register(&PLUGIN);
+}
+
+unsafe extern "C" fn init_functions() {
+    #[allow(static_mut_refs)] // Wireshark keeps references to the function definitions
+    for function in FUNCTIONS.iter_mut() {
+        epan_sys::df_func_register(function);
+    }
+}
+
+unsafe extern "C" fn cleanup_functions() {
+    #[allow(static_mut_refs)] // Wireshark keeps references to the function definitions
+    for function in FUNCTIONS.iter_mut() {
+        epan_sys::df_func_deregister(function);
+    }
+}
+
+/// Both arguments are strings, either string fields or literals.
+unsafe extern "C" fn semcheck_key_exprs(
+    dfw: *mut epan_sys::dfwork_t,
+    func_name: *const c_char,
+    _lhs_ftype: epan_sys::ftenum_t,
+    param_list: *mut epan_sys::GSList,
+    func_loc: epan_sys::df_loc_t,
+) -> epan_sys::ftenum_t {
+    let mut param = param_list;
+    while !param.is_null() {
+        // Raises a display filter error if the parameter isn't a string.
+        epan_sys::df_semcheck_param(
+            dfw,
+            func_name,
+            epan_sys::ftenum_FT_STRING,
+            (*param).data as *mut epan_sys::stnode_t,
+            func_loc,
+        );
+        param = (*param).next;
+    }
+    epan_sys::ftenum_FT_BOOLEAN
+}
+
+/// Returns the string values of a function argument.
+unsafe fn strings<'a>(cell: *mut epan_sys::df_cell_t) -> Vec<&'a str> {
+    let mut strings = Vec::new();
+    if cell.is_null() {
+        return strings;
+    }
+    let mut iter = std::mem::zeroed();
+    epan_sys::df_cell_iter_init(cell, &mut iter);
+    loop {
+        let fv = epan_sys::df_cell_iter_next(&mut iter);
+        if fv.is_null() {
+            break;
+        }
+        let value = epan_sys::fvalue_get_string(fv);
+        if value.is_null() {
+            continue;
+        }
+        if let Ok(value) = CStr::from_ptr(value).to_str() {
+            strings.push(value);
+        }
+    }
+    strings
+}
+
+/// Returns whether a pair of values of the two arguments satisfies a predicate, ignoring the
+/// values that aren't valid key expressions, or `None` if either argument has no valid one.
+fn any_match(
+    lhs: &[&str],
+    rhs: &[&str],
+    predicate: fn(&keyexpr, &keyexpr) -> bool,
+) -> Option<bool> {
+    fn key_exprs<'a>(values: &[&'a str]) -> Vec<&'a keyexpr> {
+        values
+            .iter()
+            .filter_map(|value| keyexpr::new(*value).ok())
+            .collect()
+    }
+    let (lhs, rhs) = (key_exprs(lhs), key_exprs(rhs));
+    if lhs.is_empty() || rhs.is_empty() {
+        return None;
+    }
+    Some(
+        lhs.iter()
+            .any(|lhs| rhs.iter().any(|rhs| predicate(lhs, rhs))),
+    )
+}
+
+/// Evaluate a predicate on every pair of values of the two arguments.
+///
+/// The arguments are pushed on the stack in order, so the last one comes first.
+unsafe fn df_func_key_exprs(
+    stack: *mut epan_sys::GSList,
+    arg_count: u32,
+    retval: *mut epan_sys::df_cell_t,
+    predicate: fn(&keyexpr, &keyexpr) -> bool,
+) -> bool {
+    if arg_count != 2 || stack.is_null() || (*stack).next.is_null() {
+        return false;
+    }
+    let rhs = strings((*stack).data as *mut epan_sys::df_cell_t);
+    let lhs = strings((*(*stack).next).data as *mut epan_sys::df_cell_t);
+    let Some(matches) = any_match(&lhs, &rhs, predicate) else {
+        return false;
+    };
+    let fv = epan_sys::fvalue_new(epan_sys::ftenum_FT_BOOLEAN);
+    epan_sys::fvalue_set_uinteger64(fv, matches as u64);
+    epan_sys::df_cell_append(retval, fv);
+    true
+}
+
+unsafe extern "C" fn df_func_intersects(
+    stack: *mut epan_sys::GSList,
+    arg_count: u32,
+    retval: *mut epan_sys::df_cell_t,
+) -> bool {
+    df_func_key_exprs(stack, arg_count, retval, intersects)
+}
+
+unsafe extern "C" fn df_func_includes(
+    stack: *mut epan_sys::GSList,
+    arg_count: u32,
+    retval: *mut epan_sys::df_cell_t,
+) -> bool {
+    df_func_key_exprs(stack, arg_count, retval, includes)
+}
+
+fn intersects(lhs: &keyexpr, rhs: &keyexpr) -> bool {
+    lhs.intersects(rhs)
+}
+
+fn includes(lhs: &keyexpr, rhs: &keyexpr) -> bool {
+    lhs.includes(rhs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn intersecting_key_exprs() {
+        assert_eq!(
+            any_match(&["robot/*/sensors"], &["robot/1/sensors"], intersects),
+            Some(true)
+        );
+        assert_eq!(
+            any_match(&["robot/*/sensors"], &["robot/**"], intersects),
+            Some(true)
+        );
+        assert_eq!(
+            any_match(&["robot/1"], &["robot/2", "robot/3"], intersects),
+            Some(false)
+        );
+        // Any value of a field occurring several times may match.
+        assert_eq!(any_match(&["a/b", "c/d"], &["c/*"], intersects), Some(true));
+    }
+
+    #[test]
+    fn including_key_exprs() {
+        assert_eq!(
+            any_match(&["robot/**"], &["robot/1/sensors"], includes),
+            Some(true)
+        );
+        assert_eq!(
+            any_match(&["robot/1/sensors"], &["robot/**"], includes),
+            Some(false)
+        );
+        assert_eq!(any_match(&["a/*"], &["a/b/c"], includes), Some(false));
+    }
+
+    #[test]
+    fn invalid_key_exprs() {
+        // Invalid values are ignored, and arguments without a valid one don't match.
+        assert_eq!(any_match(&["a//b"], &["a/b"], intersects), None);
+        assert_eq!(any_match(&["a/b"], &["/a/b", ""], includes), None);
+        assert_eq!(any_match(&["a/**"], &[], includes), None);
+        assert_eq!(any_match(&["a/b/", "a/*"], &["a/b"], includes), Some(true));
+    }
+}
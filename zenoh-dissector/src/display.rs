@@ -0,0 +1,531 @@
+//! Human-readable rendering of the zenoh-protocol types shown in the protocol tree.
+//!
+//! Every plain field listed in an `impl_for_struct!` invocation is rendered through
+//! [`FieldDisplay`] instead of its `Debug` representation.
+
+use std::{num::NonZeroU32, time::Duration};
+
+use anyhow::Result;
+
+use zenoh_buffers::{buffer::SplitBuffer, ZBuf, ZSlice};
+use zenoh_protocol::{
+    common::{ZExtBody, ZExtUnit, ZExtUnknown, ZExtZ64, ZExtZBuf},
+    core::{EntityGlobalIdProto, Field, Resolution, Timestamp, WhatAmI, WireExpr, ZenohIdProto},
+    network::{
+        declare::{common::ext::WireExprType, queryable::ext::QueryableInfoType},
+        ext::{EntityGlobalIdType, NodeIdType, QoSType, TimestampType},
+        interest::{InterestMode, InterestOptions},
+        request::ext::QueryTarget,
+        Mapping,
+    },
+    transport::{ext::PatchType, PrioritySn},
+    zenoh::{
+        ext::{AttachmentType, ShmType, SourceInfoType, ValueType},
+        ConsolidationMode, PushBody,
+    },
+};
+
+use crate::{
+    ext::{self, ShmBufInfo},
+    key_expr,
+    qos::QoSBits,
+    timestamp,
+    tree::TreeArgs,
+};
+
+/// Max number of payload bytes rendered in a preview.
+const PREVIEW_LIMIT: usize = 16;
+
+/// Mime names of the encoding ids predefined by Zenoh.
+///
+/// See `zenoh::bytes::Encoding`.
+const ENCODING_NAMES: &[&str] = &[
+    "zenoh/bytes",
+    "zenoh/string",
+    "zenoh/serialized",
+    "application/octet-stream",
+    "text/plain",
+    "application/json",
+    "text/json",
+    "application/cdr",
+    "application/cbor",
+    "application/yaml",
+    "text/yaml",
+    "text/json5",
+    "application/python-serialized-object",
+    "application/protobuf",
+    "application/java-serialized-object",
+    "application/openmetrics-text",
+    "image/png",
+    "image/jpeg",
+    "image/gif",
+    "image/bmp",
+    "image/webp",
+    "application/xml",
+    "application/x-www-form-urlencoded",
+    "text/html",
+    "text/xml",
+    "text/css",
+    "text/javascript",
+    "text/markdown",
+    "text/csv",
+    "application/sql",
+    "application/coap-payload",
+    "application/json-patch+json",
+    "application/json-seq",
+    "application/jsonpath",
+    "application/jwt",
+    "application/mp4",
+    "application/soap+xml",
+    "application/yang",
+    "audio/aac",
+    "audio/flac",
+    "audio/mp4",
+    "audio/ogg",
+    "audio/vorbis",
+    "video/h261",
+    "video/h263",
+    "video/h264",
+    "video/h265",
+    "video/h266",
+    "video/mp4",
+    "video/ogg",
+    "video/raw",
+    "video/vp8",
+    "video/vp9",
+];
+
+/// Returns the mime name of a predefined encoding id.
+pub(crate) fn encoding_name(id: u16) -> Option<&'static str> {
+    ENCODING_NAMES.get(id as usize).copied()
+}
+
+/// Renders the length of a byte buffer followed by a short preview of its content.
+///
+/// Printable UTF-8 content is shown as a quoted string, anything else as hex bytes.
+pub(crate) fn bytes_preview(bytes: &[u8]) -> String {
+    if bytes.is_empty() {
+        return "0 bytes".to_string();
+    }
+
+    let head = &bytes[..bytes.len().min(PREVIEW_LIMIT)];
+    let ellipsis = if bytes.len() > PREVIEW_LIMIT {
+        "..."
+    } else {
+        ""
+    };
+    let preview = match std::str::from_utf8(head) {
+        Ok(s) if s.chars().all(|c| !c.is_control() || c.is_whitespace()) => {
+            format!("{:?}{ellipsis}", s)
+        }
+        _ => {
+            head.iter()
+                .map(|b| format!("{b:02x}"))
+                .collect::<Vec<_>>()
+                .join(" ")
+                + ellipsis
+        }
+    };
+    format!("{} bytes: {preview}", bytes.len())
+}
+
+/// Concise, human-readable text of a protocol field.
+pub trait FieldDisplay {
+    fn field_display(&self) -> String;
+
+    /// Add decoded details below the text item of the field, if the type has any.
+    fn add_details(&self, _item: *mut epan_sys::proto_item, _args: &TreeArgs) -> Result<()> {
+        Ok(())
+    }
+}
+
+macro_rules! impl_with_to_string {
+    ($($ty:ty),* $(,)?) => {
+        $(
+            impl FieldDisplay for $ty {
+                fn field_display(&self) -> String {
+                    self.to_string()
+                }
+            }
+        )*
+    };
+}
+
+impl_with_to_string!(u8, u16, u32, u64, bool, String, NonZeroU32, ZenohIdProto);
+
+impl<T: FieldDisplay> FieldDisplay for Option<T> {
+    fn field_display(&self) -> String {
+        match self {
+            Some(inner) => inner.field_display(),
+            None => "None".to_string(),
+        }
+    }
+
+    fn add_details(&self, item: *mut epan_sys::proto_item, args: &TreeArgs) -> Result<()> {
+        match self {
+            Some(inner) => inner.add_details(item, args),
+            None => Ok(()),
+        }
+    }
+}
+
+impl<T: FieldDisplay> FieldDisplay for [T] {
+    fn field_display(&self) -> String {
+        format!(
+            "[{}]",
+            self.iter()
+                .map(FieldDisplay::field_display)
+                .collect::<Vec<_>>()
+                .join(", ")
+        )
+    }
+
+    fn add_details(&self, item: *mut epan_sys::proto_item, args: &TreeArgs) -> Result<()> {
+        self.iter()
+            .try_for_each(|elem| elem.add_details(item, args))
+    }
+}
+
+impl<T: FieldDisplay, const N: usize> FieldDisplay for [T; N] {
+    fn field_display(&self) -> String {
+        self.as_slice().field_display()
+    }
+}
+
+impl<T: FieldDisplay> FieldDisplay for Vec<T> {
+    fn field_display(&self) -> String {
+        self.as_slice().field_display()
+    }
+
+    fn add_details(&self, item: *mut epan_sys::proto_item, args: &TreeArgs) -> Result<()> {
+        self.as_slice().add_details(item, args)
+    }
+}
+
+impl<T: FieldDisplay + ?Sized> FieldDisplay for Box<T> {
+    fn field_display(&self) -> String {
+        self.as_ref().field_display()
+    }
+
+    fn add_details(&self, item: *mut epan_sys::proto_item, args: &TreeArgs) -> Result<()> {
+        self.as_ref().add_details(item, args)
+    }
+}
+
+impl FieldDisplay for Duration {
+    fn field_display(&self) -> String {
+        format!("{} ms", self.as_millis())
+    }
+}
+
+impl FieldDisplay for ZSlice {
+    fn field_display(&self) -> String {
+        bytes_preview(self)
+    }
+}
+
+impl FieldDisplay for ZBuf {
+    fn field_display(&self) -> String {
+        bytes_preview(&self.contiguous())
+    }
+
+    fn add_details(&self, item: *mut epan_sys::proto_item, args: &TreeArgs) -> Result<()> {
+        for info in self.zslices().filter_map(ShmBufInfo::from_zslice) {
+            info?.add_to_tree(item, args)?;
+        }
+        Ok(())
+    }
+}
+
+impl FieldDisplay for WhatAmI {
+    fn field_display(&self) -> String {
+        self.to_str().to_string()
+    }
+}
+
+impl FieldDisplay for Resolution {
+    fn field_display(&self) -> String {
+        format!(
+            "frame_sn={}, request_id={}",
+            self.get(Field::FrameSN),
+            self.get(Field::RequestID)
+        )
+    }
+}
+
+impl FieldDisplay for zenoh_protocol::core::Reliability {
+    fn field_display(&self) -> String {
+        format!("{self:?}")
+    }
+}
+
+impl FieldDisplay for zenoh_protocol::core::Encoding {
+    fn field_display(&self) -> String {
+        let schema = self
+            .schema
+            .as_ref()
+            .map(|s| String::from_utf8_lossy(s).into_owned());
+        match (self.id, encoding_name(self.id), schema) {
+            // Custom encodings are `zenoh/bytes` with their full mime name as schema.
+            (0, _, Some(schema)) => schema,
+            (_, Some(name), None) => name.to_string(),
+            (_, Some(name), Some(schema)) => format!("{name};{schema}"),
+            (id, None, None) => format!("unknown({id})"),
+            (id, None, Some(schema)) => format!("unknown({id});{schema}"),
+        }
+    }
+}
+
+impl FieldDisplay for WireExpr<'_> {
+    fn field_display(&self) -> String {
+        let mut s = match (self.scope, self.suffix.is_empty()) {
+            (0, _) => format!("{:?}", self.suffix),
+            (scope, true) => format!("scope={scope}"),
+            (scope, false) => format!("scope={scope} + {:?}", self.suffix),
+        };
+        if self.scope != 0 && self.mapping == Mapping::Sender {
+            s += " (sender mapping)";
+        }
+        s
+    }
+
+    fn add_details(&self, item: *mut epan_sys::proto_item, args: &TreeArgs) -> Result<()> {
+        key_expr::add_to_tree(self, item, args)
+    }
+}
+
+impl FieldDisplay for Timestamp {
+    fn field_display(&self) -> String {
+        // The alternate format renders the time as RFC3339, followed by the HLC id.
+        format!("{self:#}")
+    }
+
+    fn add_details(&self, item: *mut epan_sys::proto_item, args: &TreeArgs) -> Result<()> {
+        timestamp::add_to_tree(self, item, args)
+    }
+}
+
+impl FieldDisplay for EntityGlobalIdProto {
+    fn field_display(&self) -> String {
+        format!("zid={}, eid={}", self.zid, self.eid)
+    }
+}
+
+impl<const ID: u8> FieldDisplay for ZExtUnit<ID> {
+    fn field_display(&self) -> String {
+        "Enabled".to_string()
+    }
+}
+
+impl<const ID: u8> FieldDisplay for ZExtZ64<ID> {
+    fn field_display(&self) -> String {
+        self.value.to_string()
+    }
+}
+
+impl<const ID: u8> FieldDisplay for ZExtZBuf<ID> {
+    fn field_display(&self) -> String {
+        self.value.field_display()
+    }
+}
+
+impl FieldDisplay for ZExtBody {
+    fn field_display(&self) -> String {
+        match self {
+            ZExtBody::Unit => "Unit".to_string(),
+            ZExtBody::Z64(value) => value.to_string(),
+            ZExtBody::ZBuf(zbuf) => zbuf.field_display(),
+        }
+    }
+}
+
+impl FieldDisplay for ZExtUnknown {
+    fn field_display(&self) -> String {
+        let mandatory = if self.is_mandatory() { " (M)" } else { "" };
+        format!(
+            "id={:#04x}{mandatory}: {}",
+            self.id & 0x0f,
+            self.body.field_display()
+        )
+    }
+
+    fn add_details(&self, item: *mut epan_sys::proto_item, args: &TreeArgs) -> Result<()> {
+        ext::add_unknown(self, item, args)
+    }
+}
+
+impl<const ID: u8> FieldDisplay for PatchType<ID> {
+    fn field_display(&self) -> String {
+        self.raw().to_string()
+    }
+}
+
+impl<const ID: u8> FieldDisplay for zenoh_protocol::transport::ext::QoSType<ID> {
+    fn field_display(&self) -> String {
+        QoSBits::transport(ZExtZ64::<ID>::from(*self).value)
+            .priority_name()
+            .to_string()
+    }
+
+    fn add_details(&self, item: *mut epan_sys::proto_item, args: &TreeArgs) -> Result<()> {
+        QoSBits::transport(ZExtZ64::<ID>::from(*self).value).add_to_tree(item, args)
+    }
+}
+
+impl FieldDisplay for PrioritySn {
+    fn field_display(&self) -> String {
+        format!(
+            "reliable={}, best_effort={}",
+            self.reliable, self.best_effort
+        )
+    }
+}
+
+impl<const ID: u8> FieldDisplay for QoSType<ID> {
+    fn field_display(&self) -> String {
+        let qos = QoSBits::network(ZExtZ64::<ID>::from(*self).value);
+        format!(
+            "{}, {}{}",
+            qos.priority_name(),
+            qos.congestion_control_name(),
+            if qos.express == Some(true) {
+                ", express"
+            } else {
+                ""
+            }
+        )
+    }
+
+    fn add_details(&self, item: *mut epan_sys::proto_item, args: &TreeArgs) -> Result<()> {
+        QoSBits::network(ZExtZ64::<ID>::from(*self).value).add_to_tree(item, args)
+    }
+}
+
+impl<const ID: u8> FieldDisplay for TimestampType<ID> {
+    fn field_display(&self) -> String {
+        self.timestamp.field_display()
+    }
+
+    fn add_details(&self, item: *mut epan_sys::proto_item, args: &TreeArgs) -> Result<()> {
+        self.timestamp.add_details(item, args)
+    }
+}
+
+impl<const ID: u8> FieldDisplay for NodeIdType<ID> {
+    fn field_display(&self) -> String {
+        self.node_id.to_string()
+    }
+}
+
+impl<const ID: u8> FieldDisplay for EntityGlobalIdType<ID> {
+    fn field_display(&self) -> String {
+        format!("zid={}, eid={}", self.zid, self.eid)
+    }
+}
+
+impl FieldDisplay for QueryTarget {
+    fn field_display(&self) -> String {
+        format!("{self:?}")
+    }
+}
+
+impl FieldDisplay for InterestMode {
+    fn field_display(&self) -> String {
+        format!("{self:?}")
+    }
+}
+
+impl FieldDisplay for InterestOptions {
+    fn field_display(&self) -> String {
+        let flags = [
+            (self.keyexprs(), "keyexprs"),
+            (self.subscribers(), "subscribers"),
+            (self.queryables(), "queryables"),
+            (self.tokens(), "tokens"),
+            (self.restricted(), "restricted"),
+            (self.named(), "named"),
+            (self.mapping(), "mapping"),
+            (self.aggregate(), "aggregate"),
+        ];
+        let set = flags
+            .iter()
+            .filter_map(|(is_set, name)| is_set.then_some(*name))
+            .collect::<Vec<_>>();
+        if set.is_empty() {
+            "None".to_string()
+        } else {
+            set.join(" | ")
+        }
+    }
+}
+
+impl FieldDisplay for QueryableInfoType {
+    fn field_display(&self) -> String {
+        format!("complete={}, distance={}", self.complete, self.distance)
+    }
+}
+
+impl FieldDisplay for WireExprType {
+    fn field_display(&self) -> String {
+        self.wire_expr.field_display()
+    }
+
+    fn add_details(&self, item: *mut epan_sys::proto_item, args: &TreeArgs) -> Result<()> {
+        self.wire_expr.add_details(item, args)
+    }
+}
+
+impl<const ID: u8> FieldDisplay for SourceInfoType<ID> {
+    fn field_display(&self) -> String {
+        format!("{}, sn={}", self.id.field_display(), self.sn)
+    }
+
+    fn add_details(&self, item: *mut epan_sys::proto_item, args: &TreeArgs) -> Result<()> {
+        ext::add_source_info(&self.id, self.sn, item, args)
+    }
+}
+
+impl<const ID: u8> FieldDisplay for AttachmentType<ID> {
+    fn field_display(&self) -> String {
+        self.buffer.field_display()
+    }
+
+    fn add_details(&self, item: *mut epan_sys::proto_item, args: &TreeArgs) -> Result<()> {
+        ext::add_attachment(&self.buffer.contiguous(), item, args)
+    }
+}
+
+impl<const ID: u8> FieldDisplay for ShmType<ID> {
+    fn field_display(&self) -> String {
+        "Enabled".to_string()
+    }
+}
+
+impl<const VID: u8, const SID: u8> FieldDisplay for ValueType<VID, SID> {
+    fn field_display(&self) -> String {
+        format!(
+            "{}, {}",
+            self.encoding.field_display(),
+            self.payload.field_display()
+        )
+    }
+}
+
+impl FieldDisplay for ConsolidationMode {
+    fn field_display(&self) -> String {
+        format!("{self:?}")
+    }
+}
+
+impl FieldDisplay for PushBody {
+    fn field_display(&self) -> String {
+        match self {
+            PushBody::Put(put) => format!(
+                "Put, {}, {}",
+                put.encoding.field_display(),
+                put.payload.field_display()
+            ),
+            PushBody::Del(_) => "Del".to_string(),
+        }
+    }
+}
@@ -0,0 +1,179 @@
+//! Expert info raised on protocol anomalies, listed in `Analyze > Expert Information` and
+//! filterable by their name, e.g. `zenoh.expert.unknown_mandatory_ext`.
+
+use std::{cell::RefCell, collections::HashMap};
+
+use anyhow::{anyhow, Result};
+
+use crate::utils::{leak_nul_terminated_str, lossy_c_string};
+
+pub const EI_UNKNOWN_MANDATORY_EXT: &str = "zenoh.expert.unknown_mandatory_ext";
+pub const EI_SESSION_WITHOUT_CLOSE: &str = "zenoh.expert.session_without_close";
+pub const EI_HANDSHAKE_OUT_OF_ORDER: &str = "zenoh.expert.handshake_out_of_order";
+pub const EI_DATA_BEFORE_OPEN: &str = "zenoh.expert.data_before_open";
+pub const EI_VERSION_MISMATCH: &str = "zenoh.expert.version_mismatch";
+pub const EI_COOKIE_MISMATCH: &str = "zenoh.expert.cookie_mismatch";
+pub const EI_EXTENSION_DECLINED: &str = "zenoh.expert.extension_declined";
+pub const EI_EXTENSION_UNSOLICITED: &str = "zenoh.expert.extension_unsolicited";
+pub const EI_KEEP_ALIVE_MISSED: &str = "zenoh.expert.keep_alive_missed";
+pub const EI_LEASE_EXPIRED: &str = "zenoh.expert.lease_expired";
+pub const EI_MALFORMED_LINKSTATE: &str = "zenoh.expert.malformed_linkstate";
+pub const EI_INVALID_TIME_RANGE: &str = "zenoh.expert.invalid_time_range";
+pub const EI_MALFORMED_FRAGMENTS: &str = "zenoh.expert.malformed_fragments";
+pub const EI_CLEARTEXT_CREDENTIALS: &str = "zenoh.expert.cleartext_credentials";
+
+/// Name, group, severity and summary of each expert info of the zenoh protocol.
+const EXPERT_INFOS: &[(&str, u32, u32, &str)] = &[
+    (
+        EI_UNKNOWN_MANDATORY_EXT,
+        epan_sys::PI_PROTOCOL,
+        epan_sys::PI_WARN,
+        "Unknown mandatory extension, the receiver closes the session",
+    ),
+    (
+        EI_SESSION_WITHOUT_CLOSE,
+        epan_sys::PI_SEQUENCE,
+        epan_sys::PI_WARN,
+        "Session ended without a Close",
+    ),
+    (
+        EI_HANDSHAKE_OUT_OF_ORDER,
+        epan_sys::PI_SEQUENCE,
+        epan_sys::PI_WARN,
+        "Handshake step out of order or missing",
+    ),
+    (
+        EI_DATA_BEFORE_OPEN,
+        epan_sys::PI_SEQUENCE,
+        epan_sys::PI_WARN,
+        "Data sent before the handshake completed",
+    ),
+    (
+        EI_VERSION_MISMATCH,
+        epan_sys::PI_PROTOCOL,
+        epan_sys::PI_WARN,
+        "Protocol versions of the handshake differ",
+    ),
+    (
+        EI_COOKIE_MISMATCH,
+        epan_sys::PI_PROTOCOL,
+        epan_sys::PI_ERROR,
+        "OpenSyn cookie differs from the InitAck cookie",
+    ),
+    (
+        EI_EXTENSION_DECLINED,
+        epan_sys::PI_PROTOCOL,
+        epan_sys::PI_NOTE,
+        "Extension offered but not accepted",
+    ),
+    (
+        EI_EXTENSION_UNSOLICITED,
+        epan_sys::PI_PROTOCOL,
+        epan_sys::PI_WARN,
+        "Extension accepted but not offered",
+    ),
+    (
+        EI_KEEP_ALIVE_MISSED,
+        epan_sys::PI_SEQUENCE,
+        epan_sys::PI_WARN,
+        "Peer silent for several keep-alive intervals",
+    ),
+    (
+        EI_LEASE_EXPIRED,
+        epan_sys::PI_SEQUENCE,
+        epan_sys::PI_ERROR,
+        "Peer silent for longer than its lease",
+    ),
+    (
+        EI_MALFORMED_LINKSTATE,
+        epan_sys::PI_MALFORMED,
+        epan_sys::PI_ERROR,
+        "Malformed link state list",
+    ),
+    (
+        EI_INVALID_TIME_RANGE,
+        epan_sys::PI_PROTOCOL,
+        epan_sys::PI_WARN,
+        "Invalid _time parameter of a selector",
+    ),
+    (
+        EI_MALFORMED_FRAGMENTS,
+        epan_sys::PI_MALFORMED,
+        epan_sys::PI_ERROR,
+        "Malformed reassembled network message",
+    ),
+    (
+        EI_CLEARTEXT_CREDENTIALS,
+        epan_sys::PI_SECURITY,
+        epan_sys::PI_WARN,
+        "User/password authentication over an unencrypted transport",
+    ),
+];
+
+thread_local! {
+    static EXPERT_FIELDS: RefCell<HashMap<&'static str, *mut epan_sys::expert_field>> =
+        RefCell::default();
+}
+
+/// Register the expert infos of the zenoh protocol.
+///
+/// # Safety
+///
+/// Must be called once, while registering the protocol.
+pub unsafe fn register(proto_id: std::ffi::c_int) -> Result<()> {
+    let module = epan_sys::expert_register_protocol(proto_id);
+    for &(name, group, severity, summary) in EXPERT_INFOS {
+        // Wireshark keeps references to the expert field and its strings, so they are leaked
+        let ids = Box::leak(Box::new(epan_sys::expert_field { ei: -1, hf: -1 })) as *mut _;
+        let ei_register_info = epan_sys::ei_register_info {
+            ids,
+            eiinfo: epan_sys::expert_field_info {
+                name: leak_nul_terminated_str(name)?,
+                group: group as _,
+                severity: severity as _,
+                summary: leak_nul_terminated_str(summary)?,
+                id: 0,
+                protocol: std::ptr::null(),
+                orig_severity: 0,
+                hf_info: epan_sys::hf_register_info {
+                    p_id: std::ptr::null_mut(),
+                    hfinfo: epan_sys::header_field_info {
+                        name: std::ptr::null(),
+                        abbrev: std::ptr::null(),
+                        type_: epan_sys::ftenum_FT_NONE,
+                        display: epan_sys::field_display_e_BASE_NONE as _,
+                        strings: std::ptr::null(),
+                        bitmask: 0,
+                        blurb: std::ptr::null(),
+                        id: -1,
+                        parent: 0,
+                        ref_type: epan_sys::hf_ref_type_HF_REF_TYPE_NONE,
+                        same_name_prev_id: -1,
+                        same_name_next: std::ptr::null_mut(),
+                    },
+                },
+            },
+        };
+        let eis = Box::leak(Box::new([ei_register_info])) as *mut _;
+        epan_sys::expert_register_field_array(module, eis, 1);
+        EXPERT_FIELDS.with_borrow_mut(|fields| fields.insert(name, ids));
+    }
+    Ok(())
+}
+
+/// Raise the expert info `name` on `item`, with `message` in place of its summary.
+pub fn add(
+    pinfo: *mut epan_sys::packet_info,
+    item: *mut epan_sys::proto_item,
+    name: &str,
+    message: &str,
+) -> Result<()> {
+    let field = EXPERT_FIELDS
+        .with_borrow(|fields| fields.get(name).copied())
+        .ok_or_else(|| anyhow!("expert info {name} is not registered"))?;
+    let message = lossy_c_string(message);
+    unsafe {
+        epan_sys::expert_add_info_format(pinfo, item, field, c"%s".as_ptr(), message.as_ptr())
+    };
+    Ok(())
+}
@@ -0,0 +1,299 @@
+//! `File > Export Objects > Zenoh`: the payloads of publications and replies.
+//!
+//! Every `Put` and `Reply` payload, including the ones of reassembled fragments, is queued on
+//! the export object tap with its resolved key expression and the mime type of its encoding.
+//! Files are named after the key expression, with an extension guessed from the mime type.
+//!
+//! Every frame with link states also lists DOT and JSON snapshots of the router graph as of that
+//! frame, e.g. `router-topology-42.dot`, see `linkstate`, and every frame with ROS 2 liveliness
+//! tokens a DOT snapshot of the ROS 2 graph, e.g. `ros2-graph-42.dot`, see `ros2_graph`.
+
+use std::ffi::{c_char, c_int, c_void, CStr, CString};
+
+use zenoh_buffers::buffer::SplitBuffer;
+use zenoh_protocol::transport::TransportMessage;
+
+use crate::{
+    display::FieldDisplay,
+    key_expr, linkstate,
+    payload::Payload,
+    ros2_graph,
+    utils::{network_messages, packet_str},
+};
+
+static mut TAP_ID: c_int = -1;
+
+/// File extensions of the mime types, the others being saved as `.bin`.
+const EXTENSIONS: &[(&str, &str)] = &[
+    ("application/json", "json"),
+    ("text/json", "json"),
+    ("application/xml", "xml"),
+    ("text/xml", "xml"),
+    ("application/cbor", "cbor"),
+    ("application/yaml", "yaml"),
+    ("text/yaml", "yaml"),
+    ("image/png", "png"),
+    ("image/jpeg", "jpg"),
+    ("image/gif", "gif"),
+    ("image/bmp", "bmp"),
+    ("image/webp", "webp"),
+    ("text/html", "html"),
+    ("text/csv", "csv"),
+    ("text/markdown", "md"),
+    ("text/plain", "txt"),
+    ("zenoh/string", "txt"),
+];
+
+/// Register the export objects of the zenoh protocol.
+pub unsafe fn register(proto_id: c_int) {
+    TAP_ID = epan_sys::register_export_object(proto_id, Some(export_packet), Some(export_reset));
+}
+
+/// Data queued on the tap for every exported payload, or for the link state nodes and the ROS 2
+/// tokens of a frame, allocated in the packet scope.
+#[repr(C)]
+struct ObjectEvent {
+    key_expr: *const c_char,
+    content_type: *const c_char,
+    payload: *const u8,
+    payload_len: usize,
+    /// Node events of `linkstate`, the payload fields being null.
+    nodes: *const *const c_void,
+    nodes_len: usize,
+    /// Token events of `ros2_graph`, the payload fields being null.
+    tokens: *const *const c_void,
+    tokens_len: usize,
+}
+
+/// Copy graph events to the packet scope, or returns null if there are none.
+unsafe fn packet_events(
+    pinfo: *mut epan_sys::_packet_info,
+    events: &[*const c_void],
+) -> *const *const c_void {
+    if events.is_empty() {
+        return std::ptr::null();
+    }
+    epan_sys::wmem_memdup(
+        (*pinfo).pool,
+        events.as_ptr() as _,
+        std::mem::size_of_val(events),
+    ) as *const *const c_void
+}
+
+/// Queue the publication and reply payloads of a transport message, including the network
+/// message reassembled from its fragment.
+pub(crate) unsafe fn tap_objects(pinfo: *mut epan_sys::_packet_info, msg: &TransportMessage) {
+    if !epan_sys::have_tap_listener(TAP_ID) {
+        return;
+    }
+
+    let msgs = network_messages(pinfo, msg);
+    for payload in msgs
+        .iter()
+        .filter_map(|msg| Payload::from_network_message(msg))
+        .filter(|payload| matches!(payload.kind, "Put" | "Reply"))
+    {
+        let key_expr = key_expr::resolve(pinfo, payload.wire_expr)
+            .unwrap_or_else(|| payload.wire_expr.to_string());
+        let bytes = payload.bytes.contiguous();
+
+        let pool = (*pinfo).pool;
+        let event =
+            epan_sys::wmem_alloc0(pool, std::mem::size_of::<ObjectEvent>()) as *mut ObjectEvent;
+        *event = ObjectEvent {
+            key_expr: packet_str(pinfo, Some(key_expr)),
+            content_type: packet_str(pinfo, Some(payload.encoding.field_display())),
+            payload: epan_sys::wmem_memdup(pool, bytes.as_ptr() as _, bytes.len()) as *const u8,
+            payload_len: bytes.len(),
+            nodes: std::ptr::null(),
+            nodes_len: 0,
+            tokens: std::ptr::null(),
+            tokens_len: 0,
+        };
+        epan_sys::tap_queue_packet(TAP_ID, pinfo, event as *const c_void);
+    }
+
+    let nodes = linkstate::node_events(pinfo, msg);
+    let tokens = ros2_graph::token_events(pinfo, msg);
+    if !nodes.is_empty() || !tokens.is_empty() {
+        let event = epan_sys::wmem_alloc0((*pinfo).pool, std::mem::size_of::<ObjectEvent>())
+            as *mut ObjectEvent;
+        *event = ObjectEvent {
+            key_expr: std::ptr::null(),
+            content_type: std::ptr::null(),
+            payload: std::ptr::null(),
+            payload_len: 0,
+            nodes: packet_events(pinfo, &nodes),
+            nodes_len: nodes.len(),
+            tokens: packet_events(pinfo, &tokens),
+            tokens_len: tokens.len(),
+        };
+        epan_sys::tap_queue_packet(TAP_ID, pinfo, event as *const c_void);
+    }
+}
+
+/// Returns the file name of a payload: its key expression with the chunk separators, wildcards
+/// and control characters replaced, and an extension guessed from its encoding.
+fn filename(key_expr: &str, content_type: &str) -> String {
+    let mime = content_type.split(';').next().unwrap_or_default();
+    let extension = EXTENSIONS
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case(mime))
+        .map_or("bin", |(_, extension)| extension);
+    let stem: String = key_expr
+        .chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect();
+    match stem.is_empty() {
+        true => format!("payload.{extension}"),
+        false => format!("{stem}.{extension}"),
+    }
+}
+
+unsafe fn str_or_empty<'a>(s: *const c_char) -> &'a str {
+    if s.is_null() {
+        ""
+    } else {
+        CStr::from_ptr(s).to_str().unwrap_or_default()
+    }
+}
+
+unsafe fn g_str(s: *const c_char) -> *mut c_char {
+    if s.is_null() {
+        std::ptr::null_mut()
+    } else {
+        epan_sys::g_strdup(s)
+    }
+}
+
+/// Add the snapshots of a graph to the list of export objects.
+unsafe fn add_graph_objects(
+    object_list: &epan_sys::export_object_list_t,
+    pkt_num: u32,
+    hostname: &CStr,
+    objects: impl IntoIterator<Item = (String, &'static CStr, String)>,
+) {
+    for (filename, content_type, contents) in objects {
+        let Ok(filename) = CString::new(filename) else {
+            continue;
+        };
+        add_object(
+            object_list,
+            pkt_num,
+            hostname.as_ptr(),
+            content_type.as_ptr(),
+            &filename,
+            contents.as_ptr(),
+            contents.len(),
+        );
+    }
+}
+
+/// Add an entry to the list of export objects, which takes ownership of it and frees it with glib.
+unsafe fn add_object(
+    object_list: &epan_sys::export_object_list_t,
+    pkt_num: u32,
+    hostname: *const c_char,
+    content_type: *const c_char,
+    filename: &CStr,
+    payload: *const u8,
+    payload_len: usize,
+) {
+    let Some(add_entry) = object_list.add_entry else {
+        return;
+    };
+    let entry = epan_sys::g_malloc0(std::mem::size_of::<epan_sys::export_object_entry_t>() as _)
+        as *mut epan_sys::export_object_entry_t;
+    (*entry).pkt_num = pkt_num;
+    (*entry).hostname = g_str(hostname);
+    (*entry).content_type = g_str(content_type);
+    (*entry).filename = epan_sys::g_strdup(filename.as_ptr());
+    (*entry).payload_len = payload_len;
+    (*entry).payload_data =
+        epan_sys::g_memdup2(payload as *const c_void, payload_len as _) as *mut u8;
+    add_entry(object_list.gui_data, entry);
+}
+
+unsafe extern "C" fn export_packet(
+    tapdata: *mut c_void,
+    pinfo: *mut epan_sys::packet_info,
+    _edt: *mut epan_sys::epan_dissect_t,
+    data: *const c_void,
+    _flags: epan_sys::tap_flags_t,
+) -> epan_sys::tap_packet_status {
+    let object_list = &*(tapdata as *mut epan_sys::export_object_list_t);
+    let event = &*(data as *const ObjectEvent);
+
+    if !event.nodes.is_null() || !event.tokens.is_null() {
+        if !event.nodes.is_null() {
+            let nodes = std::slice::from_raw_parts(event.nodes, event.nodes_len);
+            let objects = linkstate::graph_objects(pinfo, nodes);
+            add_graph_objects(object_list, (*pinfo).num, c"Router topology", objects);
+        }
+        if !event.tokens.is_null() {
+            let tokens = std::slice::from_raw_parts(event.tokens, event.tokens_len);
+            let objects = ros2_graph::graph_objects(pinfo, tokens);
+            add_graph_objects(object_list, (*pinfo).num, c"ROS 2 graph", objects);
+        }
+        return epan_sys::tap_packet_status_TAP_PACKET_REDRAW;
+    }
+
+    let filename = filename(
+        str_or_empty(event.key_expr),
+        str_or_empty(event.content_type),
+    );
+    let Ok(filename) = CString::new(filename) else {
+        return epan_sys::tap_packet_status_TAP_PACKET_DONT_REDRAW;
+    };
+    add_object(
+        object_list,
+        (*pinfo).num,
+        event.key_expr,
+        event.content_type,
+        &filename,
+        event.payload,
+        event.payload_len,
+    );
+
+    epan_sys::tap_packet_status_TAP_PACKET_REDRAW
+}
+
+unsafe extern "C" fn export_reset() {
+    linkstate::reset_graph_objects();
+    ros2_graph::reset_graph_objects();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn filenames() {
+        assert_eq!(
+            filename("demo/example/image", "image/png"),
+            "demo_example_image.png"
+        );
+        assert_eq!(
+            filename("sensors/temp", "application/json;charset=utf-8"),
+            "sensors_temp.json"
+        );
+        assert_eq!(filename("a/b", "TEXT/PLAIN"), "a_b.txt");
+        assert_eq!(filename("a/b", "zenoh/bytes"), "a_b.bin");
+    }
+
+    #[test]
+    fn filenames_of_unusual_input() {
+        // Wildcards and characters invalid in file names are replaced.
+        assert_eq!(filename("robot/*/**", ""), "robot_____.bin");
+        assert_eq!(filename("a\nb\0", "text/plain"), "a_b_.txt");
+        assert_eq!(
+            filename(r#"c:\a"b<c>d|e?"#, "text/csv"),
+            "c__a_b_c_d_e_.csv"
+        );
+        assert_eq!(filename("", ";"), "payload.bin");
+    }
+}
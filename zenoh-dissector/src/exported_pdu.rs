@@ -0,0 +1,272 @@
+//! `File > Export PDUs to File... > Zenoh`: captures of the Zenoh batches alone.
+//!
+//! Every batch is exported once reassembled, decrypted and decompressed, as the bytes its
+//! transport messages were decoded from, and so are the messages of lowlatency sessions. Besides
+//! the addresses, ports and original frame number, the exported PDUs are tagged with the ZIDs of
+//! the conversation, whether the batch was compressed and whether it is made of lowlatency
+//! messages. The `zenoh_exported` dissector reads them back: the ZIDs seed the conversation state,
+//! which a capture without the handshake would otherwise lack.
+
+use std::{
+    ffi::{c_int, c_void, CStr},
+    ptr, slice,
+};
+
+use anyhow::Result;
+
+use crate::{
+    conversation,
+    header_field::{FieldKind, HeaderFieldMap, Registration},
+    tree::{set_generated, TreeArgs},
+    Framing,
+};
+
+pub const FIELD_EXPORTED: &str = "zenoh.exported";
+pub const FIELD_SRC_ZID: &str = "zenoh.exported.src_zid";
+pub const FIELD_DST_ZID: &str = "zenoh.exported.dst_zid";
+pub const FIELD_COMPRESSION: &str = "zenoh.exported.compression";
+pub const FIELD_LOWLATENCY: &str = "zenoh.exported.lowlatency";
+
+const TAP_NAME: &CStr = c"Zenoh";
+/// Name of the dissector called on the exported PDUs.
+pub const DISSECTOR_NAME: &CStr = c"zenoh_exported";
+
+/// Tags of the exported PDUs, outside of the range used by wireshark.
+const TAG_SRC_ZID: u16 = 0x5a01;
+const TAG_DST_ZID: u16 = 0x5a02;
+const TAG_COMPRESSION: u16 = 0x5a03;
+const TAG_LOWLATENCY: u16 = 0x5a04;
+
+/// Length of the type and length of a tag.
+const TAG_HEADER_LEN: usize = 4;
+
+static mut TAP_ID: c_int = -1;
+
+/// The header fields of the exported PDU tags.
+pub struct ExportedPdu;
+
+impl Registration for ExportedPdu {
+    fn generate_hf_map(prefix: &str) -> HeaderFieldMap {
+        HeaderFieldMap::new()
+            .add(prefix.to_string(), "Exported PDU", FieldKind::Branch)
+            .add(format!("{prefix}.src_zid"), "Source ZID", FieldKind::Text)
+            .add(
+                format!("{prefix}.dst_zid"),
+                "Destination ZID",
+                FieldKind::Text,
+            )
+            .add(
+                format!("{prefix}.compression"),
+                "Compressed on the Wire",
+                FieldKind::Bool,
+            )
+            .add(
+                format!("{prefix}.lowlatency"),
+                "Low Latency",
+                FieldKind::Bool,
+            )
+    }
+
+    fn generate_subtree_names(prefix: &str) -> Vec<String> {
+        vec![prefix.to_string()]
+    }
+}
+
+/// The Zenoh tags of an exported PDU.
+#[derive(Debug, Default)]
+pub(crate) struct Tags {
+    src_zid: Option<String>,
+    dst_zid: Option<String>,
+    compression: bool,
+    lowlatency: bool,
+}
+
+/// Register the exported PDU tap and the dissector of the exported PDUs.
+pub unsafe fn register(proto_id: c_int) {
+    TAP_ID = epan_sys::register_export_pdu_tap(TAP_NAME.as_ptr());
+    epan_sys::register_dissector(DISSECTOR_NAME.as_ptr(), Some(dissect_exported), proto_id);
+}
+
+/// A tag added to the exported PDUs, allocated in the packet scope.
+#[repr(C)]
+struct TagItem {
+    tag: u16,
+    value: *const u8,
+    len: usize,
+}
+
+fn padded_len(len: usize) -> usize {
+    (len + 3) & !3
+}
+
+unsafe extern "C" fn tag_size(_pinfo: *mut epan_sys::packet_info, data: *mut c_void) -> c_int {
+    let item = &*(data as *const TagItem);
+    (TAG_HEADER_LEN + padded_len(item.len)) as c_int
+}
+
+unsafe extern "C" fn tag_populate(
+    _pinfo: *mut epan_sys::packet_info,
+    data: *mut c_void,
+    tlv_buffer: *mut u8,
+    tlv_buffer_size: u32,
+) -> c_int {
+    let item = &*(data as *const TagItem);
+    let size = TAG_HEADER_LEN + padded_len(item.len);
+    if (tlv_buffer_size as usize) < size {
+        return 0;
+    }
+    let buffer = slice::from_raw_parts_mut(tlv_buffer, size);
+    buffer.fill(0);
+    buffer[..2].copy_from_slice(&item.tag.to_be_bytes());
+    buffer[2..4].copy_from_slice(&(padded_len(item.len) as u16).to_be_bytes());
+    buffer[TAG_HEADER_LEN..TAG_HEADER_LEN + item.len]
+        .copy_from_slice(slice::from_raw_parts(item.value, item.len));
+    size as c_int
+}
+
+/// Returns an exported PDU item for a tag, allocated in the packet scope.
+unsafe fn tag_item(
+    pinfo: *mut epan_sys::_packet_info,
+    tag: u16,
+    value: &[u8],
+) -> *const epan_sys::exp_pdu_data_item_t {
+    let pool = (*pinfo).pool;
+    let data = epan_sys::wmem_alloc0(pool, std::mem::size_of::<TagItem>()) as *mut TagItem;
+    *data = TagItem {
+        tag,
+        value: epan_sys::wmem_memdup(pool, value.as_ptr() as _, value.len()) as *const u8,
+        len: value.len(),
+    };
+    let item = epan_sys::wmem_alloc0(pool, std::mem::size_of::<epan_sys::exp_pdu_data_item_t>())
+        as *mut epan_sys::exp_pdu_data_item_t;
+    *item = epan_sys::exp_pdu_data_item_t {
+        size_func: Some(tag_size),
+        populate_data: Some(tag_populate),
+        data: data as *mut c_void,
+    };
+    item
+}
+
+/// Queue the transport messages of a batch on the exported PDU tap, as the bytes they were
+/// decoded from with the framing they were received with.
+pub(crate) unsafe fn export_batch(
+    tvb: *mut epan_sys::tvbuff,
+    pinfo: *mut epan_sys::_packet_info,
+    framing: Framing,
+    bytes: &[u8],
+) {
+    if !epan_sys::have_tap_listener(TAP_ID) || bytes.is_empty() {
+        return;
+    }
+
+    let mut items = vec![
+        &raw const epan_sys::exp_pdu_data_src_ip,
+        &raw const epan_sys::exp_pdu_data_dst_ip,
+        &raw const epan_sys::exp_pdu_data_port_type,
+        &raw const epan_sys::exp_pdu_data_src_port,
+        &raw const epan_sys::exp_pdu_data_dst_port,
+        &raw const epan_sys::exp_pdu_data_orig_frame_num,
+    ];
+    if let Some(zid) = conversation::source_zid(pinfo) {
+        items.push(tag_item(pinfo, TAG_SRC_ZID, zid.as_bytes()));
+    }
+    if let Some(zid) = conversation::destination_zid(pinfo) {
+        items.push(tag_item(pinfo, TAG_DST_ZID, zid.as_bytes()));
+    }
+    let (compression, lowlatency) = match framing {
+        Framing::Batch { compression } => (compression, false),
+        Framing::LowLatency => (false, true),
+    };
+    items.push(tag_item(pinfo, TAG_COMPRESSION, &[compression as u8]));
+    items.push(tag_item(pinfo, TAG_LOWLATENCY, &[lowlatency as u8]));
+    items.push(ptr::null());
+
+    let exp_pdu_data = epan_sys::export_pdu_create_tags(
+        pinfo,
+        DISSECTOR_NAME.as_ptr(),
+        epan_sys::EXP_PDU_TAG_DISSECTOR_NAME as u16,
+        items.as_mut_ptr(),
+    );
+    let data = epan_sys::wmem_memdup((*pinfo).pool, bytes.as_ptr() as _, bytes.len());
+    (*exp_pdu_data).tvb_captured_length = bytes.len() as _;
+    (*exp_pdu_data).tvb_reported_length = bytes.len() as _;
+    (*exp_pdu_data).pdu_tvb = epan_sys::tvb_new_child_real_data(
+        tvb,
+        data as *const u8,
+        bytes.len() as _,
+        bytes.len() as _,
+    );
+    epan_sys::tap_queue_packet(TAP_ID, pinfo, exp_pdu_data as *const c_void);
+}
+
+/// Read the Zenoh tags preceding an exported PDU, in the data source it's a subset of.
+unsafe fn read_tags(tvb: *mut epan_sys::tvbuff) -> Tags {
+    let mut tags = Tags::default();
+    let ds_tvb = epan_sys::tvb_get_ds_tvb(tvb);
+    if ds_tvb.is_null() || ds_tvb == tvb {
+        return tags;
+    }
+
+    let ds_len = epan_sys::tvb_captured_length(ds_tvb) as usize;
+    let mut offset = 0;
+    while offset + TAG_HEADER_LEN <= ds_len {
+        let tag = epan_sys::tvb_get_ntohs(ds_tvb, offset as _);
+        let len = epan_sys::tvb_get_ntohs(ds_tvb, (offset + 2) as _) as usize;
+        let value_offset = offset + TAG_HEADER_LEN;
+        if tag as u32 == epan_sys::EXP_PDU_TAG_END_OF_OPT || value_offset + len > ds_len {
+            break;
+        }
+        let value = slice::from_raw_parts(
+            epan_sys::tvb_get_ptr(ds_tvb, value_offset as _, len as _),
+            len,
+        );
+        // String values are padded with nul bytes.
+        let string = || {
+            let end = value.iter().position(|&b| b == 0).unwrap_or(len);
+            String::from_utf8_lossy(&value[..end]).into_owned()
+        };
+        match tag {
+            TAG_SRC_ZID => tags.src_zid = Some(string()),
+            TAG_DST_ZID => tags.dst_zid = Some(string()),
+            TAG_COMPRESSION => tags.compression = value.first().is_some_and(|&b| b != 0),
+            TAG_LOWLATENCY => tags.lowlatency = value.first().is_some_and(|&b| b != 0),
+            _ => {}
+        }
+        offset = value_offset + len;
+    }
+    tags
+}
+
+/// Dissect an exported PDU, restoring the ZIDs of its conversation from its tags.
+unsafe extern "C" fn dissect_exported(
+    tvb: *mut epan_sys::tvbuff,
+    pinfo: *mut epan_sys::_packet_info,
+    tree: *mut epan_sys::_proto_node,
+    _data: *mut c_void,
+) -> c_int {
+    let tags = read_tags(tvb);
+    // Exported PDUs aren't dissected by TCP or UDP, so their conversation doesn't exist yet.
+    epan_sys::find_or_create_conversation(pinfo);
+    conversation::record_zids(pinfo, tags.src_zid.as_deref(), tags.dst_zid.as_deref());
+    // The batch was exported decompressed.
+    let framing = if tags.lowlatency {
+        Framing::LowLatency
+    } else {
+        Framing::Batch { compression: false }
+    };
+    crate::dissect_batch(tvb, pinfo, tree, framing, Some(&tags))
+}
+
+/// Add the Zenoh tags of an exported PDU.
+pub(crate) fn add_to_tree(tags: &Tags, args: &TreeArgs) -> Result<()> {
+    let args = args.make_subtree(FIELD_EXPORTED, "Exported PDU")?;
+    if let Some(zid) = &tags.src_zid {
+        set_generated(args.add_text(FIELD_SRC_ZID, zid)?);
+    }
+    if let Some(zid) = &tags.dst_zid {
+        set_generated(args.add_text(FIELD_DST_ZID, zid)?);
+    }
+    set_generated(args.add_bool(FIELD_COMPRESSION, tags.compression)?);
+    set_generated(args.add_bool(FIELD_LOWLATENCY, tags.lowlatency)?);
+    Ok(())
+}
@@ -0,0 +1,239 @@
+//! Decoding of the source info, attachment and shared-memory extensions of zenoh messages, and
+//! of the unknown extensions of any message, into filterable fields.
+//!
+//! See the `ext` definitions in `zenoh_protocol::zenoh` and `zenoh_protocol::common::extension`.
+//! The shared-memory extension is a mere marker: the buffer descriptors are carried instead of
+//! the payload, in slices tagged as shared-memory pointers.
+
+use anyhow::{anyhow, Result};
+use zenoh_buffers::{buffer::SplitBuffer, ZSlice, ZSliceKind};
+use zenoh_codec::{RCodec, Zenoh080};
+use zenoh_protocol::{
+    common::{ZExtBody, ZExtUnknown},
+    core::EntityGlobalIdProto,
+};
+
+use crate::{
+    display::FieldDisplay,
+    expert,
+    header_field::{FieldKind, HeaderFieldMap, Registration},
+    tree::TreeArgs,
+};
+
+pub const FIELD_SINFO: &str = "zenoh.sinfo";
+pub const FIELD_SINFO_ZID: &str = "zenoh.sinfo.zid";
+pub const FIELD_SINFO_EID: &str = "zenoh.sinfo.eid";
+pub const FIELD_SINFO_SN: &str = "zenoh.sinfo.sn";
+pub const FIELD_ATTACHMENT: &str = "zenoh.attachment";
+pub const FIELD_ATTACHMENT_LEN: &str = "zenoh.attachment.len";
+pub const FIELD_ATTACHMENT_DATA: &str = "zenoh.attachment.data";
+pub const FIELD_SHM: &str = "zenoh.shm";
+pub const FIELD_SHM_SEGMENT: &str = "zenoh.shm.segment";
+pub const FIELD_SHM_CHUNK: &str = "zenoh.shm.chunk";
+pub const FIELD_SHM_SIZE: &str = "zenoh.shm.size";
+pub const FIELD_SHM_GENERATION: &str = "zenoh.shm.generation";
+pub const FIELD_EXT_UNKNOWN: &str = "zenoh.ext_unknown";
+pub const FIELD_EXT_UNKNOWN_EXTENSION: &str = "zenoh.ext_unknown.extension";
+pub const FIELD_EXT_UNKNOWN_ID: &str = "zenoh.ext_unknown.id";
+pub const FIELD_EXT_UNKNOWN_ENCODING: &str = "zenoh.ext_unknown.encoding";
+pub const FIELD_EXT_UNKNOWN_MANDATORY: &str = "zenoh.ext_unknown.mandatory";
+pub const FIELD_EXT_UNKNOWN_Z64: &str = "zenoh.ext_unknown.z64";
+pub const FIELD_EXT_UNKNOWN_ZBUF: &str = "zenoh.ext_unknown.zbuf";
+
+const ENCODING_NAMES: &[(u32, &str)] = &[(0, "Unit"), (1, "Z64"), (2, "ZBuf")];
+
+const ID_MASK: u8 = 0b0000_1111;
+const ENC_MASK: u8 = 0b0110_0000;
+const ENC_SHIFT: u8 = 5;
+
+/// The source info header fields, shared by every message carrying an `ext_sinfo`.
+pub struct SourceInfo;
+
+impl Registration for SourceInfo {
+    fn generate_hf_map(prefix: &str) -> HeaderFieldMap {
+        HeaderFieldMap::new()
+            .add(format!("{prefix}.zid"), "Source ZID", FieldKind::Text)
+            .add(
+                format!("{prefix}.eid"),
+                "Source Entity ID",
+                FieldKind::Number,
+            )
+            .add(format!("{prefix}.sn"), "Source SN", FieldKind::Number)
+    }
+
+    fn generate_subtree_names(prefix: &str) -> Vec<String> {
+        vec![prefix.to_string()]
+    }
+}
+
+/// The attachment header fields, shared by every message carrying an `ext_attachment`.
+pub struct Attachment;
+
+impl Registration for Attachment {
+    fn generate_hf_map(prefix: &str) -> HeaderFieldMap {
+        HeaderFieldMap::new()
+            .add(format!("{prefix}.len"), "Length", FieldKind::Number)
+            .add(format!("{prefix}.data"), "Data", FieldKind::Bytes)
+    }
+
+    fn generate_subtree_names(prefix: &str) -> Vec<String> {
+        vec![prefix.to_string()]
+    }
+}
+
+/// The shared-memory buffer descriptor header fields, shared by every payload sent through
+/// shared memory.
+pub struct Shm;
+
+impl Registration for Shm {
+    fn generate_hf_map(prefix: &str) -> HeaderFieldMap {
+        HeaderFieldMap::new()
+            .add(format!("{prefix}.segment"), "Segment ID", FieldKind::Number)
+            .add(format!("{prefix}.chunk"), "Chunk", FieldKind::Number)
+            .add(format!("{prefix}.size"), "Size", FieldKind::Number)
+            .add(
+                format!("{prefix}.generation"),
+                "Generation",
+                FieldKind::Number,
+            )
+    }
+
+    fn generate_subtree_names(prefix: &str) -> Vec<String> {
+        vec![prefix.to_string()]
+    }
+}
+
+/// Add the source ZID, entity id and sequence number below the `ext_sinfo` text item.
+pub fn add_source_info(
+    id: &EntityGlobalIdProto,
+    sn: u32,
+    item: *mut epan_sys::proto_item,
+    args: &TreeArgs,
+) -> Result<()> {
+    let args = args.item_subtree(item, FIELD_SINFO)?;
+    args.add_text(FIELD_SINFO_ZID, &id.zid.to_string())?;
+    args.add_number(FIELD_SINFO_EID, id.eid as u64)?;
+    args.add_number(FIELD_SINFO_SN, sn as u64)?;
+    Ok(())
+}
+
+/// Add the length and raw bytes below the `ext_attachment` text item.
+pub fn add_attachment(
+    bytes: &[u8],
+    item: *mut epan_sys::proto_item,
+    args: &TreeArgs,
+) -> Result<()> {
+    let args = args.item_subtree(item, FIELD_ATTACHMENT)?;
+    args.add_number(FIELD_ATTACHMENT_LEN, bytes.len() as u64)?;
+    args.add_bytes(FIELD_ATTACHMENT_DATA, bytes)?;
+    Ok(())
+}
+
+/// A shared-memory buffer descriptor, i.e. the `ShmBufInfo` of `zenoh_shm`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ShmBufInfo {
+    /// Size of the data in the buffer.
+    pub size: u64,
+    /// Id of the metadata segment.
+    pub segment: u64,
+    /// Index of the metadata chunk in its segment.
+    pub chunk: u64,
+    /// Generation of the buffer, bumped whenever the chunk is reused.
+    pub generation: u64,
+}
+
+impl ShmBufInfo {
+    pub fn decode(mut bytes: &[u8]) -> Result<Self> {
+        let codec = Zenoh080::new();
+        let mut read = || -> Result<u64> {
+            codec
+                .read(&mut bytes)
+                .map_err(|_| anyhow!("truncated shared-memory buffer descriptor"))
+        };
+        Ok(Self {
+            size: read()?,
+            segment: read()?,
+            chunk: read()?,
+            generation: read()?,
+        })
+    }
+
+    /// Decode the descriptor of a payload slice, if the slice was sent through shared memory.
+    pub fn from_zslice(slice: &ZSlice) -> Option<Result<Self>> {
+        (slice.kind == ZSliceKind::ShmPtr).then(|| Self::decode(slice))
+    }
+
+    /// Add the decoded descriptor below the payload text item.
+    pub fn add_to_tree(&self, item: *mut epan_sys::proto_item, args: &TreeArgs) -> Result<()> {
+        let args = args.item_subtree(item, FIELD_SHM)?;
+        args.add_number(FIELD_SHM_SEGMENT, self.segment)?;
+        args.add_number(FIELD_SHM_CHUNK, self.chunk)?;
+        args.add_number(FIELD_SHM_SIZE, self.size)?;
+        args.add_number(FIELD_SHM_GENERATION, self.generation)?;
+        Ok(())
+    }
+}
+
+/// The header fields of unknown extensions, shared by every message carrying an `ext_unknown`.
+pub struct UnknownExt;
+
+impl Registration for UnknownExt {
+    fn generate_hf_map(prefix: &str) -> HeaderFieldMap {
+        HeaderFieldMap::new()
+            .add(format!("{prefix}.extension"), "Extension", FieldKind::Text)
+            .add(format!("{prefix}.id"), "ID", FieldKind::Number)
+            .add(
+                format!("{prefix}.encoding"),
+                "Encoding",
+                FieldKind::Enum(ENCODING_NAMES),
+            )
+            .add(format!("{prefix}.mandatory"), "Mandatory", FieldKind::Bool)
+            .add(format!("{prefix}.z64"), "Value", FieldKind::Number)
+            .add(format!("{prefix}.zbuf"), "Value", FieldKind::Bytes)
+    }
+
+    fn generate_subtree_names(prefix: &str) -> Vec<String> {
+        vec![prefix.to_string(), format!("{prefix}.extension")]
+    }
+}
+
+/// Add an unknown extension as its own subtree below the `ext_unknown` text item.
+///
+/// Unknown mandatory extensions are flagged as they make the receiver close the session.
+pub fn add_unknown(
+    ext: &ZExtUnknown,
+    item: *mut epan_sys::proto_item,
+    args: &TreeArgs,
+) -> Result<()> {
+    let args = args.item_subtree(item, FIELD_EXT_UNKNOWN)?;
+    let ext_item = args.add_text(FIELD_EXT_UNKNOWN_EXTENSION, &ext.field_display())?;
+    if ext.is_mandatory() {
+        expert::add(
+            args.pinfo,
+            ext_item,
+            expert::EI_UNKNOWN_MANDATORY_EXT,
+            &format!(
+                "Unknown mandatory extension {:#04x}, the receiver closes the session",
+                ext.id & ID_MASK
+            ),
+        )?;
+    }
+
+    let args = args.item_subtree(ext_item, FIELD_EXT_UNKNOWN_EXTENSION)?;
+    args.add_number(FIELD_EXT_UNKNOWN_ID, (ext.id & ID_MASK) as u64)?;
+    args.add_enum(
+        FIELD_EXT_UNKNOWN_ENCODING,
+        ((ext.id & ENC_MASK) >> ENC_SHIFT) as u32,
+    )?;
+    args.add_bool(FIELD_EXT_UNKNOWN_MANDATORY, ext.is_mandatory())?;
+    match &ext.body {
+        ZExtBody::Unit => {}
+        ZExtBody::Z64(value) => {
+            args.add_number(FIELD_EXT_UNKNOWN_Z64, *value)?;
+        }
+        ZExtBody::ZBuf(zbuf) => {
+            args.add_bytes(FIELD_EXT_UNKNOWN_ZBUF, &zbuf.contiguous())?;
+        }
+    }
+    Ok(())
+}
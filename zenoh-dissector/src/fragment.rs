@@ -0,0 +1,510 @@
+//! Reassembly of network messages split across `Fragment` transport messages.
+//!
+//! Fragments are collected during the first pass per sending side, reliability and priority,
+//! since each of those channels has its own sequence numbers, shared by the links of a multilink
+//! session. A new message starts with the `First` extension, or with the first fragment seen on a
+//! channel for peers predating it. A gap in the sequence numbers, which wrap around at the
+//! resolution negotiated by the handshake, or the `Drop` extension discards the fragments
+//! collected so far. The network message decoded from the last fragment is shown in the frame of
+//! that fragment, and its payload handed off like the ones of a `Frame`.
+
+use std::{cell::RefCell, collections::HashMap};
+
+use anyhow::Result;
+use zenoh_codec::{RCodec, Zenoh080Reliability};
+use zenoh_protocol::{
+    core::{Bits, Field, Reliability},
+    network::{Mapping, NetworkMessage},
+    transport::{Fragment, TransportBody, TransportMessage, TransportSn},
+};
+
+use crate::{
+    expert,
+    header_field::{FieldKind, HeaderFieldMap, Registration},
+    key_expr,
+    payload::Payload,
+    session::{self, Side},
+    tree::{set_generated, AddToTree, TreeArgs},
+};
+
+pub const FIELD_FRAGMENT: &str = "zenoh.fragment";
+pub const FIELD_COUNT: &str = "zenoh.fragment.count";
+pub const FIELD_LENGTH: &str = "zenoh.fragment.length";
+pub const FIELD_FRAGMENT_IN: &str = "zenoh.fragment.fragment_in";
+pub const FIELD_REASSEMBLED_IN: &str = "zenoh.fragment.reassembled_in";
+
+/// Prefix of the network messages of a `Frame`, shared with the reassembled ones.
+pub(crate) const NETWORK_PREFIX: &str = "zenoh.transport.frame.network";
+
+/// The reassembly header fields, added below `Fragment` messages.
+pub struct Fragments;
+
+impl Registration for Fragments {
+    fn generate_hf_map(prefix: &str) -> HeaderFieldMap {
+        HeaderFieldMap::new()
+            .add(prefix.to_string(), "Fragments", FieldKind::Branch)
+            .add(
+                format!("{prefix}.count"),
+                "Fragment Count",
+                FieldKind::Number,
+            )
+            .add(
+                format!("{prefix}.length"),
+                "Reassembled Length",
+                FieldKind::Number,
+            )
+            .add(
+                format!("{prefix}.fragment_in"),
+                "Fragment in",
+                FieldKind::FrameNum,
+            )
+            .add(
+                format!("{prefix}.reassembled_in"),
+                "Reassembled in",
+                FieldKind::FrameNum,
+            )
+    }
+
+    fn generate_subtree_names(prefix: &str) -> Vec<String> {
+        vec![prefix.to_string()]
+    }
+}
+
+/// Sending side, reliability and priority of the fragments.
+pub(crate) type ChannelKey = (Side, bool, u8);
+
+/// Fragments collected on a channel, waiting for the last one.
+#[derive(Debug, Default)]
+struct PendingMessage {
+    next_sn: TransportSn,
+    bytes: Vec<u8>,
+    /// Frame and sequence number of the fragments.
+    fragments: Vec<(u32, TransportSn)>,
+}
+
+/// A network message reassembled from its fragments.
+#[derive(Debug, Clone)]
+pub(crate) struct Reassembled {
+    pub fragments: usize,
+    /// Frames of the fragments, in order.
+    pub frames: Vec<u32>,
+    pub length: usize,
+    /// `None` if the reassembled bytes don't decode as a network message.
+    pub msg: Option<NetworkMessage>,
+}
+
+/// Frame number, reliability, priority and sequence number of a fragment.
+type FragmentKey = (u32, bool, u8, TransportSn);
+
+thread_local! {
+    static PENDING: RefCell<HashMap<ChannelKey, PendingMessage>> = RefCell::default();
+    /// Reassembled messages, keyed by their last fragment.
+    static REASSEMBLED: RefCell<HashMap<FragmentKey, Reassembled>> = RefCell::default();
+    /// Frame of the last fragment, keyed by the other fragments.
+    static REASSEMBLED_IN: RefCell<HashMap<FragmentKey, u32>> = RefCell::default();
+    /// Resolution of the frame sequence numbers, keyed by conversation index.
+    static RESOLUTIONS: RefCell<HashMap<u32, Bits>> = RefCell::default();
+}
+
+/// Clear the collected fragments.
+pub(crate) fn init_fragments() {
+    PENDING.with_borrow_mut(|pending| pending.clear());
+    REASSEMBLED.with_borrow_mut(|reassembled| reassembled.clear());
+    REASSEMBLED_IN.with_borrow_mut(|reassembled_in| reassembled_in.clear());
+    RESOLUTIONS.with_borrow_mut(|resolutions| resolutions.clear());
+}
+
+fn fragment(msg: &TransportMessage) -> Option<&Fragment> {
+    match &msg.body {
+        TransportBody::Fragment(fragment) => Some(fragment),
+        _ => None,
+    }
+}
+
+unsafe fn fragment_key(pinfo: *mut epan_sys::_packet_info, fragment: &Fragment) -> FragmentKey {
+    (
+        (*pinfo).num,
+        fragment.reliability == Reliability::Reliable,
+        fragment.ext_qos.priority() as u8,
+        fragment.sn,
+    )
+}
+
+/// Record the frame sequence number resolution proposed by an InitSyn, or the one an InitAck
+/// settles on.
+unsafe fn record_resolution(pinfo: *mut epan_sys::_packet_info, msg: &TransportMessage) {
+    let (resolution, negotiated) = match &msg.body {
+        TransportBody::InitSyn(init_syn) => (init_syn.resolution, false),
+        TransportBody::InitAck(init_ack) => (init_ack.resolution, true),
+        _ => return,
+    };
+    let Some(conv_index) = session::conversation_index(pinfo) else {
+        return;
+    };
+    let bits = resolution.get(Field::FrameSN);
+    RESOLUTIONS.with_borrow_mut(|resolutions| {
+        if negotiated {
+            resolutions.insert(conv_index, bits);
+        } else {
+            resolutions.entry(conv_index).or_insert(bits);
+        }
+    });
+}
+
+/// Returns the sequence number following `sn` on the conversation of this packet, wrapping
+/// around at its resolution, or the default one if its handshake wasn't captured.
+unsafe fn next_sn(pinfo: *mut epan_sys::_packet_info, sn: TransportSn) -> TransportSn {
+    let bits = session::conversation_index(pinfo)
+        .and_then(|conv_index| {
+            RESOLUTIONS.with_borrow(|resolutions| resolutions.get(&conv_index).copied())
+        })
+        .unwrap_or_else(|| Bits::from(TransportSn::MAX));
+    following_sn(sn, bits)
+}
+
+fn following_sn(sn: TransportSn, bits: Bits) -> TransportSn {
+    (sn.wrapping_add(1) as u64 & bits.mask()) as TransportSn
+}
+
+/// Collect the fragment carried by a transport message, reassembling the network message it
+/// completes.
+pub(crate) unsafe fn update_state(pinfo: *mut epan_sys::_packet_info, msg: &TransportMessage) {
+    record_resolution(pinfo, msg);
+    let Some(fragment) = fragment(msg) else {
+        return;
+    };
+    let Some(side) = key_expr::declaring_side(pinfo, Mapping::Sender) else {
+        return;
+    };
+    let (frame, reliable, priority, sn) = fragment_key(pinfo, fragment);
+    collect(
+        (side, reliable, priority),
+        frame,
+        fragment,
+        next_sn(pinfo, sn),
+    );
+}
+
+/// Collect a fragment sent on a channel in a frame, given the sequence number expected after it.
+pub(crate) fn collect(channel: ChannelKey, frame: u32, fragment: &Fragment, next_sn: TransportSn) {
+    let (_, reliable, priority) = channel;
+    let sn = fragment.sn;
+    PENDING.with_borrow_mut(|pending| {
+        if fragment.ext_drop.is_some() {
+            pending.remove(&channel);
+            return;
+        }
+        if fragment.ext_first.is_some() {
+            pending.remove(&channel);
+        }
+        let message = pending.entry(channel.clone()).or_default();
+        if !message.fragments.is_empty() && message.next_sn != sn {
+            // A fragment is missing, the message can't be reassembled.
+            pending.remove(&channel);
+            return;
+        }
+        message.next_sn = next_sn;
+        message.bytes.extend_from_slice(&fragment.payload);
+        message.fragments.push((frame, sn));
+        if fragment.more {
+            return;
+        }
+
+        let Some(message) = pending.remove(&channel) else {
+            return;
+        };
+        let mut reader = message.bytes.as_slice();
+        let msg = Zenoh080Reliability::new(fragment.reliability)
+            .read(&mut reader)
+            .ok();
+        let mut frames = Vec::new();
+        REASSEMBLED_IN.with_borrow_mut(|reassembled_in| {
+            for &(fragment_frame, fragment_sn) in &message.fragments {
+                if fragment_sn != sn {
+                    reassembled_in.insert((fragment_frame, reliable, priority, fragment_sn), frame);
+                }
+                if frames.last() != Some(&fragment_frame) {
+                    frames.push(fragment_frame);
+                }
+            }
+        });
+        REASSEMBLED.with_borrow_mut(|reassembled| {
+            reassembled.insert(
+                (frame, reliable, priority, sn),
+                Reassembled {
+                    fragments: message.fragments.len(),
+                    frames,
+                    length: message.bytes.len(),
+                    msg,
+                },
+            )
+        });
+    });
+}
+
+/// Returns the network message completed by the fragment of a transport message, if any.
+pub(crate) fn reassembled(
+    pinfo: *mut epan_sys::_packet_info,
+    msg: &TransportMessage,
+) -> Option<Reassembled> {
+    let key = unsafe { fragment_key(pinfo, fragment(msg)?) };
+    REASSEMBLED.with_borrow(|reassembled| reassembled.get(&key).cloned())
+}
+
+/// Add the reassembly subtree below a `Fragment` message: the frame completing the network
+/// message, or the reassembled message in the frame of the last fragment.
+pub(crate) fn add_to_tree(msg: &TransportMessage, args: &TreeArgs) -> Result<()> {
+    let Some(fragment) = fragment(msg) else {
+        return Ok(());
+    };
+    let key = unsafe { fragment_key(args.pinfo, fragment) };
+
+    if let Some(frame) =
+        REASSEMBLED_IN.with_borrow(|reassembled_in| reassembled_in.get(&key).copied())
+    {
+        let args = args.make_subtree(
+            FIELD_FRAGMENT,
+            &format!("Fragment, Reassembled in: {frame}"),
+        )?;
+        set_generated(args.add_frame(FIELD_REASSEMBLED_IN, frame)?);
+        return Ok(());
+    }
+    let Some(reassembled) = reassembled(args.pinfo, msg) else {
+        return Ok(());
+    };
+
+    let args = args.make_subtree(
+        FIELD_FRAGMENT,
+        &format!(
+            "Reassembled NetworkMessage, Fragments: {}, Len: {}",
+            reassembled.fragments, reassembled.length
+        ),
+    )?;
+    set_generated(args.add_number(FIELD_COUNT, reassembled.fragments as u64)?);
+    set_generated(args.add_number(FIELD_LENGTH, reassembled.length as u64)?);
+    for frame in &reassembled.frames {
+        set_generated(args.add_frame(FIELD_FRAGMENT_IN, *frame)?);
+    }
+    match &reassembled.msg {
+        Some(msg) => {
+            msg.add_to_tree(NETWORK_PREFIX, &args)?;
+            if let Some(payload) = Payload::from_network_message(msg) {
+                payload.dissect(&args)?;
+            }
+        }
+        None => expert::add(
+            args.pinfo,
+            args.tree,
+            expert::EI_MALFORMED_FRAGMENTS,
+            "Reassembled fragments don't decode as a network message",
+        )?,
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use zenoh_buffers::writer::HasWriter;
+    use zenoh_codec::{WCodec, Zenoh080};
+    use zenoh_protocol::{
+        network::{response, NetworkBody, ResponseFinal},
+        transport::{fragment::ext, TransportSn},
+    };
+
+    use super::*;
+
+    const RELIABLE: bool = true;
+    const PRIORITY: u8 = 5;
+
+    fn response_final(rid: u32) -> NetworkMessage {
+        NetworkBody::ResponseFinal(ResponseFinal {
+            rid,
+            ext_qos: response::ext::QoSType::DEFAULT,
+            ext_tstamp: None,
+        })
+        .into()
+    }
+
+    fn encode(msg: &NetworkMessage) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        Zenoh080::new().write(&mut bytes.writer(), msg).unwrap();
+        bytes
+    }
+
+    fn new_fragment(payload: &[u8], sn: TransportSn, more: bool) -> Fragment {
+        Fragment {
+            reliability: Reliability::Reliable,
+            more,
+            sn,
+            payload: payload.to_vec().into(),
+            ext_qos: Default::default(),
+            ext_first: None,
+            ext_drop: None,
+        }
+    }
+
+    /// Split a network message into fragments of a byte each, numbered from `sn`.
+    fn split(msg: &NetworkMessage, sn: TransportSn, bits: Bits) -> Vec<Fragment> {
+        let bytes = encode(msg);
+        let mut sn = sn;
+        let mut fragments = Vec::new();
+        for (index, byte) in bytes.iter().enumerate() {
+            fragments.push(new_fragment(&[*byte], sn, index + 1 < bytes.len()));
+            sn = following_sn(sn, bits);
+        }
+        fragments
+    }
+
+    fn session(zid: &str) -> ChannelKey {
+        (Side::Session(1, zid.to_string()), RELIABLE, PRIORITY)
+    }
+
+    fn collect_in(channel: &ChannelKey, frame: u32, fragment: &Fragment, bits: Bits) {
+        collect(
+            channel.clone(),
+            frame,
+            fragment,
+            following_sn(fragment.sn, bits),
+        );
+    }
+
+    fn reassembled_in(frame: u32, fragment: &Fragment) -> Option<Reassembled> {
+        REASSEMBLED.with_borrow(|reassembled| {
+            reassembled
+                .get(&(frame, RELIABLE, PRIORITY, fragment.sn))
+                .cloned()
+        })
+    }
+
+    #[test]
+    fn sn_wraps_around_at_resolution() {
+        assert_eq!(following_sn(0xff, Bits::U8), 0);
+        assert_eq!(following_sn(0xff, Bits::U16), 0x100);
+        assert_eq!(following_sn(0xffff_ffff, Bits::U32), 0);
+        assert_eq!(following_sn(0xffff_ffff, Bits::from(TransportSn::MAX)), 0);
+    }
+
+    #[test]
+    fn reassemble_across_frames() {
+        let msg = response_final(0xdead_beef);
+        let fragments = split(&msg, 10, Bits::U32);
+        assert!(fragments.len() > 1);
+        let channel = session("a");
+        for (frame, fragment) in (1..).zip(&fragments) {
+            collect_in(&channel, frame, fragment, Bits::U32);
+        }
+
+        let last = fragments.len() as u32;
+        let reassembled = reassembled_in(last, fragments.last().unwrap()).unwrap();
+        assert_eq!(reassembled.fragments, fragments.len());
+        assert_eq!(reassembled.frames, (1..=last).collect::<Vec<_>>());
+        assert_eq!(reassembled.length, encode(&msg).len());
+        assert_eq!(reassembled.msg, Some(msg));
+        REASSEMBLED_IN.with_borrow(|reassembled_in| {
+            assert_eq!(
+                reassembled_in.get(&(1, RELIABLE, PRIORITY, 10)),
+                Some(&last)
+            );
+        });
+    }
+
+    #[test]
+    fn reassemble_across_sn_wrap_around() {
+        let msg = response_final(0xdead_beef);
+        for (bits, reassembles) in [(Bits::U8, true), (Bits::U16, false)] {
+            init_fragments();
+            // The sequence numbers wrap around after the first fragment with an 8 bit resolution.
+            let mut fragments = split(&msg, 0xff, Bits::U8);
+            let last = fragments.pop().unwrap();
+            let channel = session("a");
+            for fragment in &fragments {
+                collect_in(&channel, 1, fragment, bits);
+            }
+            collect_in(&channel, 2, &last, bits);
+            let reassembled = reassembled_in(2, &last).and_then(|r| r.msg);
+            assert_eq!(reassembled == Some(msg.clone()), reassembles, "{bits:?}");
+        }
+    }
+
+    #[test]
+    fn discard_on_gap() {
+        // The second fragment was lost.
+        let msg = response_final(0xdead_beef);
+        let fragments = split(&msg, 10, Bits::U32);
+        let channel = session("a");
+        for (frame, fragment) in (1..).zip(&fragments).filter(|(frame, _)| *frame != 2) {
+            collect_in(&channel, frame, fragment, Bits::U32);
+        }
+        REASSEMBLED_IN.with_borrow(|reassembled_in| {
+            assert!(!reassembled_in.contains_key(&(1, RELIABLE, PRIORITY, 10)));
+        });
+        let last = reassembled_in(fragments.len() as u32, fragments.last().unwrap());
+        assert!(last.is_none_or(|r| !r.frames.contains(&1) && r.msg != Some(msg)));
+    }
+
+    #[test]
+    fn first_extension_starts_a_new_message() {
+        let msg = response_final(0xdead_beef);
+        let channel = session("a");
+        collect_in(&channel, 1, &new_fragment(&[0xff], 1, true), Bits::U32);
+
+        let mut fragments = split(&msg, 20, Bits::U32);
+        fragments[0].ext_first = Some(ext::First::new());
+        for (frame, fragment) in (2..).zip(&fragments) {
+            collect_in(&channel, frame, fragment, Bits::U32);
+        }
+        let last = fragments.len() as u32 + 1;
+        let reassembled = reassembled_in(last, fragments.last().unwrap()).unwrap();
+        assert_eq!(reassembled.frames, (2..=last).collect::<Vec<_>>());
+        assert_eq!(reassembled.msg, Some(msg));
+    }
+
+    #[test]
+    fn drop_extension_discards_the_message() {
+        let channel = session("a");
+        let fragments = split(&response_final(0xdead_beef), 10, Bits::U32);
+        let (first, rest) = fragments.split_first().unwrap();
+        collect_in(&channel, 1, first, Bits::U32);
+        let mut dropped = rest[0].clone();
+        dropped.ext_drop = Some(ext::Drop::new());
+        collect_in(&channel, 2, &dropped, Bits::U32);
+        for fragment in &rest[1..] {
+            collect_in(&channel, 3, fragment, Bits::U32);
+        }
+        REASSEMBLED_IN.with_borrow(|reassembled_in| {
+            assert!(!reassembled_in.contains_key(&(1, RELIABLE, PRIORITY, first.sn)));
+        });
+        assert!(reassembled_in(3, fragments.last().unwrap()).is_none_or(|r| r.frames == [3]));
+    }
+
+    #[test]
+    fn channels_are_keyed_by_side_across_links() {
+        // The links of a multilink session share the sequence numbers of each side, while the
+        // other side, reliability and priority each have their own.
+        let (a, b) = (response_final(0xdead_beef), response_final(0xcafe_f00d));
+        let a_fragments = split(&a, 10, Bits::U32);
+        let b_fragments = split(&b, 10, Bits::U32);
+        let channels = [
+            session("a"),
+            session("b"),
+            (Side::Session(1, "a".to_string()), RELIABLE, PRIORITY + 1),
+        ];
+        let n = a_fragments.len();
+        for (index, (a_fragment, b_fragment)) in a_fragments.iter().zip(&b_fragments).enumerate() {
+            // Each fragment of the first side is sent on another link of the session.
+            let frame = 10 * index as u32;
+            collect_in(&channels[0], frame + 1, a_fragment, Bits::U32);
+            collect_in(&channels[1], frame + 2, b_fragment, Bits::U32);
+            if index + 1 < n {
+                collect_in(&channels[2], frame + 3, b_fragment, Bits::U32);
+            }
+        }
+
+        let frame = 10 * (n as u32 - 1);
+        let a_reassembled = reassembled_in(frame + 1, &a_fragments[n - 1]).unwrap();
+        assert_eq!(a_reassembled.msg, Some(a));
+        let b_reassembled = reassembled_in(frame + 2, &b_fragments[n - 1]).unwrap();
+        assert_eq!(b_reassembled.msg, Some(b));
+        REASSEMBLED.with_borrow(|reassembled| assert_eq!(reassembled.len(), 2));
+    }
+}
@@ -0,0 +1,496 @@
+//! Validation of the session handshake: `InitSyn`, `InitAck`, `OpenSyn` then `OpenAck`.
+//!
+//! Each conversation's handshake is checked during the first pass: the order of its steps, the
+//! protocol versions, the extensions offered by the `InitSyn` against those accepted by the
+//! `InitAck`, and the cookie echoed by the `OpenSyn`. The findings are recorded per frame, so
+//! that re-dissecting any frame shows the same expert info.
+
+use std::{cell::RefCell, collections::HashMap};
+
+use anyhow::Result;
+use zenoh_protocol::transport::{TransportBody, TransportMessage};
+
+use crate::{
+    expert,
+    header_field::{FieldKind, HeaderFieldMap, Registration},
+    timestamp::{capture_time_nanos, to_nstime},
+    tree::{set_generated, TreeArgs},
+    utils::first_in_frame,
+    PROTO_DATA_KEY_EARLY_DATA,
+};
+
+pub const FIELD_HANDSHAKE: &str = "zenoh.handshake";
+pub const FIELD_STARTED_IN: &str = "zenoh.handshake.started_in";
+pub const FIELD_LATENCY: &str = "zenoh.handshake.latency";
+
+/// Names of the extensions negotiated by the `InitSyn` and `InitAck` of a handshake.
+macro_rules! negotiated_extensions {
+    ($msg:expr) => {
+        [
+            ("QoS", $msg.ext_qos.is_some()),
+            ("QoS Link", $msg.ext_qos_link.is_some()),
+            ("Shared Memory", $msg.ext_shm.is_some()),
+            ("Authentication", $msg.ext_auth.is_some()),
+            ("Multilink", $msg.ext_mlink.is_some()),
+            ("Low Latency", $msg.ext_lowlatency.is_some()),
+            ("Compression", $msg.ext_compression.is_some()),
+        ]
+        .into_iter()
+        .filter_map(|(name, present)| present.then_some(name))
+        .collect::<Vec<_>>()
+    };
+}
+
+/// The handshake header fields, added below handshake messages and early data.
+pub struct Handshake;
+
+impl Registration for Handshake {
+    fn generate_hf_map(prefix: &str) -> HeaderFieldMap {
+        HeaderFieldMap::new()
+            .add(prefix.to_string(), "Handshake", FieldKind::Branch)
+            .add(
+                format!("{prefix}.started_in"),
+                "Started in",
+                FieldKind::FrameNum,
+            )
+            .add(
+                format!("{prefix}.latency"),
+                "Latency",
+                FieldKind::RelativeTime,
+            )
+    }
+
+    fn generate_subtree_names(prefix: &str) -> Vec<String> {
+        vec![prefix.to_string()]
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Step {
+    InitSyn,
+    InitAck,
+    OpenSyn,
+    OpenAck,
+}
+
+impl Step {
+    fn of(msg: &TransportMessage) -> Option<Self> {
+        match &msg.body {
+            TransportBody::InitSyn(_) => Some(Self::InitSyn),
+            TransportBody::InitAck(_) => Some(Self::InitAck),
+            TransportBody::OpenSyn(_) => Some(Self::OpenSyn),
+            TransportBody::OpenAck(_) => Some(Self::OpenAck),
+            _ => None,
+        }
+    }
+
+    fn previous(self) -> Option<Self> {
+        match self {
+            Self::InitSyn => None,
+            Self::InitAck => Some(Self::InitSyn),
+            Self::OpenSyn => Some(Self::InitAck),
+            Self::OpenAck => Some(Self::OpenSyn),
+        }
+    }
+}
+
+/// Progress of the latest handshake of a conversation.
+#[derive(Debug, Default)]
+struct HandshakeState {
+    /// Frame and capture time in nanoseconds of the `InitSyn`.
+    started: Option<(u32, i128)>,
+    /// Latest step seen.
+    step: Option<Step>,
+    version: Option<u8>,
+    /// Extensions offered by the `InitSyn`.
+    offered: Vec<&'static str>,
+    /// Cookie of the `InitAck`.
+    cookie: Option<Vec<u8>>,
+}
+
+/// What the validation found about a message, keyed by frame and message kind.
+#[derive(Debug, Default)]
+struct Report {
+    /// Frame of the `InitSyn` and handshake latency in nanoseconds, on the `OpenAck`.
+    latency: Option<(u32, i128)>,
+    /// Expert info names and messages.
+    issues: Vec<(&'static str, String)>,
+}
+
+thread_local! {
+    static HANDSHAKES: RefCell<HashMap<u32, HandshakeState>> = RefCell::default();
+    static REPORTS: RefCell<HashMap<(u32, &'static str), Report>> = RefCell::default();
+}
+
+/// Clear the recorded handshakes.
+pub(crate) fn init_handshakes() {
+    HANDSHAKES.with_borrow_mut(|handshakes| handshakes.clear());
+    REPORTS.with_borrow_mut(|reports| reports.clear());
+}
+
+/// Kind of the reports of data messages, shared by the messages of a frame.
+const DATA: &str = "Data";
+
+/// Kind of the messages that get a report, i.e. handshake messages and data.
+fn report_kind(msg: &TransportMessage) -> Option<&'static str> {
+    match &msg.body {
+        TransportBody::InitSyn(_) => Some("InitSyn"),
+        TransportBody::InitAck(_) => Some("InitAck"),
+        TransportBody::OpenSyn(_) => Some("OpenSyn"),
+        TransportBody::OpenAck(_) => Some("OpenAck"),
+        TransportBody::Frame(_) | TransportBody::Fragment(_) => Some(DATA),
+        _ => None,
+    }
+}
+
+/// Validate a transport message against the handshake of its conversation.
+pub(crate) unsafe fn update_state(pinfo: *mut epan_sys::_packet_info, msg: &TransportMessage) {
+    let Some(kind) = report_kind(msg) else {
+        return;
+    };
+    let conv = epan_sys::find_conversation_pinfo(pinfo, 0);
+    if conv.is_null() {
+        return;
+    }
+    let frame = (*pinfo).num;
+    let now = capture_time_nanos(pinfo);
+
+    let report = HANDSHAKES.with_borrow_mut(|handshakes| {
+        let state = handshakes.entry((*conv).conv_index).or_default();
+        validate(state, msg, frame, now)
+    });
+    if report.latency.is_some() || !report.issues.is_empty() {
+        REPORTS.with_borrow_mut(|reports| {
+            let entry = reports.entry((frame, kind)).or_default();
+            entry.latency = entry.latency.or(report.latency);
+            for issue in report.issues {
+                if !entry.issues.contains(&issue) {
+                    entry.issues.push(issue);
+                }
+            }
+        });
+    }
+}
+
+fn validate(state: &mut HandshakeState, msg: &TransportMessage, frame: u32, now: i128) -> Report {
+    let mut report = Report::default();
+
+    let Some(step) = Step::of(msg) else {
+        // Data is only expected once a started handshake completed.
+        if state.started.is_some() && state.step != Some(Step::OpenAck) {
+            report.issues.push((
+                expert::EI_DATA_BEFORE_OPEN,
+                "Data sent before the handshake completed with an OpenAck".to_string(),
+            ));
+        }
+        return report;
+    };
+
+    if step == Step::InitSyn {
+        if state.step.is_some_and(|s| s != Step::OpenAck) {
+            let since = state
+                .started
+                .map_or(String::new(), |(f, _)| format!(" started in frame {f}"));
+            report.issues.push((
+                expert::EI_HANDSHAKE_OUT_OF_ORDER,
+                format!("InitSyn restarts the pending handshake{since}"),
+            ));
+        }
+        *state = HandshakeState {
+            started: Some((frame, now)),
+            ..Default::default()
+        };
+    } else if state.step != step.previous() {
+        let expected = step.previous().map_or("nothing", step_name);
+        let seen = state.step.map_or("nothing", step_name);
+        report.issues.push((
+            expert::EI_HANDSHAKE_OUT_OF_ORDER,
+            format!("{} follows {seen} instead of {expected}", step_name(step)),
+        ));
+    }
+    state.step = Some(step);
+
+    match &msg.body {
+        TransportBody::InitSyn(init_syn) => {
+            state.version = Some(init_syn.version);
+            state.offered = negotiated_extensions!(init_syn);
+        }
+        TransportBody::InitAck(init_ack) => {
+            if let Some(version) = state.version.filter(|v| *v != init_ack.version) {
+                report.issues.push((
+                    expert::EI_VERSION_MISMATCH,
+                    format!(
+                        "InitAck version {:#04x} differs from InitSyn version {version:#04x}",
+                        init_ack.version
+                    ),
+                ));
+            }
+            let accepted = negotiated_extensions!(init_ack);
+            if state.started.is_some() {
+                for name in state.offered.iter().filter(|n| !accepted.contains(n)) {
+                    report.issues.push((
+                        expert::EI_EXTENSION_DECLINED,
+                        format!("{name} extension offered by the InitSyn but not accepted"),
+                    ));
+                }
+                for name in accepted.iter().filter(|n| !state.offered.contains(n)) {
+                    report.issues.push((
+                        expert::EI_EXTENSION_UNSOLICITED,
+                        format!("{name} extension accepted but not offered by the InitSyn"),
+                    ));
+                }
+            }
+            state.cookie = Some(init_ack.cookie.to_vec());
+        }
+        TransportBody::OpenSyn(open_syn)
+            if state
+                .cookie
+                .as_ref()
+                .is_some_and(|cookie| cookie[..] != open_syn.cookie[..]) =>
+        {
+            report.issues.push((
+                expert::EI_COOKIE_MISMATCH,
+                "OpenSyn cookie differs from the InitAck cookie".to_string(),
+            ));
+        }
+        TransportBody::OpenAck(_) => {
+            report.latency = state
+                .started
+                .map(|(started_in, started_at)| (started_in, now - started_at));
+        }
+        _ => {}
+    }
+    report
+}
+
+fn step_name(step: Step) -> &'static str {
+    match step {
+        Step::InitSyn => "InitSyn",
+        Step::InitAck => "InitAck",
+        Step::OpenSyn => "OpenSyn",
+        Step::OpenAck => "OpenAck",
+    }
+}
+
+/// Add the handshake subtree, with the latency and the issues found, below a handshake message
+/// or the first data message of a frame.
+pub(crate) fn add_to_tree(msg: &TransportMessage, args: &TreeArgs) -> Result<()> {
+    let Some(kind) = report_kind(msg) else {
+        return Ok(());
+    };
+    if kind == DATA && !unsafe { first_in_frame(args.pinfo, PROTO_DATA_KEY_EARLY_DATA) } {
+        return Ok(());
+    }
+    let frame = unsafe { (*args.pinfo).num };
+    REPORTS.with_borrow(|reports| {
+        let Some(report) = reports.get(&(frame, kind)) else {
+            return Ok(());
+        };
+        let label = match report.latency {
+            Some((_, latency)) => format!("Handshake, Latency: {:.3} ms", latency as f64 / 1e6),
+            None => "Handshake".to_string(),
+        };
+        let args = args.make_subtree(FIELD_HANDSHAKE, &label)?;
+        if let Some((started_in, latency)) = report.latency {
+            set_generated(args.add_frame(FIELD_STARTED_IN, started_in)?);
+            set_generated(args.add_time(FIELD_LATENCY, &to_nstime(latency))?);
+        }
+        // The subtree is the item it was added to.
+        for (name, message) in &report.issues {
+            expert::add(args.pinfo, args.tree, name, message)?;
+        }
+        Ok(())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use zenoh_codec::{RCodec, Zenoh080};
+    use zenoh_protocol::{
+        core::Reliability,
+        transport::{frame, Frame},
+    };
+
+    use super::*;
+    use crate::expert::*;
+
+    const VERSION: u8 = 0x09;
+    const COOKIE: &[u8] = b"cookie";
+    /// Extension headers of the `InitSyn` and `InitAck`.
+    const QOS: u8 = 0x01;
+    const LOWLATENCY: u8 = 0x05;
+    const COMPRESSION: u8 = 0x06;
+
+    fn decode(bytes: &[u8]) -> TransportMessage {
+        Zenoh080::new().read(&mut &bytes[..]).unwrap()
+    }
+
+    /// Returns the extension flag of a message header and the unit extensions, each but the last
+    /// one flagged as followed by another one.
+    fn exts(exts: &[u8]) -> (u8, Vec<u8>) {
+        let flag = if exts.is_empty() { 0 } else { 0x80 };
+        let mut exts = exts.to_vec();
+        let last = exts.len().saturating_sub(1);
+        for ext in exts.iter_mut().take(last) {
+            *ext |= 0x80;
+        }
+        (flag, exts)
+    }
+
+    fn init_syn(offered: &[u8]) -> TransportMessage {
+        let (z, offered) = exts(offered);
+        decode(&[&[0x01 | z, VERSION, 0x01, 0xaa][..], &offered].concat())
+    }
+
+    fn init_ack(version: u8, accepted: &[u8]) -> TransportMessage {
+        let (z, accepted) = exts(accepted);
+        let header = [0x21 | z, version, 0x01, 0xbb, COOKIE.len() as u8];
+        decode(&[&header[..], COOKIE, &accepted].concat())
+    }
+
+    fn open_syn(cookie: &[u8]) -> TransportMessage {
+        decode(&[&[0x02, 10, 0, cookie.len() as u8][..], cookie].concat())
+    }
+
+    fn open_ack() -> TransportMessage {
+        decode(&[0x22, 10, 0])
+    }
+
+    fn data() -> TransportMessage {
+        Frame {
+            reliability: Reliability::Reliable,
+            sn: 0,
+            ext_qos: frame::ext::QoSType::DEFAULT,
+            payload: vec![],
+        }
+        .into()
+    }
+
+    /// Messages of a conversation, with the expert infos each one is expected to get.
+    type Steps = Vec<(TransportMessage, &'static [&'static str])>;
+
+    /// Validate messages sent a millisecond apart from frame 1, returning the report of each.
+    fn validate_all(msgs: &[TransportMessage]) -> Vec<Report> {
+        let mut state = HandshakeState::default();
+        (1..)
+            .zip(msgs)
+            .map(|(frame, msg)| validate(&mut state, msg, frame, frame as i128 * 1_000_000))
+            .collect()
+    }
+
+    #[test]
+    fn validate_handshakes() {
+        let cases: [(&str, Steps); 7] = [
+            (
+                "in order",
+                vec![
+                    (init_syn(&[QOS]), &[]),
+                    (init_ack(VERSION, &[QOS]), &[]),
+                    (open_syn(COOKIE), &[]),
+                    (open_ack(), &[]),
+                    (data(), &[]),
+                ],
+            ),
+            (
+                "restarted",
+                vec![
+                    (init_syn(&[]), &[]),
+                    (init_ack(VERSION, &[]), &[]),
+                    (init_syn(&[]), &[EI_HANDSHAKE_OUT_OF_ORDER]),
+                    (init_ack(VERSION, &[]), &[]),
+                    (open_syn(COOKIE), &[]),
+                    (open_ack(), &[]),
+                ],
+            ),
+            (
+                "out of order",
+                vec![
+                    (init_syn(&[]), &[]),
+                    (open_syn(COOKIE), &[EI_HANDSHAKE_OUT_OF_ORDER]),
+                    (data(), &[EI_DATA_BEFORE_OPEN]),
+                    (open_ack(), &[]),
+                    (data(), &[]),
+                ],
+            ),
+            (
+                "captured from the InitAck",
+                vec![
+                    (init_ack(VERSION, &[QOS]), &[EI_HANDSHAKE_OUT_OF_ORDER]),
+                    (open_syn(COOKIE), &[]),
+                    (open_ack(), &[]),
+                ],
+            ),
+            (
+                "cookie mismatch",
+                vec![
+                    (init_syn(&[]), &[]),
+                    (init_ack(VERSION, &[]), &[]),
+                    (open_syn(b"forged"), &[EI_COOKIE_MISMATCH]),
+                    (open_ack(), &[]),
+                ],
+            ),
+            (
+                "extension declined",
+                vec![
+                    (init_syn(&[LOWLATENCY, COMPRESSION]), &[]),
+                    (
+                        init_ack(VERSION, &[QOS, COMPRESSION]),
+                        &[EI_EXTENSION_DECLINED, EI_EXTENSION_UNSOLICITED],
+                    ),
+                ],
+            ),
+            (
+                "version mismatch",
+                vec![
+                    (init_syn(&[]), &[]),
+                    (init_ack(VERSION + 1, &[]), &[EI_VERSION_MISMATCH]),
+                ],
+            ),
+        ];
+        for (name, steps) in cases {
+            let (msgs, expected): (Vec<_>, Vec<_>) = steps.into_iter().unzip();
+            for ((frame, report), expected) in (1..).zip(validate_all(&msgs)).zip(expected) {
+                let issues = report.issues.iter().map(|(ei, _)| *ei).collect::<Vec<_>>();
+                assert_eq!(issues, expected, "{name}, frame {frame}");
+            }
+        }
+    }
+
+    #[test]
+    fn validate_latency() {
+        let reports = validate_all(&[
+            init_syn(&[]),
+            init_ack(VERSION, &[]),
+            init_syn(&[]),
+            init_ack(VERSION, &[]),
+            open_syn(COOKIE),
+            open_ack(),
+        ]);
+        // Measured from the InitSyn restarting the handshake.
+        assert_eq!(reports[5].latency, Some((3, 3_000_000)));
+        assert!(reports[..5].iter().all(|report| report.latency.is_none()));
+        // Without the InitSyn, there's nothing to measure from.
+        let reports = validate_all(&[init_ack(VERSION, &[]), open_syn(COOKIE), open_ack()]);
+        assert_eq!(reports[2].latency, None);
+    }
+
+    #[test]
+    fn extension_issues() {
+        let reports = validate_all(&[
+            init_syn(&[LOWLATENCY, COMPRESSION]),
+            init_ack(VERSION, &[QOS, COMPRESSION]),
+        ]);
+        let messages = reports[1]
+            .issues
+            .iter()
+            .map(|(_, message)| message.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            messages,
+            [
+                "Low Latency extension offered by the InitSyn but not accepted",
+                "QoS extension accepted but not offered by the InitSyn",
+            ]
+        );
+    }
+}
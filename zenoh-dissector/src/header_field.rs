@@ -57,8 +57,15 @@ impl HeaderFieldMap {
 pub enum FieldKind {
     Text,
     Branch,
-    // Number,
-    // Bytes,
+    Number,
+    Bool,
+    Bytes,
+    AbsoluteTime,
+    RelativeTime,
+    /// A frame number, rendered as a link to the frame.
+    FrameNum,
+    /// A number displayed through a value-string table.
+    Enum(&'static [(u32, &'static str)]),
 }
 
 pub trait Registration {
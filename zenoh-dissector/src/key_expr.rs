@@ -0,0 +1,141 @@
+//! Resolution of wire expressions into full key expressions.
+//!
+//! A non-zero scope refers to a `DeclareKeyExpr` sent earlier by one side of the session:
+//! the sender of the message for [`Mapping::Sender`], its receiver for [`Mapping::Receiver`].
+//! Declarations are recorded per declaring [`Side`] during the first pass, with the frames they
+//! are valid for, so that re-dissecting any frame resolves the same way.
+
+use std::{cell::RefCell, collections::HashMap};
+
+use anyhow::Result;
+use zenoh_protocol::{
+    core::{ExprId, WireExpr},
+    network::{declare::DeclareBody, Mapping, NetworkBody},
+    transport::TransportMessage,
+};
+
+use crate::{
+    conversation,
+    session::{self, Side},
+    tree::{set_generated, TreeArgs},
+    utils::network_messages,
+};
+
+pub const FIELD_WIRE_EXPR: &str = "zenoh.wire_expr";
+pub const FIELD_KEY_EXPR: &str = "zenoh.key_expr";
+
+/// Declaring side and declared id.
+type DeclarationKey = (Side, ExprId);
+
+#[derive(Debug)]
+struct Declaration {
+    key_expr: String,
+    /// Frame of the `DeclareKeyExpr`.
+    declared: u32,
+    /// Frame of the matching `UndeclareKeyExpr`, if any.
+    undeclared: Option<u32>,
+}
+
+thread_local! {
+    static DECLARATIONS: RefCell<HashMap<DeclarationKey, Vec<Declaration>>> = RefCell::default();
+}
+
+/// Clear the recorded declarations.
+pub(crate) fn init_declarations() {
+    DECLARATIONS.with_borrow_mut(|declarations| declarations.clear());
+}
+
+/// Returns the side that declared the scope of a wire expression with the given mapping: its
+/// ZID within the session of the frame, shared by the links of a multilink session, or else its
+/// port within the conversation.
+pub(crate) unsafe fn declaring_side(
+    pinfo: *mut epan_sys::_packet_info,
+    mapping: Mapping,
+) -> Option<Side> {
+    let conv_index = session::conversation_index(pinfo)?;
+    let (zid, port) = match mapping {
+        Mapping::Sender => (conversation::source_zid(pinfo), (*pinfo).srcport),
+        Mapping::Receiver => (conversation::destination_zid(pinfo), (*pinfo).destport),
+    };
+    match (session::session_index(pinfo), zid) {
+        (Some(index), Some(zid)) => Some(Side::Session(index, zid)),
+        _ => Some(Side::Link(conv_index, port)),
+    }
+}
+
+/// Record the key expressions declared and undeclared by a transport message.
+pub(crate) unsafe fn update_state(pinfo: *mut epan_sys::_packet_info, msg: &TransportMessage) {
+    let Some(side) = declaring_side(pinfo, Mapping::Sender) else {
+        return;
+    };
+    let frame = (*pinfo).num;
+
+    for msg in &network_messages(pinfo, msg) {
+        let NetworkBody::Declare(declare) = &msg.body else {
+            continue;
+        };
+        match &declare.body {
+            DeclareBody::DeclareKeyExpr(decl) => {
+                let Some(key_expr) = resolve(pinfo, &decl.wire_expr) else {
+                    continue;
+                };
+                DECLARATIONS.with_borrow_mut(|declarations| {
+                    declarations
+                        .entry((side.clone(), decl.id))
+                        .or_default()
+                        .push(Declaration {
+                            key_expr,
+                            declared: frame,
+                            undeclared: None,
+                        })
+                });
+            }
+            DeclareBody::UndeclareKeyExpr(undecl) => {
+                DECLARATIONS.with_borrow_mut(|declarations| {
+                    if let Some(decl) = declarations
+                        .get_mut(&(side.clone(), undecl.id))
+                        .and_then(|decls| decls.last_mut())
+                    {
+                        decl.undeclared.get_or_insert(frame);
+                    }
+                });
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Resolve a wire expression into a full key expression, as of the frame being dissected.
+pub(crate) fn resolve(pinfo: *mut epan_sys::_packet_info, wire_expr: &WireExpr) -> Option<String> {
+    if wire_expr.scope == 0 {
+        return Some(wire_expr.suffix.to_string());
+    }
+
+    let side = unsafe { declaring_side(pinfo, wire_expr.mapping)? };
+    let frame = unsafe { (*pinfo).num };
+    let prefix = DECLARATIONS.with_borrow(|declarations| {
+        declarations
+            .get(&(side, wire_expr.scope))?
+            .iter()
+            .rev()
+            .find(|decl| {
+                decl.declared <= frame
+                    && decl.undeclared.is_none_or(|undeclared| frame < undeclared)
+            })
+            .map(|decl| decl.key_expr.clone())
+    })?;
+    Some(prefix + &wire_expr.suffix)
+}
+
+/// Add the resolved key expression below the wire expression text item.
+pub fn add_to_tree(
+    wire_expr: &WireExpr,
+    item: *mut epan_sys::proto_item,
+    args: &TreeArgs,
+) -> Result<()> {
+    if let Some(key_expr) = resolve(args.pinfo, wire_expr) {
+        let args = args.item_subtree(item, FIELD_WIRE_EXPR)?;
+        set_generated(args.add_text(FIELD_KEY_EXPR, &key_expr)?);
+    }
+    Ok(())
+}
@@ -0,0 +1,177 @@
+//! Lease monitoring: the time between consecutive messages of each peer, against the lease it
+//! announced in its `OpenSyn` or `OpenAck`.
+//!
+//! An idle peer sends a `KeepAlive` every lease divided by its keep-alive count, and the other
+//! side expires the session after hearing nothing from it for a whole lease. See `start_tx` and
+//! `start_rx` in `zenoh_transport::unicast::universal::transport`.
+
+use std::{cell::RefCell, collections::HashMap, time::Duration};
+
+use anyhow::Result;
+use zenoh_protocol::transport::{TransportBody, TransportMessage};
+
+use crate::{
+    expert,
+    header_field::{FieldKind, HeaderFieldMap, Registration},
+    session,
+    timestamp::{capture_time_nanos, to_nstime},
+    tree::{set_generated, TreeArgs},
+    utils::first_in_frame,
+    PROTO_DATA_KEY_LEASE,
+};
+
+pub const FIELD_LEASE: &str = "zenoh.lease";
+pub const FIELD_GAP: &str = "zenoh.lease.gap";
+pub const FIELD_PREVIOUS_IN: &str = "zenoh.lease.previous_in";
+pub const FIELD_PEER_LEASE: &str = "zenoh.lease.peer_lease";
+
+/// Number of keep-alive intervals a peer may stay silent before it is flagged, as idle peers
+/// send their `KeepAlive` right at the interval, give or take some jitter.
+const MISSED_KEEP_ALIVES: i128 = 2;
+
+/// The lease header fields, added below every message.
+pub struct Lease;
+
+impl Registration for Lease {
+    fn generate_hf_map(prefix: &str) -> HeaderFieldMap {
+        HeaderFieldMap::new()
+            .add(
+                format!("{prefix}.gap"),
+                "Time since Previous Message from Peer",
+                FieldKind::RelativeTime,
+            )
+            .add(
+                format!("{prefix}.previous_in"),
+                "Previous Message in",
+                FieldKind::FrameNum,
+            )
+            .add(
+                format!("{prefix}.peer_lease"),
+                "Lease of Peer",
+                FieldKind::RelativeTime,
+            )
+    }
+
+    fn generate_subtree_names(prefix: &str) -> Vec<String> {
+        vec![prefix.to_string()]
+    }
+}
+
+/// Time elapsed since the previous frame of the same peer, in nanoseconds.
+#[derive(Debug, Clone, Copy)]
+struct Gap {
+    previous_in: u32,
+    gap: i128,
+    /// Lease announced by the peer, if its handshake was captured.
+    lease: Option<i128>,
+}
+
+/// Conversation index and port of a peer.
+type Peer = (u32, u32);
+
+thread_local! {
+    static LEASES: RefCell<HashMap<Peer, i128>> = RefCell::default();
+    static LAST_SEEN: RefCell<HashMap<Peer, (u32, i128)>> = RefCell::default();
+    static GAPS: RefCell<HashMap<u32, Gap>> = RefCell::default();
+}
+
+/// Clear the recorded leases and gaps.
+pub(crate) fn init_leases() {
+    LEASES.with_borrow_mut(|leases| leases.clear());
+    LAST_SEEN.with_borrow_mut(|last_seen| last_seen.clear());
+    GAPS.with_borrow_mut(|gaps| gaps.clear());
+}
+
+fn nanos(duration: &Duration) -> i128 {
+    duration.as_nanos() as i128
+}
+
+/// Record the lease announced by a transport message and the gap since the previous frame of
+/// its sender.
+pub(crate) unsafe fn update_state(pinfo: *mut epan_sys::_packet_info, msg: &TransportMessage) {
+    // Every link of a multilink session has its own keep-alives.
+    let Some(conv_index) = session::conversation_index(pinfo) else {
+        return;
+    };
+    let peer = (conv_index, (*pinfo).srcport);
+    let frame = (*pinfo).num;
+    let now = capture_time_nanos(pinfo);
+
+    let lease = LEASES.with_borrow_mut(|leases| {
+        match &msg.body {
+            TransportBody::OpenSyn(open_syn) => {
+                leases.insert(peer, nanos(&open_syn.lease));
+            }
+            TransportBody::OpenAck(open_ack) => {
+                leases.insert(peer, nanos(&open_ack.lease));
+            }
+            _ => {}
+        }
+        leases.get(&peer).copied()
+    });
+
+    // Messages of the same frame share the gap of the first one.
+    let previous = LAST_SEEN.with_borrow_mut(|last_seen| last_seen.insert(peer, (frame, now)));
+    if let Some((previous_in, previous_at)) = previous.filter(|(f, _)| *f != frame) {
+        GAPS.with_borrow_mut(|gaps| {
+            gaps.insert(
+                frame,
+                Gap {
+                    previous_in,
+                    gap: now - previous_at,
+                    lease,
+                },
+            )
+        });
+    }
+}
+
+/// Add the time since the previous message of the sender below the first message of a frame,
+/// flagging gaps longer than [`MISSED_KEEP_ALIVES`] keep-alive intervals or its whole lease.
+pub(crate) fn add_to_tree(args: &TreeArgs) -> Result<()> {
+    if !unsafe { first_in_frame(args.pinfo, PROTO_DATA_KEY_LEASE) } {
+        return Ok(());
+    }
+    let frame = unsafe { (*args.pinfo).num };
+    let Some(gap) = GAPS.with_borrow(|gaps| gaps.get(&frame).copied()) else {
+        return Ok(());
+    };
+
+    let item = args.add_time(FIELD_GAP, &to_nstime(gap.gap))?;
+    set_generated(item);
+    let subtree = args.item_subtree(item, FIELD_LEASE)?;
+    set_generated(subtree.add_frame(FIELD_PREVIOUS_IN, gap.previous_in)?);
+    let Some(lease) = gap.lease else {
+        return Ok(());
+    };
+    set_generated(subtree.add_time(FIELD_PEER_LEASE, &to_nstime(lease))?);
+
+    let keep_alive = unsafe { crate::KEEP_ALIVE }.max(1) as i128;
+    let interval = lease / keep_alive;
+    let ms = |nanos: i128| nanos as f64 / 1e6;
+    if gap.gap > lease {
+        expert::add(
+            args.pinfo,
+            item,
+            expert::EI_LEASE_EXPIRED,
+            &format!(
+                "{:.3} ms since the previous message exceeds the {:.3} ms lease of the peer",
+                ms(gap.gap),
+                ms(lease)
+            ),
+        )?;
+    } else if gap.gap > MISSED_KEEP_ALIVES * interval {
+        expert::add(
+            args.pinfo,
+            item,
+            expert::EI_KEEP_ALIVE_MISSED,
+            &format!(
+                "{:.3} ms since the previous message exceeds {MISSED_KEEP_ALIVES} times the \
+                 {:.3} ms keep-alive interval of the peer",
+                ms(gap.gap),
+                ms(interval)
+            ),
+        )?;
+    }
+    Ok(())
+}
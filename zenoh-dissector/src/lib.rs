@@ -1,29 +1,63 @@
 use anyhow::Result;
 use header_field::{FieldKind, Registration};
-use std::{cell::RefCell, collections::HashMap, ffi::CString, slice, sync::LazyLock};
+use std::{borrow::Cow, cell::RefCell, collections::HashMap, ffi::CString, slice, sync::LazyLock};
 use tree::{AddToTree, TreeArgs};
-use utils::{new_rbatch, transport_message_summary, SizedSummary};
+use utils::{
+    batch_payload, network_messages_summary, new_rbatch, transport_message_summary, SizedSummary,
+    SummaryOptions,
+};
 use wireshark::register_header_field;
 use zenoh_impl::ZenohProtocol;
-use zenoh_protocol::transport::{BatchSize, TransportMessage};
+use zenoh_protocol::transport::{BatchSize, TransportBody, TransportMessage};
 use zenoh_transport::common::batch::Decode;
 
+mod alias;
+mod auth;
+mod cdr;
 mod conversation;
+mod dfilter;
+mod display;
+mod expert;
+mod export;
+mod exported_pdu;
+mod ext;
+mod fragment;
+mod handshake;
 mod header_field;
+mod key_expr;
+mod lease;
+mod linkstate;
+mod lowlatency;
 mod macros;
+mod payload;
+mod qos;
+mod query;
+mod ros2;
+mod ros2_graph;
+mod selector;
+mod serialization;
+mod session;
+mod tap;
+mod timestamp;
 mod tree;
+mod uat;
 mod utils;
 mod wireshark;
 mod ws_log;
 mod zenoh_impl;
 
-/// Max number of message summaries in one batch.
-const MAX_BATCH_SUMMARY: usize = 5;
-/// Max length of a single message summary string.
-const MSG_SUMMARY_LIMIT: usize = 30;
 /// Length of the batch size header prepended to each Zenoh batch in TCP streams.
 const BATCH_HEADER_LEN: usize = 2;
 
+/// How the transport messages of a PDU are framed.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Framing {
+    /// In a batch, possibly compressed.
+    Batch { compression: bool },
+    /// One after the other, in a session using the lowlatency transport.
+    LowLatency,
+}
+
 // Version symbols are generated at build time from Cargo.toml metadata
 include!(concat!(env!("OUT_DIR"), "/version.rs"));
 
@@ -35,6 +69,9 @@ struct ProtocolData {
     // subtree map
     st_map: HashMap<String, std::ffi::c_int>,
     handle: Option<epan_sys::dissector_handle_t>,
+    // payload sub-dissector tables, keyed by encoding mime name and id
+    encoding_table: Option<epan_sys::dissector_table_t>,
+    encoding_id_table: Option<epan_sys::dissector_table_t>,
 }
 
 thread_local! {
@@ -43,6 +80,15 @@ thread_local! {
 
 // Global variables for interacting wtih wireshark preference
 static mut IS_COMPRESSION: bool = false;
+static mut INFO_PRIORITY: bool = false;
+static mut INFO_DETAILS: bool = false;
+/// Max number of message summaries in one batch, 0 for all.
+static mut INFO_MAX_MESSAGES: u32 = 5;
+/// Max length of a single message summary string, 0 for no limit.
+static mut INFO_MAX_LENGTH: u32 = 30;
+static mut ROS2_TYPE_DIR: *const std::ffi::c_char = std::ptr::null();
+static mut ZENOH_EXT_HEURISTIC: bool = false;
+static mut KEEP_ALIVE: u32 = 4;
 static mut UDP_PORT: u32 = 7447;
 static mut TCP_PORT: u32 = 7447;
 static mut CURR_UDP_PORT: u32 = 7447;
@@ -59,6 +105,7 @@ extern "C" fn plugin_register() {
         PLUG.register_protoinfo = Some(register_protoinfo);
         PLUG.register_handoff = Some(register_handoff);
         epan_sys::proto_register_plugin(&raw const PLUG);
+        dfilter::register();
     }
 }
 
@@ -97,6 +144,8 @@ unsafe extern "C" fn prefs_callback() {
         });
         CURR_UDP_PORT = UDP_PORT;
     }
+
+    ros2::load_types();
 }
 
 fn register_zenoh_protocol() -> Result<()> {
@@ -134,13 +183,98 @@ fn register_zenoh_protocol() -> Result<()> {
             c"Is Zenoh message compressed".as_ptr(),
             &raw mut IS_COMPRESSION as _,
         );
+        epan_sys::prefs_register_bool_preference(
+            zenoh_module,
+            c"info_priority".as_ptr(),
+            c"Show Priority in Info".as_ptr(),
+            c"Show the QoS priority of each network message in the Info column".as_ptr(),
+            &raw mut INFO_PRIORITY as _,
+        );
+        epan_sys::prefs_register_bool_preference(
+            zenoh_module,
+            c"info_details".as_ptr(),
+            c"Detailed Info".as_ptr(),
+            c"Show the resolved key expression, request id and payload size of each network \
+              message in the Info column, along with the direction of the batch, e.g. \
+              A→B: Frame[Push demo/a (42B), Request #7 demo/q]"
+                .as_ptr(),
+            &raw mut INFO_DETAILS as _,
+        );
+        epan_sys::prefs_register_uint_preference(
+            zenoh_module,
+            c"info_max_messages".as_ptr(),
+            c"Messages in Info".as_ptr(),
+            c"Max number of transport messages of a batch summarized in the Info column, 0 for \
+              all"
+            .as_ptr(),
+            10 as _,
+            &raw mut INFO_MAX_MESSAGES as _,
+        );
+        epan_sys::prefs_register_uint_preference(
+            zenoh_module,
+            c"info_max_length".as_ptr(),
+            c"Message Summary Length".as_ptr(),
+            c"Max number of characters of the summary of a transport message in the Info \
+              column, 0 for no limit"
+                .as_ptr(),
+            10 as _,
+            &raw mut INFO_MAX_LENGTH as _,
+        );
+
+        epan_sys::prefs_register_directory_preference(
+            zenoh_module,
+            c"ros2_type_dir".as_ptr(),
+            c"ROS 2 Type Definitions".as_ptr(),
+            c"Directory searched for the ROS 2 .msg, .srv and .idl files used to decode CDR \
+              payloads, e.g. /opt/ros/jazzy/share"
+                .as_ptr(),
+            &raw mut ROS2_TYPE_DIR,
+        );
+        payload::register_keyexpr_dissectors(zenoh_module);
+        alias::register(zenoh_module);
+        epan_sys::prefs_register_bool_preference(
+            zenoh_module,
+            c"zenoh_ext_heuristic".as_ptr(),
+            c"Try zenoh-ext Deserialization".as_ptr(),
+            c"Try to decode payloads that no other dissector handles as zenoh-ext serialized \
+              data"
+                .as_ptr(),
+            &raw mut ZENOH_EXT_HEURISTIC as _,
+        );
+
+        epan_sys::prefs_register_uint_preference(
+            zenoh_module,
+            c"keep_alive".as_ptr(),
+            c"Keep-Alives per Lease".as_ptr(),
+            c"Number of KeepAlive messages an idle peer sends per lease, i.e. the \
+              transport/link/tx/keep_alive setting of zenoh"
+                .as_ptr(),
+            10 as _,
+            &raw mut KEEP_ALIVE as _,
+        );
+
+        epan_sys::register_init_routine(Some(init_state));
+
+        ros2_graph::register();
+        linkstate::register();
+        query::register(proto_id);
+        export::register(proto_id);
+        tap::register(proto_id);
+        exported_pdu::register(proto_id);
+        auth::register();
     }
 
     let hf_map = ZenohProtocol::generate_hf_map("zenoh");
     let subtree_names = ZenohProtocol::generate_subtree_names("zenoh");
 
+    unsafe { expert::register(proto_id)? };
+    let (encoding_table, encoding_id_table) = unsafe { payload::register_tables(proto_id) };
+    unsafe { serialization::register(proto_id) };
+
     PROTOCOL_DATA.with(|data| {
         data.borrow_mut().id = proto_id;
+        data.borrow_mut().encoding_table = Some(encoding_table);
+        data.borrow_mut().encoding_id_table = Some(encoding_id_table);
 
         // Header Field
         for (key, hf) in hf_map {
@@ -169,6 +303,28 @@ fn register_zenoh_protocol() -> Result<()> {
                 FieldKind::Text,
             )?,
         );
+        data.borrow_mut().hf_map.insert(
+            conversation::FIELD_SRCNAME.to_string(),
+            register_header_field(
+                proto_id,
+                "Source Name",
+                conversation::FIELD_SRCNAME,
+                FieldKind::Text,
+            )?,
+        );
+        data.borrow_mut().hf_map.insert(
+            conversation::FIELD_DSTNAME.to_string(),
+            register_header_field(
+                proto_id,
+                "Destination Name",
+                conversation::FIELD_DSTNAME,
+                FieldKind::Text,
+            )?,
+        );
+        data.borrow_mut().hf_map.insert(
+            conversation::FIELD_NAME.to_string(),
+            register_header_field(proto_id, "Name", conversation::FIELD_NAME, FieldKind::Text)?,
+        );
 
         // Subtree
         for name in subtree_names {
@@ -209,6 +365,9 @@ unsafe extern "C" fn register_handoff() {
         epan_sys::dissector_add_uint_with_preference(c"udp.port".as_ptr(), UDP_PORT as _, handle);
         data.borrow_mut().handle = Some(handle);
 
+        payload::add_builtin_dissectors();
+        session::register_teardown_listener();
+
         // See https://www.wireshark.org/docs/wsar_html/group__packet.html#gac1f89fb22ed3dd53cb3aecbc7b87a528
         epan_sys::heur_dissector_add(
             c"tcp".as_ptr(),
@@ -263,16 +422,31 @@ unsafe extern "C" fn dissect_zenoh(
 ///
 /// Reads the 2-byte little-endian batch size and returns `BATCH_HEADER_LEN + batch_size`.
 unsafe extern "C" fn get_pdu_len_zenoh_tcp(
-    _pinfo: *mut epan_sys::_packet_info,
+    pinfo: *mut epan_sys::_packet_info,
     tvb: *mut epan_sys::tvbuff,
     offset: std::ffi::c_int,
     _data: *mut std::ffi::c_void,
 ) -> std::ffi::c_uint {
-    let batch_size = epan_sys::tvb_get_letohs(tvb, offset) as std::ffi::c_uint;
-    (BATCH_HEADER_LEN as std::ffi::c_uint) + batch_size
+    if !session::is_lowlatency(pinfo) {
+        let batch_size = epan_sys::tvb_get_letohs(tvb, offset) as std::ffi::c_uint;
+        return (BATCH_HEADER_LEN as std::ffi::c_uint) + batch_size;
+    }
+    // Past an `OpenAck`, the header is longer than the one `tcp_dissect_pdus` was asked for, and
+    // 0 asks for one more segment.
+    let header_len = lowlatency::TCP_HEADER_LEN as std::ffi::c_uint;
+    if epan_sys::tvb_reported_length_remaining(tvb, offset) < header_len as std::ffi::c_int {
+        return 0;
+    }
+    let message_size = epan_sys::tvb_get_letohl(tvb, offset) as std::ffi::c_uint;
+    header_len.saturating_add(message_size)
 }
 
 const PROTO_DATA_KEY_FRAME: u32 = 0;
+/// Keys of the markers of [`utils::first_in_frame`].
+pub(crate) const PROTO_DATA_KEY_EARLY_DATA: u32 = 1;
+pub(crate) const PROTO_DATA_KEY_LEASE: u32 = 2;
+/// Key of the marker of the frames switching to the lowlatency transport.
+pub(crate) const PROTO_DATA_KEY_LOWLATENCY: u32 = 3;
 
 /// Per-frame state shared between `dissect_zenoh_tcp` calls for the same frame.
 /// Stored via `p_add_proto_data` so it survives when the TCP layer calls us
@@ -331,14 +505,20 @@ unsafe extern "C" fn dissect_zenoh_tcp(
         existing as *mut ZenohFrameData
     };
 
-    // `tcp_dissect_pdus` calls dissect_zenoh_pdu for each complete batch.
-    // Batch subtrees are added as siblings of the protocol tree on the frame tree.
+    // `tcp_dissect_pdus` calls dissect_pdu_zenoh_tcp for each complete batch, or each message of
+    // a lowlatency session. Batch subtrees are added as siblings of the protocol tree on the frame
+    // tree.
+    let header_len = if session::is_lowlatency(pinfo) {
+        lowlatency::TCP_HEADER_LEN
+    } else {
+        BATCH_HEADER_LEN
+    };
     epan_sys::tcp_dissect_pdus(
         tvb,
         pinfo,
         tree,
         true,
-        BATCH_HEADER_LEN as std::ffi::c_uint,
+        header_len as std::ffi::c_uint,
         Some(get_pdu_len_zenoh_tcp),
         Some(dissect_pdu_zenoh_tcp),
         std::ptr::null_mut(),
@@ -355,10 +535,10 @@ unsafe extern "C" fn dissect_zenoh_tcp(
     epan_sys::tvb_reported_length(tvb) as std::ffi::c_int
 }
 
-/// Dissect a single, complete Zenoh batch PDU.
+/// Dissect a single, complete Zenoh batch PDU, or transport message of a lowlatency session.
 ///
-/// The TVB contains exactly `BATCH_HEADER_LEN + batch_payload` bytes. `tcp_dissect_pdus`
-/// has already handled reassembly and framing.
+/// The TVB contains exactly `BATCH_HEADER_LEN + batch_payload` bytes, or `TCP_HEADER_LEN` and the
+/// message. `tcp_dissect_pdus` has already handled reassembly and framing.
 ///
 /// Batch subtrees are added to the frame `tree` as siblings of the "Zenoh Protocol" item.
 unsafe extern "C" fn dissect_pdu_zenoh_tcp(
@@ -366,94 +546,94 @@ unsafe extern "C" fn dissect_pdu_zenoh_tcp(
     pinfo: *mut epan_sys::_packet_info,
     tree: *mut epan_sys::_proto_node,
     _data: *mut std::ffi::c_void,
+) -> std::ffi::c_int {
+    if session::is_lowlatency(pinfo) {
+        return dissect_pdu(
+            tvb,
+            pinfo,
+            tree,
+            lowlatency::TCP_HEADER_LEN,
+            Framing::LowLatency,
+        );
+    }
+    let framing = Framing::Batch {
+        compression: IS_COMPRESSION,
+    };
+    dissect_pdu(tvb, pinfo, tree, BATCH_HEADER_LEN, framing)
+}
+
+/// Dissect a TCP PDU made of a length header of `header_len` bytes and the framed transport
+/// messages.
+unsafe fn dissect_pdu(
+    tvb: *mut epan_sys::tvbuff,
+    pinfo: *mut epan_sys::_packet_info,
+    tree: *mut epan_sys::_proto_node,
+    header_len: usize,
+    framing: Framing,
 ) -> std::ffi::c_int {
     let tvb_len = epan_sys::tvb_reported_length(tvb) as usize;
-    if tvb_len < BATCH_HEADER_LEN {
+    if tvb_len < header_len {
         return 0;
     }
 
-    // Skip the 2-byte length header; the rest is the batch payload.
-    let payload_len = tvb_len - BATCH_HEADER_LEN;
-    let payload_ptr = epan_sys::tvb_get_ptr(tvb, BATCH_HEADER_LEN as _, payload_len as _);
+    // Skip the length header; the rest is the batch payload.
+    let payload_len = tvb_len - header_len;
+    let payload_ptr = epan_sys::tvb_get_ptr(tvb, header_len as _, payload_len as _);
     let payload_slice = slice::from_raw_parts(payload_ptr, payload_len);
 
-    let msgs = {
-        let mut rbatch = match new_rbatch(payload_slice, IS_COMPRESSION) {
-            Ok(rbatch) => rbatch,
-            Err(err) => {
-                ws_log::message!("zenoh_tcp: {err} (no={})", (*pinfo).num);
-                return 0;
-            }
-        };
-        let mut msgs = Vec::new();
-
-        let mut offset: usize = 0;
-        while !rbatch.is_empty() {
-            let Ok((msg, len)): Result<(TransportMessage, BatchSize), _> = rbatch.decode() else {
-                ws_log::message!(
-                    "zenoh_tcp: failed to decode transport message (no={})",
-                    (*pinfo).num
-                );
-                return 0;
-            };
-
-            msgs.push(Message {
-                msg,
-                len: len as _,
-                offset,
-            });
-            offset += len as usize;
-        }
-
-        msgs
+    let Some((msgs, framing, bytes)) = decode_messages(pinfo, payload_slice, framing) else {
+        return 0;
+    };
+    let label = match framing {
+        Framing::Batch { .. } => "Batch",
+        Framing::LowLatency => "Low Latency",
     };
 
-    let summary = PROTOCOL_DATA.with(|data| {
+    PROTOCOL_DATA.with(|data| {
         let borrowed_data = data.borrow();
 
         // Add a batch subtree on the frame tree (sibling of "Zenoh Protocol").
         let batch_tree = TreeArgs {
             tree,
             tvb,
+            pinfo,
             hf_map: &borrowed_data.hf_map,
             st_map: &borrowed_data.st_map,
             start: 0,
             length: tvb_len,
         }
-        .make_subtree("zenoh.batch", &format!("Batch, Len: {payload_len}"))
-        .unwrap();
+        .make_subtree("zenoh.batch", &format!("{label}, Len: {payload_len}"));
+        let batch_tree = match batch_tree {
+            Ok(batch_tree) => batch_tree,
+            Err(err) => {
+                ws_log::message!("zenoh_batch: {err} (no={})", (*pinfo).num);
+                return;
+            }
+        };
 
         // Update conversation state (ZIDs) from this batch's messages.
         for m in &msgs {
-            conversation::update_state(pinfo, &m.msg);
+            update_state(pinfo, &m.msg);
+            session::switch_framing(pinfo, &m.msg);
         }
 
         for m in &msgs {
-            // Message offsets are relative to the batch payload; shift by BATCH_HEADER_LEN
+            // Message offsets are relative to the batch payload; shift by the header length
             // to make them relative to the TVB.
             let msg_tree = TreeArgs {
-                start: BATCH_HEADER_LEN + m.offset,
+                start: header_len + m.offset,
                 length: m.len,
                 ..batch_tree
             };
-            m.msg.add_to_tree("zenoh", &msg_tree).unwrap();
-        }
-
-        let mut batch_summary = SizedSummary::new(MAX_BATCH_SUMMARY);
-        for m in &msgs {
-            batch_summary.append(|| {
-                let mut s = transport_message_summary(&m.msg);
-                if s.len() > MSG_SUMMARY_LIMIT {
-                    s.truncate(MSG_SUMMARY_LIMIT);
-                    s += "...]";
-                }
-                s
-            });
+            if let Err(err) = dissect_message(pinfo, &m.msg, framing, &msg_tree) {
+                ws_log::message!("zenoh_message: {err} (no={})", (*pinfo).num);
+            }
         }
-        batch_summary
     });
 
-    let summary_c_str = CString::new(format!("{summary}")).unwrap();
+    exported_pdu::export_batch(tvb, pinfo, framing, &bytes);
+
+    let summary_c_str = utils::lossy_c_string(info_summary(pinfo, &msgs, framing));
     epan_sys::col_clear((*pinfo).cinfo, epan_sys::COL_INFO as _);
     epan_sys::col_add_str(
         (*pinfo).cinfo,
@@ -464,12 +644,32 @@ unsafe extern "C" fn dissect_pdu_zenoh_tcp(
     tvb_len as std::ffi::c_int
 }
 
-/// Dissect a Zenoh UDP datagram (entire payload is a single batch, no length prefix).
+/// Dissect a Zenoh UDP datagram (entire payload is a single batch, or the messages of a
+/// lowlatency session, no length prefix).
 unsafe extern "C" fn dissect_zenoh_udp(
     tvb: *mut epan_sys::tvbuff,
     pinfo: *mut epan_sys::_packet_info,
     tree: *mut epan_sys::_proto_node,
     _data: *mut std::ffi::c_void,
+) -> std::ffi::c_int {
+    let framing = if session::is_lowlatency(pinfo) {
+        Framing::LowLatency
+    } else {
+        Framing::Batch {
+            compression: IS_COMPRESSION,
+        }
+    };
+    dissect_batch(tvb, pinfo, tree, framing, None)
+}
+
+/// Dissect a single Zenoh batch without length prefix, along with the tags it was exported
+/// with, if any.
+unsafe fn dissect_batch(
+    tvb: *mut epan_sys::tvbuff,
+    pinfo: *mut epan_sys::_packet_info,
+    tree: *mut epan_sys::_proto_node,
+    framing: Framing,
+    tags: Option<&exported_pdu::Tags>,
 ) -> std::ffi::c_int {
     epan_sys::col_add_str(
         (*pinfo).cinfo,
@@ -485,38 +685,11 @@ unsafe extern "C" fn dissect_zenoh_udp(
     let tvb_ptr = epan_sys::tvb_get_ptr(tvb, 0, tvb_len as _);
     let tvb_slice = slice::from_raw_parts(tvb_ptr, tvb_len);
 
-    let msgs = {
-        let mut rbatch = match new_rbatch(tvb_slice, IS_COMPRESSION) {
-            Ok(rbatch) => rbatch,
-            Err(err) => {
-                ws_log::message!("zenoh_udp: {err} (no={})", (*pinfo).num);
-                return 0;
-            }
-        };
-        let mut msgs = Vec::new();
-
-        let mut offset: usize = 0;
-        while !rbatch.is_empty() {
-            let Ok((msg, len)): Result<(TransportMessage, BatchSize), _> = rbatch.decode() else {
-                ws_log::message!(
-                    "zenoh_udp: failed to decode transport message (no={})",
-                    (*pinfo).num
-                );
-                return 0;
-            };
-
-            msgs.push(Message {
-                msg,
-                len: len as _,
-                offset,
-            });
-            offset += len as usize;
-        }
-
-        msgs
+    let Some((msgs, framing, bytes)) = decode_messages(pinfo, tvb_slice, framing) else {
+        return 0;
     };
 
-    let summary = PROTOCOL_DATA.with(|data| {
+    PROTOCOL_DATA.with(|data| {
         let borrowed_data = data.borrow();
 
         let ti =
@@ -530,6 +703,7 @@ unsafe extern "C" fn dissect_zenoh_udp(
         let tree_args = TreeArgs {
             tree: zenoh_tree,
             tvb,
+            pinfo,
             hf_map: &borrowed_data.hf_map,
             st_map: &borrowed_data.st_map,
             start: 0,
@@ -537,9 +711,14 @@ unsafe extern "C" fn dissect_zenoh_udp(
         };
 
         for m in &msgs {
-            conversation::update_state(pinfo, &m.msg);
+            update_state(pinfo, &m.msg);
         }
         conversation::update_tree(tvb, pinfo, zenoh_tree, ti);
+        if let Some(tags) = tags {
+            if let Err(err) = exported_pdu::add_to_tree(tags, &tree_args) {
+                ws_log::message!("zenoh_exported_pdu: {err} (no={})", (*pinfo).num);
+            }
+        }
 
         for m in &msgs {
             let msg_tree = TreeArgs {
@@ -547,24 +726,15 @@ unsafe extern "C" fn dissect_zenoh_udp(
                 length: m.len,
                 ..tree_args
             };
-            m.msg.add_to_tree("zenoh", &msg_tree).unwrap();
-        }
-
-        let mut batch_summary = SizedSummary::new(MAX_BATCH_SUMMARY);
-        for m in &msgs {
-            batch_summary.append(|| {
-                let mut s = transport_message_summary(&m.msg);
-                if s.len() > MSG_SUMMARY_LIMIT {
-                    s.truncate(MSG_SUMMARY_LIMIT);
-                    s += "...]";
-                }
-                s
-            });
+            if let Err(err) = dissect_message(pinfo, &m.msg, framing, &msg_tree) {
+                ws_log::message!("zenoh_message: {err} (no={})", (*pinfo).num);
+            }
         }
-        batch_summary
     });
 
-    let summary_c_str = CString::new(format!("{summary}")).unwrap();
+    exported_pdu::export_batch(tvb, pinfo, framing, &bytes);
+
+    let summary_c_str = utils::lossy_c_string(info_summary(pinfo, &msgs, framing));
     epan_sys::col_clear((*pinfo).cinfo, epan_sys::COL_INFO as _);
     epan_sys::col_add_str(
         (*pinfo).cinfo,
@@ -575,6 +745,179 @@ unsafe extern "C" fn dissect_zenoh_udp(
     tvb_len as std::ffi::c_int
 }
 
+/// Clear the state of every module, called by wireshark whenever a capture is (re)loaded.
+unsafe extern "C" fn init_state() {
+    timestamp::init_skew_stats();
+    key_expr::init_declarations();
+    ros2_graph::init_tokens();
+    session::init_sessions();
+    handshake::init_handshakes();
+    lease::init_leases();
+    linkstate::init_zids();
+    query::init_requests();
+    fragment::init_fragments();
+    alias::init_aliases();
+}
+
+/// Update the state of every module from a transport message, in capture order on the first
+/// pass.
+unsafe fn update_state(pinfo: *mut epan_sys::_packet_info, msg: &TransportMessage) {
+    conversation::update_state(pinfo, msg);
+    if (*(*pinfo).fd).visited() != 0 {
+        return;
+    }
+    // Sessions scope the state of the modules below.
+    session::update_state(pinfo, msg);
+    // Fragments are reassembled before the modules below look at the network messages.
+    fragment::update_state(pinfo, msg);
+    key_expr::update_state(pinfo, msg);
+    ros2_graph::update_state(pinfo, msg);
+    handshake::update_state(pinfo, msg);
+    lease::update_state(pinfo, msg);
+    linkstate::update_state(pinfo, msg);
+    query::update_state(pinfo, msg);
+    alias::update_state(pinfo, msg);
+}
+
+/// Add a transport message to the tree along with the analyses of every module, and queue it
+/// on the taps.
+unsafe fn dissect_message(
+    pinfo: *mut epan_sys::_packet_info,
+    msg: &TransportMessage,
+    framing: Framing,
+    args: &TreeArgs,
+) -> Result<()> {
+    add_message_to_tree(msg, framing, args)?;
+    session::add_to_tree(msg, args)?;
+    handshake::add_to_tree(msg, args)?;
+    auth::add_to_tree(msg, args)?;
+    lease::add_to_tree(args)?;
+    linkstate::add_to_tree(msg, args)?;
+    selector::add_to_tree(msg, args)?;
+    query::add_to_tree(msg, args)?;
+    fragment::add_to_tree(msg, args)?;
+    ros2::dissect_messages(msg, args)?;
+    ros2_graph::tap_tokens(pinfo, msg);
+    linkstate::tap_link_states(pinfo, msg);
+    query::tap_replies(pinfo, msg);
+    export::tap_objects(pinfo, msg);
+    tap::tap_messages(pinfo, msg);
+    auth::tap_credentials(pinfo, msg);
+    payload::dissect_payloads(msg, args)?;
+    serialization::dissect_attachments(msg, args)
+}
+
+/// Decode the transport messages of a batch, or of a lowlatency PDU, with their offset, the
+/// framing they were actually decoded with, i.e. whether the batch was compressed, and the bytes
+/// they were decoded from, i.e. decompressed.
+unsafe fn decode_messages<'a>(
+    pinfo: *mut epan_sys::_packet_info,
+    bytes: &'a [u8],
+    framing: Framing,
+) -> Option<(Vec<Message>, Framing, Cow<'a, [u8]>)> {
+    let mut msgs = Vec::new();
+    let mut offset: usize = 0;
+    let mut push = |msg, len: usize| {
+        msgs.push(Message { msg, len, offset });
+        offset += len;
+    };
+
+    let (framing, bytes) = match framing {
+        Framing::Batch { compression } => {
+            let (payload, compressed) = batch_payload(bytes, compression);
+            let mut rbatch = match new_rbatch(&payload) {
+                Ok(rbatch) => rbatch,
+                Err(err) => {
+                    ws_log::message!("zenoh_batch: {err} (no={})", (*pinfo).num);
+                    return None;
+                }
+            };
+            while !rbatch.is_empty() {
+                let Ok((msg, len)): Result<(TransportMessage, BatchSize), _> = rbatch.decode()
+                else {
+                    ws_log::message!(
+                        "zenoh_batch: failed to decode transport message (no={})",
+                        (*pinfo).num
+                    );
+                    return None;
+                };
+                push(msg, len as usize);
+            }
+            let framing = Framing::Batch {
+                compression: compressed,
+            };
+            (framing, payload)
+        }
+        Framing::LowLatency => {
+            match lowlatency::decode(bytes) {
+                Ok(decoded) => decoded.into_iter().for_each(|(msg, len)| push(msg, len)),
+                Err(err) => {
+                    ws_log::message!("zenoh_lowlatency: {err} (no={})", (*pinfo).num);
+                    return None;
+                }
+            }
+            (Framing::LowLatency, bytes.into())
+        }
+    };
+    Some((msgs, framing, bytes))
+}
+
+/// Add a transport message, framed as it was received.
+fn add_message_to_tree(msg: &TransportMessage, framing: Framing, args: &TreeArgs) -> Result<()> {
+    match framing {
+        Framing::Batch { .. } => msg.add_to_tree("zenoh", args),
+        Framing::LowLatency => lowlatency::add_to_tree(msg, args),
+    }
+}
+
+/// Returns the Info column text of the transport messages of a batch, preceded by the names of
+/// their sender and receiver or, with detailed info, the direction of the batch.
+unsafe fn info_summary(
+    pinfo: *mut epan_sys::_packet_info,
+    msgs: &[Message],
+    framing: Framing,
+) -> String {
+    let options = SummaryOptions {
+        priority: INFO_PRIORITY,
+        details: INFO_DETAILS,
+    };
+    let max_length = INFO_MAX_LENGTH as usize;
+    let mut batch_summary = SizedSummary::new(match INFO_MAX_MESSAGES {
+        0 => usize::MAX,
+        max_messages => max_messages as usize,
+    });
+    for m in msgs {
+        batch_summary.append(|| {
+            let s = match (&m.msg.body, framing) {
+                // The network messages of a lowlatency session are sent without `Frame`.
+                (TransportBody::Frame(frame), Framing::LowLatency) => {
+                    network_messages_summary(pinfo, &frame.payload, options)
+                }
+                _ => transport_message_summary(pinfo, &m.msg, options),
+            };
+            if max_length == 0 || s.chars().count() <= max_length {
+                return s;
+            }
+            let ellipsis = if s.starts_with("Frame[") {
+                "...]"
+            } else {
+                "..."
+            };
+            s.chars().take(max_length).collect::<String>() + ellipsis
+        });
+    }
+
+    let prefix = conversation::info_names(pinfo).or_else(|| {
+        INFO_DETAILS
+            .then(|| conversation::direction(pinfo).map(str::to_string))
+            .flatten()
+    });
+    match prefix {
+        Some(prefix) => format!("{prefix}: {batch_summary}"),
+        None => batch_summary.to_string(),
+    }
+}
+
 /// A single decoded transport message with its position within the batch payload.
 #[derive(Debug, PartialEq)]
 struct Message {
@@ -0,0 +1,799 @@
+//! Decoding of the linkstate `Oam` messages exchanged by routers, and reconstruction of the
+//! router graph they describe.
+//!
+//! Every node of a link state list is queued on the `zenoh.linkstate` tap. The graph is shown by
+//! the `Zenoh/Router Topology` statistics tree (`-z zenoh.linkstate.topology,tree` in tshark) and
+//! printed as DOT by `-z zenoh.linkstate.dot` or as JSON by `-z zenoh.linkstate.json`. Snapshots of
+//! the graph as of every frame with link states are listed in `File > Export Objects > Zenoh`.
+//!
+//! Nodes and links are identified by a psid, the index of the node in the graph of the sending
+//! router, and the ZID of a psid is only sent the first time. The ZIDs are thus recorded per
+//! sending side of the session during the first pass. See `zenoh::net::codec::linkstate`.
+
+use std::{
+    cell::RefCell,
+    collections::{BTreeMap, HashMap},
+    ffi::{c_char, c_int, c_void, CStr, CString},
+    fmt::Write,
+};
+
+use anyhow::{anyhow, Result};
+use serde_json::json;
+use zenoh_buffers::buffer::SplitBuffer;
+use zenoh_codec::{RCodec, Zenoh080};
+use zenoh_protocol::{
+    common::ZExtBody,
+    core::{Locator, WhatAmI, ZenohIdProto},
+    network::{oam::id::OAM_LINKSTATE, Mapping, NetworkBody},
+    transport::TransportMessage,
+};
+
+use crate::{
+    alias, expert,
+    header_field::{FieldKind, HeaderFieldMap, Registration},
+    key_expr,
+    session::Side,
+    tree::{set_generated, TreeArgs},
+    utils::{network_messages, packet_str, register_cli_listener, CliListener},
+};
+
+pub const FIELD_LINKSTATE: &str = "zenoh.linkstate";
+pub const FIELD_NODE: &str = "zenoh.linkstate.node";
+pub const FIELD_PSID: &str = "zenoh.linkstate.psid";
+pub const FIELD_SN: &str = "zenoh.linkstate.sn";
+pub const FIELD_ZID: &str = "zenoh.linkstate.zid";
+pub const FIELD_WHATAMI: &str = "zenoh.linkstate.whatami";
+pub const FIELD_LOCATOR: &str = "zenoh.linkstate.locator";
+pub const FIELD_GATEWAY: &str = "zenoh.linkstate.gateway";
+pub const FIELD_LINK: &str = "zenoh.linkstate.link";
+pub const FIELD_LINK_PSID: &str = "zenoh.linkstate.link.psid";
+pub const FIELD_LINK_ZID: &str = "zenoh.linkstate.link.zid";
+pub const FIELD_LINK_WEIGHT: &str = "zenoh.linkstate.link.weight";
+
+const TAP_NAME: &CStr = c"zenoh.linkstate";
+const DOT_CLI: &CStr = c"zenoh.linkstate.dot";
+const JSON_CLI: &CStr = c"zenoh.linkstate.json";
+
+// Options of a link state, see `zenoh::net::protocol::linkstate`.
+const PID: u64 = 1;
+const WAI: u64 = 1 << 1;
+const LOC: u64 = 1 << 2;
+const WGT: u64 = 1 << 3;
+const GWY: u64 = 1 << 4;
+
+/// Weight of the links sent without one.
+const DEFAULT_LINK_WEIGHT: u16 = 100;
+
+static mut TAP_ID: c_int = -1;
+
+static mut DOT_UI: epan_sys::stat_tap_ui = epan_sys::stat_tap_ui {
+    group: epan_sys::register_stat_group_t_REGISTER_STAT_GROUP_GENERIC,
+    title: c"Router Topology (DOT)".as_ptr(),
+    cli_string: DOT_CLI.as_ptr(),
+    tap_init_cb: Some(export_init),
+    nparams: 0,
+    params: std::ptr::null_mut(),
+};
+
+static mut JSON_UI: epan_sys::stat_tap_ui = epan_sys::stat_tap_ui {
+    group: epan_sys::register_stat_group_t_REGISTER_STAT_GROUP_GENERIC,
+    title: c"Router Topology (JSON)".as_ptr(),
+    cli_string: JSON_CLI.as_ptr(),
+    tap_init_cb: Some(export_init),
+    nparams: 0,
+    params: std::ptr::null_mut(),
+};
+
+/// The link state header fields, added below linkstate `Oam` messages.
+pub struct LinkStates;
+
+impl Registration for LinkStates {
+    fn generate_hf_map(prefix: &str) -> HeaderFieldMap {
+        HeaderFieldMap::new()
+            .add(prefix.to_string(), "Link State List", FieldKind::Branch)
+            .add(format!("{prefix}.node"), "Node", FieldKind::Branch)
+            .add(format!("{prefix}.psid"), "PSID", FieldKind::Number)
+            .add(format!("{prefix}.sn"), "SN", FieldKind::Number)
+            .add(format!("{prefix}.zid"), "ZID", FieldKind::Text)
+            .add(format!("{prefix}.whatami"), "WhatAmI", FieldKind::Text)
+            .add(format!("{prefix}.locator"), "Locator", FieldKind::Text)
+            .add(format!("{prefix}.gateway"), "Gateway", FieldKind::Bool)
+            .add(format!("{prefix}.link"), "Link", FieldKind::Branch)
+            .add(format!("{prefix}.link.psid"), "PSID", FieldKind::Number)
+            .add(format!("{prefix}.link.zid"), "ZID", FieldKind::Text)
+            .add(format!("{prefix}.link.weight"), "Weight", FieldKind::Number)
+    }
+
+    fn generate_subtree_names(prefix: &str) -> Vec<String> {
+        vec![
+            prefix.to_string(),
+            format!("{prefix}.node"),
+            format!("{prefix}.link"),
+        ]
+    }
+}
+
+/// A node of a link state list, i.e. the `LinkState` of `zenoh::net::protocol::linkstate`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LinkState {
+    pub psid: u64,
+    pub sn: u64,
+    pub zid: Option<ZenohIdProto>,
+    pub whatami: Option<WhatAmI>,
+    pub locators: Option<Vec<Locator>>,
+    /// Psids of the linked nodes.
+    pub links: Vec<u64>,
+    /// Weights of the links, zero standing for the default weight.
+    pub link_weights: Option<Vec<u16>>,
+    pub is_gateway: bool,
+}
+
+impl LinkState {
+    /// Decode a link state list, the body of a linkstate `Oam` message.
+    pub fn decode_list(mut bytes: &[u8]) -> Result<Vec<Self>> {
+        let codec = Zenoh080::new();
+        let truncated = || anyhow!("truncated link state list");
+        let len: usize = codec.read(&mut bytes).map_err(|_| truncated())?;
+        let mut link_states = Vec::new();
+        for _ in 0..len {
+            let options: u64 = codec.read(&mut bytes).map_err(|_| truncated())?;
+            let psid: u64 = codec.read(&mut bytes).map_err(|_| truncated())?;
+            let sn: u64 = codec.read(&mut bytes).map_err(|_| truncated())?;
+            let zid = match options & PID {
+                0 => None,
+                _ => Some(codec.read(&mut bytes).map_err(|_| truncated())?),
+            };
+            let whatami = match options & WAI {
+                0 => None,
+                _ => {
+                    let whatami: u8 = codec.read(&mut bytes).map_err(|_| truncated())?;
+                    Some(
+                        WhatAmI::try_from(whatami)
+                            .map_err(|_| anyhow!("invalid whatami {whatami:#04x}"))?,
+                    )
+                }
+            };
+            let locators = match options & LOC {
+                0 => None,
+                _ => Some(codec.read(&mut bytes).map_err(|_| truncated())?),
+            };
+            let links_len: usize = codec.read(&mut bytes).map_err(|_| truncated())?;
+            let links = (0..links_len)
+                .map(|_| codec.read(&mut bytes).map_err(|_| truncated()))
+                .collect::<Result<Vec<u64>>>()?;
+            let link_weights = match options & WGT {
+                0 => None,
+                _ => Some(
+                    (0..links_len)
+                        .map(|_| codec.read(&mut bytes).map_err(|_| truncated()))
+                        .collect::<Result<Vec<u16>>>()?,
+                ),
+            };
+            link_states.push(Self {
+                psid,
+                sn,
+                zid,
+                whatami,
+                locators,
+                links,
+                link_weights,
+                is_gateway: options & GWY != 0,
+            });
+        }
+        Ok(link_states)
+    }
+
+    /// Weight of the link at `index`, falling back to the default weight.
+    fn weight(&self, index: usize) -> (u16, bool) {
+        match self.link_weights.as_ref().map(|weights| weights[index]) {
+            Some(0) | None => (DEFAULT_LINK_WEIGHT, true),
+            Some(weight) => (weight, false),
+        }
+    }
+}
+
+/// Returns the bodies of the linkstate `Oam` messages of a transport message.
+fn link_state_lists(pinfo: *mut epan_sys::_packet_info, msg: &TransportMessage) -> Vec<Vec<u8>> {
+    network_messages(pinfo, msg)
+        .iter()
+        .filter_map(|msg| match &msg.body {
+            NetworkBody::OAM(oam) if oam.id == OAM_LINKSTATE => match &oam.body {
+                ZExtBody::ZBuf(zbuf) => Some(zbuf.contiguous().into_owned()),
+                _ => None,
+            },
+            _ => None,
+        })
+        .collect()
+}
+
+/// Sending router and psid.
+type PsidKey = (Side, u64);
+
+thread_local! {
+    /// ZIDs of the psids of each sending router, with the frame they were sent in.
+    static ZIDS: RefCell<HashMap<PsidKey, Vec<(u32, ZenohIdProto)>>> = RefCell::default();
+    /// Router graph of the export objects.
+    static GRAPH_OBJECTS: RefCell<Topology> = RefCell::default();
+}
+
+/// Register the tap, the statistics tree and the DOT and JSON outputs.
+pub unsafe fn register() {
+    TAP_ID = epan_sys::register_tap(TAP_NAME.as_ptr());
+    epan_sys::stats_tree_register_plugin(
+        TAP_NAME.as_ptr(),
+        c"zenoh.linkstate.topology".as_ptr(),
+        c"Zenoh/Router Topology".as_ptr(),
+        0,
+        Some(stats_tree_packet),
+        Some(stats_tree_init),
+        None,
+    );
+    epan_sys::register_stat_tap_ui(&raw mut DOT_UI, Format::Dot as usize as *mut c_void);
+    epan_sys::register_stat_tap_ui(&raw mut JSON_UI, Format::Json as usize as *mut c_void);
+}
+
+/// Clear the recorded ZIDs.
+pub(crate) fn init_zids() {
+    ZIDS.with_borrow_mut(|zids| zids.clear());
+}
+
+/// Record the ZIDs of the psids sent by a transport message.
+pub(crate) unsafe fn update_state(pinfo: *mut epan_sys::_packet_info, msg: &TransportMessage) {
+    let Some(side) = key_expr::declaring_side(pinfo, Mapping::Sender) else {
+        return;
+    };
+
+    for bytes in link_state_lists(pinfo, msg) {
+        let Ok(link_states) = LinkState::decode_list(&bytes) else {
+            continue;
+        };
+        for link_state in link_states {
+            let Some(zid) = link_state.zid else {
+                continue;
+            };
+            ZIDS.with_borrow_mut(|zids| {
+                zids.entry((side.clone(), link_state.psid))
+                    .or_default()
+                    .push(((*pinfo).num, zid))
+            });
+        }
+    }
+}
+
+/// Returns the ZID of a psid of the sending router, as of the frame being dissected.
+fn resolve(pinfo: *mut epan_sys::_packet_info, psid: u64) -> Option<ZenohIdProto> {
+    let side = unsafe { key_expr::declaring_side(pinfo, Mapping::Sender)? };
+    let frame = unsafe { (*pinfo).num };
+    ZIDS.with_borrow(|zids| {
+        zids.get(&(side, psid))?
+            .iter()
+            .rev()
+            .find(|(sent, _)| *sent <= frame)
+            .map(|(_, zid)| *zid)
+    })
+}
+
+/// Add the decoded link state lists of the linkstate `Oam` messages of a transport message.
+pub(crate) fn add_to_tree(msg: &TransportMessage, args: &TreeArgs) -> Result<()> {
+    for bytes in link_state_lists(args.pinfo, msg) {
+        let link_states = match LinkState::decode_list(&bytes) {
+            Ok(link_states) => link_states,
+            Err(err) => {
+                let args = args.make_subtree(FIELD_LINKSTATE, "Link State List")?;
+                expert::add(
+                    args.pinfo,
+                    args.tree,
+                    expert::EI_MALFORMED_LINKSTATE,
+                    &err.to_string(),
+                )?;
+                continue;
+            }
+        };
+        let args = args.make_subtree(
+            FIELD_LINKSTATE,
+            &format!("Link State List, Nodes: {}", link_states.len()),
+        )?;
+        for link_state in &link_states {
+            add_link_state(link_state, &args)?;
+        }
+    }
+    Ok(())
+}
+
+/// Returns the alias of a node, or else its ZID.
+fn zid_label(zid: Option<ZenohIdProto>) -> String {
+    zid.map_or("unknown ZID".to_string(), |zid| {
+        alias::name(&zid.to_string())
+    })
+}
+
+fn add_link_state(link_state: &LinkState, args: &TreeArgs) -> Result<()> {
+    let zid = link_state
+        .zid
+        .or_else(|| resolve(args.pinfo, link_state.psid));
+    let args = args.make_subtree(
+        FIELD_NODE,
+        &format!("Node {}: {}", link_state.psid, zid_label(zid)),
+    )?;
+    args.add_number(FIELD_PSID, link_state.psid)?;
+    args.add_number(FIELD_SN, link_state.sn)?;
+    match (link_state.zid, zid) {
+        (Some(zid), _) => {
+            args.add_text(FIELD_ZID, &zid.to_string())?;
+        }
+        (None, Some(zid)) => set_generated(args.add_text(FIELD_ZID, &zid.to_string())?),
+        (None, None) => {}
+    }
+    if let Some(whatami) = link_state.whatami {
+        args.add_text(FIELD_WHATAMI, whatami.to_str())?;
+    }
+    for locator in link_state.locators.iter().flatten() {
+        args.add_text(FIELD_LOCATOR, locator.as_str())?;
+    }
+    args.add_bool(FIELD_GATEWAY, link_state.is_gateway)?;
+
+    for (index, &psid) in link_state.links.iter().enumerate() {
+        let link_zid = resolve(args.pinfo, psid);
+        let (weight, is_default) = link_state.weight(index);
+        let args = args.make_subtree(
+            FIELD_LINK,
+            &format!("Link to {psid}: {}, Weight: {weight}", zid_label(link_zid)),
+        )?;
+        args.add_number(FIELD_LINK_PSID, psid)?;
+        if let Some(link_zid) = link_zid {
+            set_generated(args.add_text(FIELD_LINK_ZID, &link_zid.to_string())?);
+        }
+        let item = args.add_number(FIELD_LINK_WEIGHT, weight as u64)?;
+        if is_default {
+            set_generated(item);
+        }
+    }
+    Ok(())
+}
+
+/// Data queued on the tap for every node of a link state list whose ZID is known.
+#[repr(C)]
+struct NodeEvent {
+    /// ZID, whatami and newline-separated locators of the node, allocated in the packet scope.
+    zid: *const c_char,
+    whatami: *const c_char,
+    locators: *const c_char,
+    sn: u64,
+    is_gateway: bool,
+    links: *const LinkEvent,
+    links_len: usize,
+}
+
+/// A link of a tapped node to a node whose ZID is known.
+#[repr(C)]
+struct LinkEvent {
+    zid: *const c_char,
+    weight: u16,
+}
+
+/// Queue the nodes of the link state lists of a transport message.
+pub(crate) unsafe fn tap_link_states(pinfo: *mut epan_sys::_packet_info, msg: &TransportMessage) {
+    if !epan_sys::have_tap_listener(TAP_ID) {
+        return;
+    }
+
+    for event in node_events(pinfo, msg) {
+        epan_sys::tap_queue_packet(TAP_ID, pinfo, event);
+    }
+}
+
+/// Returns the events of the nodes of the link state lists of a transport message whose ZID is
+/// known, allocated in the packet scope.
+pub(crate) unsafe fn node_events(
+    pinfo: *mut epan_sys::_packet_info,
+    msg: &TransportMessage,
+) -> Vec<*const c_void> {
+    let mut events = Vec::new();
+    for bytes in link_state_lists(pinfo, msg) {
+        let Ok(link_states) = LinkState::decode_list(&bytes) else {
+            continue;
+        };
+        for link_state in &link_states {
+            let Some(zid) = link_state.zid.or_else(|| resolve(pinfo, link_state.psid)) else {
+                continue;
+            };
+            let links = link_state
+                .links
+                .iter()
+                .enumerate()
+                .filter_map(|(index, &psid)| {
+                    Some((resolve(pinfo, psid)?, link_state.weight(index).0))
+                })
+                .collect::<Vec<_>>();
+
+            let scope = (*pinfo).pool;
+            let link_events =
+                epan_sys::wmem_alloc0(scope, std::mem::size_of::<LinkEvent>() * links.len().max(1))
+                    as *mut LinkEvent;
+            for (index, (link_zid, weight)) in links.iter().enumerate() {
+                *link_events.add(index) = LinkEvent {
+                    zid: packet_str(pinfo, Some(link_zid.to_string())),
+                    weight: *weight,
+                };
+            }
+            let event =
+                epan_sys::wmem_alloc0(scope, std::mem::size_of::<NodeEvent>()) as *mut NodeEvent;
+            *event = NodeEvent {
+                zid: packet_str(pinfo, Some(zid.to_string())),
+                whatami: packet_str(pinfo, link_state.whatami.map(WhatAmI::to_str)),
+                locators: packet_str(
+                    pinfo,
+                    link_state.locators.as_ref().map(|locators| {
+                        let locators = locators.iter().map(Locator::as_str).collect::<Vec<_>>();
+                        locators.join("\n")
+                    }),
+                ),
+                sn: link_state.sn,
+                is_gateway: link_state.is_gateway,
+                links: link_events,
+                links_len: links.len(),
+            };
+            events.push(event as *const c_void);
+        }
+    }
+    events
+}
+
+/// A router of the graph, as described by its latest link state.
+#[derive(Debug, Clone, Default)]
+struct Node {
+    whatami: Option<String>,
+    locators: Vec<String>,
+    sn: u64,
+    is_gateway: bool,
+    /// ZIDs and weights of the linked nodes.
+    links: Vec<(String, u16)>,
+    /// Frame of the latest link state.
+    frame: u32,
+}
+
+/// Returns the ZID and node of a tapped node event.
+unsafe fn tapped_node(pinfo: *const epan_sys::_packet_info, data: *const c_void) -> (String, Node) {
+    let event = &*(data as *const NodeEvent);
+    let string = |s: *const c_char| CStr::from_ptr(s).to_string_lossy().into_owned();
+    let links = (0..event.links_len)
+        .map(|index| {
+            let link = &*event.links.add(index);
+            (string(link.zid), link.weight)
+        })
+        .collect();
+    let node = Node {
+        whatami: (!event.whatami.is_null()).then(|| string(event.whatami)),
+        locators: match event.locators.is_null() {
+            true => Vec::new(),
+            false => string(event.locators).lines().map(str::to_string).collect(),
+        },
+        sn: event.sn,
+        is_gateway: event.is_gateway,
+        links,
+        frame: (*pinfo).num,
+    };
+    (string(event.zid), node)
+}
+
+unsafe extern "C" fn stats_tree_init(st: *mut epan_sys::stats_tree) {
+    epan_sys::stats_tree_create_node(
+        st,
+        c"Routers".as_ptr(),
+        0,
+        epan_sys::_stat_node_datatype_STAT_DT_INT,
+        true,
+    );
+}
+
+/// Increment the counter of a node of the tree, creating it if needed, and returns its id.
+unsafe fn tick(st: *mut epan_sys::stats_tree, name: &str, parent: c_int, children: bool) -> c_int {
+    let name = CString::new(name).unwrap_or_default();
+    epan_sys::stats_tree_manip_node_int(
+        epan_sys::_manip_node_mode_MN_INCREASE,
+        st,
+        name.as_ptr(),
+        parent,
+        children,
+        1,
+    )
+}
+
+unsafe extern "C" fn stats_tree_packet(
+    st: *mut epan_sys::stats_tree,
+    pinfo: *mut epan_sys::packet_info,
+    _edt: *mut epan_sys::epan_dissect_t,
+    data: *const c_void,
+    _flags: epan_sys::tap_flags_t,
+) -> epan_sys::tap_packet_status {
+    let (zid, node) = tapped_node(pinfo, data);
+
+    // Routers > node > links, counted over the link states of the node
+    let routers = tick(st, "Routers", 0, true);
+    let name = alias::name(&zid);
+    let label = match &node.whatami {
+        Some(whatami) => format!("{name} ({whatami})"),
+        None => name,
+    };
+    let router = tick(st, &label, routers, true);
+    for (link_zid, weight) in &node.links {
+        tick(
+            st,
+            &format!("Link to {}, Weight: {weight}", alias::name(link_zid)),
+            router,
+            false,
+        );
+    }
+
+    epan_sys::tap_packet_status_TAP_PACKET_REDRAW
+}
+
+/// Output format of the router graph.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Format {
+    Dot,
+    Json,
+}
+
+impl Format {
+    fn cli(self) -> &'static CStr {
+        match self {
+            Self::Dot => DOT_CLI,
+            Self::Json => JSON_CLI,
+        }
+    }
+}
+
+/// The router graph as of the last tapped frame.
+#[derive(Debug, Default)]
+struct Topology {
+    nodes: BTreeMap<String, Node>,
+}
+
+/// Data of the DOT and JSON outputs.
+#[derive(Debug)]
+struct Export {
+    format: Format,
+    topology: Topology,
+}
+
+impl Topology {
+    fn update(&mut self, zid: String, node: Node) {
+        // Link states are flooded, so older ones may be captured after newer ones.
+        match self.nodes.get(&zid) {
+            Some(known) if known.sn > node.sn => {}
+            _ => {
+                self.nodes.insert(zid, node);
+            }
+        }
+    }
+
+    fn to_dot(&self) -> String {
+        fn quote(s: &str) -> String {
+            let s = s.replace('\\', "\\\\").replace('"', "\\\"");
+            format!("\"{}\"", s.replace('\n', "\\n"))
+        }
+
+        let mut dot = String::from("digraph zenoh {\n");
+        for (zid, node) in &self.nodes {
+            let mut label = alias::name(zid);
+            if let Some(whatami) = &node.whatami {
+                let _ = write!(label, "\n{whatami}");
+            }
+            for locator in &node.locators {
+                let _ = write!(label, "\n{locator}");
+            }
+            let shape = if node.is_gateway {
+                "doublecircle"
+            } else {
+                "box"
+            };
+            let _ = writeln!(
+                dot,
+                "    {} [shape={shape}, label={}];",
+                quote(zid),
+                quote(&label)
+            );
+        }
+        for (zid, node) in &self.nodes {
+            for (link_zid, weight) in &node.links {
+                let _ = writeln!(
+                    dot,
+                    "    {} -> {} [label={}];",
+                    quote(zid),
+                    quote(link_zid),
+                    quote(&weight.to_string())
+                );
+            }
+        }
+        dot.push_str("}\n");
+        dot
+    }
+
+    fn to_json(&self) -> String {
+        let nodes = self
+            .nodes
+            .iter()
+            .map(|(zid, node)| {
+                json!({
+                    "zid": zid,
+                    "name": alias::lookup(zid).map(|alias| alias.name),
+                    "whatami": node.whatami,
+                    "locators": node.locators,
+                    "sn": node.sn,
+                    "gateway": node.is_gateway,
+                    "frame": node.frame,
+                })
+            })
+            .collect::<Vec<_>>();
+        let links = self
+            .nodes
+            .iter()
+            .flat_map(|(zid, node)| {
+                node.links.iter().map(move |(link_zid, weight)| {
+                    json!({ "source": zid, "target": link_zid, "weight": weight })
+                })
+            })
+            .collect::<Vec<_>>();
+        let graph = json!({ "nodes": nodes, "links": links });
+        format!("{graph:#}\n")
+    }
+}
+
+impl CliListener for Export {
+    fn reset(&mut self) {
+        self.topology.nodes.clear();
+    }
+
+    unsafe fn packet(&mut self, pinfo: *const epan_sys::_packet_info, data: *const c_void) -> bool {
+        let (zid, node) = tapped_node(pinfo, data);
+        self.topology.update(zid, node);
+        true
+    }
+
+    fn draw(&self) {
+        match self.format {
+            Format::Dot => print!("{}", self.topology.to_dot()),
+            Format::Json => print!("{}", self.topology.to_json()),
+        }
+    }
+}
+
+unsafe extern "C" fn export_init(opt_arg: *const c_char, userdata: *mut c_void) {
+    let format = match userdata as usize {
+        0 => Format::Dot,
+        _ => Format::Json,
+    };
+    let export = Export {
+        format,
+        topology: Topology::default(),
+    };
+    register_cli_listener(TAP_NAME, format.cli(), opt_arg, export);
+}
+
+/// Update the router graph saved from `File > Export Objects > Zenoh` with the nodes tapped in a
+/// frame, and returns the file names, content types and contents of its DOT and JSON snapshots
+/// as of that frame.
+pub(crate) unsafe fn graph_objects(
+    pinfo: *const epan_sys::_packet_info,
+    events: &[*const c_void],
+) -> [(String, &'static CStr, String); 2] {
+    GRAPH_OBJECTS.with_borrow_mut(|topology| {
+        for &event in events {
+            let (zid, node) = tapped_node(pinfo, event);
+            topology.update(zid, node);
+        }
+        let frame = (*pinfo).num;
+        [
+            (
+                format!("router-topology-{frame}.dot"),
+                c"text/vnd.graphviz",
+                topology.to_dot(),
+            ),
+            (
+                format!("router-topology-{frame}.json"),
+                c"application/json",
+                topology.to_json(),
+            ),
+        ]
+    })
+}
+
+/// Clear the router graph of the export objects, called whenever they are listed again.
+pub(crate) fn reset_graph_objects() {
+    GRAPH_OBJECTS.with_borrow_mut(|topology| topology.nodes.clear());
+}
+
+#[cfg(test)]
+mod tests {
+    use zenoh_buffers::writer::HasWriter;
+    use zenoh_codec::WCodec;
+
+    use super::*;
+
+    fn encode(link_states: &[LinkState]) -> Vec<u8> {
+        let codec = Zenoh080::new();
+        let mut bytes = Vec::new();
+        let mut writer = bytes.writer();
+        codec.write(&mut writer, link_states.len()).unwrap();
+        for link_state in link_states {
+            let options = [
+                (link_state.zid.is_some(), PID),
+                (link_state.whatami.is_some(), WAI),
+                (link_state.locators.is_some(), LOC),
+                (link_state.link_weights.is_some(), WGT),
+                (link_state.is_gateway, GWY),
+            ]
+            .iter()
+            .filter(|(set, _)| *set)
+            .fold(0, |options, (_, option)| options | option);
+            codec.write(&mut writer, options).unwrap();
+            codec.write(&mut writer, link_state.psid).unwrap();
+            codec.write(&mut writer, link_state.sn).unwrap();
+            if let Some(zid) = &link_state.zid {
+                codec.write(&mut writer, zid).unwrap();
+            }
+            if let Some(whatami) = link_state.whatami {
+                codec.write(&mut writer, whatami as u8).unwrap();
+            }
+            if let Some(locators) = &link_state.locators {
+                codec.write(&mut writer, locators.as_slice()).unwrap();
+            }
+            codec.write(&mut writer, link_state.links.len()).unwrap();
+            for &link in &link_state.links {
+                codec.write(&mut writer, link).unwrap();
+            }
+            for &weight in link_state.link_weights.iter().flatten() {
+                codec.write(&mut writer, weight).unwrap();
+            }
+        }
+        bytes
+    }
+
+    fn link_states() -> Vec<LinkState> {
+        vec![
+            LinkState {
+                psid: 0,
+                sn: 7,
+                zid: Some("1a2b3c".parse().unwrap()),
+                whatami: Some(WhatAmI::Router),
+                locators: Some(vec!["tcp/10.0.0.1:7447".parse().unwrap()]),
+                links: vec![1, 2],
+                link_weights: Some(vec![0, 250]),
+                is_gateway: true,
+            },
+            LinkState {
+                psid: 1,
+                sn: 3,
+                zid: None,
+                whatami: None,
+                locators: None,
+                links: vec![0],
+                link_weights: None,
+                is_gateway: false,
+            },
+        ]
+    }
+
+    #[test]
+    fn decode_link_states() {
+        let link_states = link_states();
+        let decoded = LinkState::decode_list(&encode(&link_states)).unwrap();
+        assert_eq!(decoded, link_states);
+        assert_eq!(decoded[0].weight(0), (DEFAULT_LINK_WEIGHT, true));
+        assert_eq!(decoded[0].weight(1), (250, false));
+        assert_eq!(decoded[1].weight(0), (DEFAULT_LINK_WEIGHT, true));
+        assert_eq!(LinkState::decode_list(&[0]).unwrap(), Vec::new());
+    }
+
+    #[test]
+    fn decode_malformed_link_states() {
+        let bytes = encode(&link_states());
+        for len in 0..bytes.len() {
+            assert!(LinkState::decode_list(&bytes[..len]).is_err(), "{len}");
+        }
+        // More nodes than encoded, and an invalid whatami
+        assert!(LinkState::decode_list(&[3, 0, 0, 0, 0]).is_err());
+        assert!(LinkState::decode_list(&[1, WAI as u8, 0, 0, 0x03, 0]).is_err());
+    }
+}
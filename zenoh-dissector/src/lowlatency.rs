@@ -0,0 +1,190 @@
+//! The lowlatency transport, negotiated by the `ext_lowlatency` extension of the `InitSyn` and
+//! `InitAck`.
+//!
+//! After the `OpenAck` of such a session, transport messages are no longer batched: on TCP each
+//! one is prefixed by its 4-byte little-endian length, and on UDP a datagram carries them alone.
+//! Besides `Close` and `KeepAlive`, they are bare network messages, which are wrapped into a
+//! `Frame` so that the rest of the dissector handles them as usual, but shown without it.
+
+use anyhow::{anyhow, Result};
+use zenoh_buffers::reader::Reader;
+use zenoh_codec::{RCodec, Zenoh080};
+use zenoh_protocol::transport::{
+    frame, Frame, TransportBody, TransportBodyLowLatency, TransportMessage,
+    TransportMessageLowLatency,
+};
+
+use crate::{
+    fragment::NETWORK_PREFIX,
+    header_field::{FieldKind, HeaderFieldMap, Registration},
+    tree::{AddToTree, TreeArgs},
+};
+
+pub const FIELD_LOWLATENCY: &str = "zenoh.lowlatency";
+
+/// Length of the prefix of the lowlatency transport messages on TCP.
+pub const TCP_HEADER_LEN: usize = 4;
+
+/// The subtree of the network messages sent without `Frame`.
+pub struct LowLatency;
+
+impl Registration for LowLatency {
+    fn generate_hf_map(prefix: &str) -> HeaderFieldMap {
+        HeaderFieldMap::new().add(prefix.to_string(), "Low Latency", FieldKind::Branch)
+    }
+
+    fn generate_subtree_names(prefix: &str) -> Vec<String> {
+        vec![prefix.to_string()]
+    }
+}
+
+/// Decode the lowlatency transport messages of a PDU, with their length. Network messages are
+/// wrapped into a `Frame`, without sequence number.
+pub(crate) fn decode(mut bytes: &[u8]) -> Result<Vec<(TransportMessage, usize)>> {
+    let codec = Zenoh080::new();
+    let mut msgs = Vec::new();
+    while bytes.can_read() {
+        let remaining = bytes.remaining();
+        let msg: TransportMessageLowLatency = codec
+            .read(&mut bytes)
+            .map_err(|_| anyhow!("failed to decode lowlatency transport message"))?;
+        msgs.push((into_transport_message(msg), remaining - bytes.remaining()));
+    }
+    Ok(msgs)
+}
+
+fn into_transport_message(msg: TransportMessageLowLatency) -> TransportMessage {
+    let body = match msg.body {
+        TransportBodyLowLatency::Close(close) => TransportBody::Close(close),
+        TransportBodyLowLatency::KeepAlive(keep_alive) => TransportBody::KeepAlive(keep_alive),
+        TransportBodyLowLatency::Network(msg) => TransportBody::Frame(Frame {
+            reliability: msg.reliability,
+            sn: 0,
+            ext_qos: frame::ext::QoSType::DEFAULT,
+            payload: vec![msg],
+        }),
+    };
+    TransportMessage { body }
+}
+
+/// Add a lowlatency transport message: network messages on their own, without the `Frame`
+/// wrapping them, and the others as usual.
+pub(crate) fn add_to_tree(msg: &TransportMessage, args: &TreeArgs) -> Result<()> {
+    let TransportBody::Frame(frame) = &msg.body else {
+        return msg.add_to_tree("zenoh", args);
+    };
+    let args = args.make_subtree(FIELD_LOWLATENCY, "Low Latency NetworkMessage")?;
+    for msg in &frame.payload {
+        msg.add_to_tree(NETWORK_PREFIX, &args)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use zenoh_buffers::writer::{HasWriter, Writer};
+    use zenoh_codec::WCodec;
+    use zenoh_protocol::{
+        core::Reliability,
+        network::{response, NetworkBody, NetworkMessage, ResponseFinal},
+        transport::{close, Close, Fragment, KeepAlive},
+    };
+
+    use super::*;
+
+    fn response_final(rid: u32) -> NetworkMessage {
+        NetworkBody::ResponseFinal(ResponseFinal {
+            rid,
+            ext_qos: response::ext::QoSType::DEFAULT,
+            ext_tstamp: None,
+        })
+        .into()
+    }
+
+    fn frame(msgs: Vec<NetworkMessage>) -> TransportMessage {
+        Frame {
+            reliability: Reliability::Reliable,
+            sn: 0,
+            ext_qos: frame::ext::QoSType::DEFAULT,
+            payload: msgs,
+        }
+        .into()
+    }
+
+    /// Encode a transport message as lowlatency transport messages, unwrapping the network
+    /// messages of a `Frame`.
+    fn encode<W: Writer>(writer: &mut W, msg: &TransportMessage) -> Result<()> {
+        let codec = Zenoh080::new();
+        let written = match &msg.body {
+            TransportBody::Close(close) => codec.write(&mut *writer, close),
+            TransportBody::KeepAlive(keep_alive) => codec.write(&mut *writer, keep_alive),
+            TransportBody::Frame(frame) => frame
+                .payload
+                .iter()
+                .try_for_each(|msg| codec.write(&mut *writer, msg)),
+            _ => return Err(anyhow!("not a lowlatency transport message")),
+        };
+        written.map_err(|_| anyhow!("failed to encode lowlatency transport message"))
+    }
+
+    fn encode_all(msgs: &[TransportMessage]) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        for msg in msgs {
+            encode(&mut bytes.writer(), msg).unwrap();
+        }
+        bytes
+    }
+
+    #[test]
+    fn round_trip() {
+        let msgs = [
+            KeepAlive.into(),
+            frame(vec![response_final(1)]),
+            Close {
+                reason: close::reason::EXPIRED,
+                session: true,
+            }
+            .into(),
+        ];
+        let bytes = encode_all(&msgs);
+        let decoded = decode(&bytes).unwrap();
+        assert_eq!(
+            decoded.iter().map(|(msg, _)| msg).collect::<Vec<_>>(),
+            msgs.iter().collect::<Vec<_>>()
+        );
+        assert_eq!(
+            decoded.iter().map(|(_, len)| len).sum::<usize>(),
+            bytes.len()
+        );
+    }
+
+    #[test]
+    fn round_trip_frame() {
+        // Each network message of a `Frame` is a lowlatency transport message of its own.
+        let bytes = encode_all(&[frame(vec![response_final(1), response_final(2)])]);
+        let decoded = decode(&bytes).unwrap();
+        assert_eq!(
+            decoded.into_iter().map(|(msg, _)| msg).collect::<Vec<_>>(),
+            [
+                frame(vec![response_final(1)]),
+                frame(vec![response_final(2)])
+            ]
+        );
+    }
+
+    #[test]
+    fn malformed() {
+        let fragment = Fragment {
+            reliability: Reliability::Reliable,
+            more: false,
+            sn: 0,
+            payload: vec![0].into(),
+            ext_qos: Default::default(),
+            ext_first: None,
+            ext_drop: None,
+        };
+        assert!(encode(&mut Vec::new().writer(), &fragment.into()).is_err());
+        let bytes = encode_all(&[frame(vec![response_final(0xdead_beef)])]);
+        assert!(decode(&bytes[..bytes.len() - 1]).is_err());
+    }
+}
@@ -158,8 +158,10 @@ macro_rules! impl_for_struct {
             fn add_to_tree(&self, prefix: &str, args: &TreeArgs) -> Result<()> {
                 $(
                     let hf_index = args.get_hf(&format!("{prefix}.{}", stringify!{$field_name}))?;
-                    unsafe {
-                        let field_name_c_str = std::ffi::CString::new(format!("{:?}", self.$field_name)).unwrap();
+                    let item = unsafe {
+                        let field_name_c_str = $crate::utils::lossy_c_string(
+                            $crate::display::FieldDisplay::field_display(&self.$field_name)
+                        );
                         // The codec doesn't expose per-field byte offsets, so we prevent wireshark
                         // from displaying it by setting length to 0.
                         epan_sys::proto_tree_add_string(
@@ -169,8 +171,9 @@ macro_rules! impl_for_struct {
                             args.start as _,
                             0,
                             field_name_c_str.as_ptr(),
-                        );
-                    }
+                        )
+                    };
+                    $crate::display::FieldDisplay::add_details(&self.$field_name, item, args)?;
                 )*
 
                 // HACK(fuzzypixelz): recursively created trees will have an incorrect length. Only
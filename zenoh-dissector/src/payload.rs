@@ -0,0 +1,378 @@
+//! Hand-off of publication, query and reply payloads to other dissectors.
+//!
+//! Payloads are first matched against the key expressions of the user's "Key Expression
+//! Dissectors" table, then decoded as ROS 2 messages for rmw_zenoh key expressions. Otherwise
+//! they're looked up by the mime string of their encoding in the `zenoh.encoding` dissector
+//! table, then by the encoding id in the `zenoh.encoding.id` table.
+//! Other plugins can register their own decoders in either table. Payloads left undecoded can
+//! optionally be tried as zenoh-ext serialized data.
+
+use std::{
+    cell::RefCell,
+    ffi::{c_uint, CStr, CString},
+};
+
+use anyhow::Result;
+use zenoh_buffers::{buffer::SplitBuffer, ZBuf};
+use zenoh_protocol::{
+    core::{
+        key_expr::{keyexpr, OwnedKeyExpr},
+        Encoding, WireExpr,
+    },
+    network::{NetworkBody, NetworkMessage},
+    transport::TransportMessage,
+    zenoh::{PushBody, RequestBody, ResponseBody},
+};
+
+use crate::{
+    display::FieldDisplay, key_expr, ros2, serialization, tree::TreeArgs, uat,
+    utils::frame_messages, PROTOCOL_DATA,
+};
+
+pub const ENCODING_TABLE: &CStr = c"zenoh.encoding";
+pub const ENCODING_ID_TABLE: &CStr = c"zenoh.encoding.id";
+
+/// Wireshark dissectors handling the predefined Zenoh encodings, by mime name and id.
+const BUILTIN_DISSECTORS: &[(&CStr, &CStr, Option<u16>)] = &[
+    (c"json", c"application/json", Some(5)),
+    (c"json", c"text/json", Some(6)),
+    (c"xml", c"application/xml", Some(21)),
+    (c"xml", c"text/xml", Some(24)),
+    (c"cbor", c"application/cbor", Some(8)),
+    (c"msgpack", c"application/msgpack", None),
+    (c"msgpack", c"application/x-msgpack", None),
+    (c"png", c"image/png", Some(16)),
+    (c"image-jfif", c"image/jpeg", Some(17)),
+    (c"zenoh-ext", c"zenoh/serialized", Some(2)),
+    (c"data-text-lines", c"zenoh/string", Some(1)),
+    (c"data-text-lines", c"text/plain", Some(4)),
+    (c"data-text-lines", c"application/yaml", Some(9)),
+    (c"data-text-lines", c"text/yaml", Some(10)),
+    (c"data-text-lines", c"text/markdown", Some(27)),
+    (c"data-text-lines", c"text/csv", Some(28)),
+];
+
+/// Register the encoding dissector tables, see [`ENCODING_TABLE`] and [`ENCODING_ID_TABLE`].
+pub unsafe fn register_tables(
+    proto_id: i32,
+) -> (epan_sys::dissector_table_t, epan_sys::dissector_table_t) {
+    let encoding_table = epan_sys::register_dissector_table(
+        ENCODING_TABLE.as_ptr(),
+        c"Zenoh Encoding".as_ptr(),
+        proto_id,
+        epan_sys::ftenum_FT_STRING,
+        epan_sys::STRING_CASE_INSENSITIVE as _,
+    );
+    let encoding_id_table = epan_sys::register_dissector_table(
+        ENCODING_ID_TABLE.as_ptr(),
+        c"Zenoh Encoding ID".as_ptr(),
+        proto_id,
+        epan_sys::ftenum_FT_UINT16,
+        epan_sys::field_display_e_BASE_DEC as _,
+    );
+    epan_sys::dissector_table_allow_decode_as(encoding_table);
+    epan_sys::dissector_table_allow_decode_as(encoding_id_table);
+    (encoding_table, encoding_id_table)
+}
+
+/// Add the wireshark dissectors of the predefined encodings to the encoding tables.
+pub unsafe fn add_builtin_dissectors() {
+    for (dissector, mime, id) in BUILTIN_DISSECTORS {
+        // Dissectors may be missing, e.g. disabled at build time.
+        let handle = epan_sys::find_dissector(dissector.as_ptr());
+        if handle.is_null() {
+            continue;
+        }
+        epan_sys::dissector_add_string(ENCODING_TABLE.as_ptr(), mime.as_ptr(), handle);
+        if let Some(id) = id {
+            epan_sys::dissector_add_uint(ENCODING_ID_TABLE.as_ptr(), *id as _, handle);
+        }
+    }
+}
+
+/// A user-configured dissector for the payloads of a key expression.
+struct KeyExprDissector {
+    key_expr: OwnedKeyExpr,
+    handle: epan_sys::dissector_handle_t,
+    /// Passed to the dissector as its data, e.g. `message,<type>` for protobuf.
+    argument: Option<CString>,
+}
+
+// Records of the key expression dissectors table: key expression, dissector and argument.
+static mut KEYEXPR_DISSECTORS: *mut uat::StringRecord<3> = std::ptr::null_mut();
+static mut NUM_KEYEXPR_DISSECTORS: c_uint = 0;
+
+thread_local! {
+    static KEYEXPR_RULES: RefCell<Vec<KeyExprDissector>> = RefCell::default();
+}
+
+/// Register the key expression dissectors table as a preference of the zenoh module.
+pub unsafe fn register_keyexpr_dissectors(module: *mut epan_sys::module_t) {
+    uat::register(
+        module,
+        c"keyexpr_dissectors",
+        c"Key Expression Dissectors",
+        c"Dissectors of the payloads whose key expression intersects a pattern, tried before \
+          the encoding based ones",
+        c"zenoh_keyexpr_dissectors",
+        [
+            uat::Column {
+                name: c"key_expr",
+                title: c"Key Expression",
+                desc: c"Key expression pattern, may contain the *, ** and $* wildcards",
+                mode: epan_sys::_uat_text_mode_t_PT_TXTMOD_STRING,
+                check: Some(check_key_expr),
+            },
+            uat::Column {
+                name: c"dissector",
+                title: c"Dissector",
+                desc: c"Name of the dissector decoding the payloads",
+                mode: epan_sys::_uat_text_mode_t_PT_TXTMOD_DISSECTOR,
+                check: Some(check_dissector),
+            },
+            uat::Column {
+                name: c"argument",
+                title: c"Argument",
+                desc: c"Optional data string passed to the dissector, e.g. \"message,<type>\" \
+                        for protobuf",
+                mode: epan_sys::_uat_text_mode_t_PT_TXTMOD_STRING,
+                check: None,
+            },
+        ],
+        &raw mut KEYEXPR_DISSECTORS,
+        &raw mut NUM_KEYEXPR_DISSECTORS,
+        Some(update_keyexpr_dissectors),
+    );
+}
+
+fn check_key_expr(key_expr: &str) -> Result<(), String> {
+    keyexpr::new(key_expr)
+        .map(|_| ())
+        .map_err(|err| err.to_string())
+}
+
+fn check_dissector(name: &str) -> Result<(), String> {
+    let name = CString::new(name).map_err(|err| err.to_string())?;
+    if unsafe { epan_sys::find_dissector(name.as_ptr()) }.is_null() {
+        Err(format!("Unknown dissector {name:?}"))
+    } else {
+        Ok(())
+    }
+}
+
+unsafe extern "C" fn update_keyexpr_dissectors() {
+    let rules = uat::records(KEYEXPR_DISSECTORS, NUM_KEYEXPR_DISSECTORS)
+        .into_iter()
+        .filter_map(|[key_expr, dissector, argument]| {
+            let key_expr = OwnedKeyExpr::new(key_expr).ok()?;
+            let handle = epan_sys::find_dissector(CString::new(dissector).ok()?.as_ptr());
+            if handle.is_null() {
+                return None;
+            }
+            let argument = if argument.is_empty() {
+                None
+            } else {
+                CString::new(argument).ok()
+            };
+            Some(KeyExprDissector {
+                key_expr,
+                handle,
+                argument,
+            })
+        })
+        .collect();
+    KEYEXPR_RULES.set(rules);
+}
+
+/// An application payload carried by a network message.
+pub(crate) struct Payload<'a> {
+    /// Name of the zenoh message carrying the payload, e.g. `Put`.
+    pub kind: &'static str,
+    pub wire_expr: &'a WireExpr<'static>,
+    pub encoding: &'a Encoding,
+    pub bytes: &'a ZBuf,
+}
+
+impl<'a> Payload<'a> {
+    pub fn from_network_message(msg: &'a NetworkMessage) -> Option<Self> {
+        let (kind, wire_expr, encoding, bytes) = match &msg.body {
+            NetworkBody::Push(push) => match &push.payload {
+                PushBody::Put(put) => ("Put", &push.wire_expr, &put.encoding, &put.payload),
+                PushBody::Del(_) => return None,
+            },
+            NetworkBody::Request(request) => match &request.payload {
+                RequestBody::Query(query) => {
+                    let body = query.ext_body.as_ref()?;
+                    ("Query", &request.wire_expr, &body.encoding, &body.payload)
+                }
+            },
+            NetworkBody::Response(response) => match &response.payload {
+                ResponseBody::Reply(reply) => match &reply.payload {
+                    PushBody::Put(put) => {
+                        ("Reply", &response.wire_expr, &put.encoding, &put.payload)
+                    }
+                    PushBody::Del(_) => return None,
+                },
+                ResponseBody::Err(err) => ("Err", &response.wire_expr, &err.encoding, &err.payload),
+            },
+            _ => return None,
+        };
+        Some(Self {
+            kind,
+            wire_expr,
+            encoding,
+            bytes,
+        })
+    }
+
+    /// Hand the payload off to the dissector configured for its key expression, or else to
+    /// the one registered for its encoding.
+    ///
+    /// The payload is decoded from a (possibly compressed) batch, so it's added to the packet
+    /// as a new data source rather than as a subset of the frame.
+    pub fn dissect(&self, args: &TreeArgs) -> Result<()> {
+        let bytes = self.bytes.contiguous();
+        if bytes.is_empty() {
+            return Ok(());
+        }
+
+        let encoding = self.encoding.field_display();
+        let args = args.make_subtree(
+            "zenoh.payload",
+            &format!("{} Payload: {encoding}, {} bytes", self.kind, bytes.len()),
+        )?;
+
+        let key_expr = key_expr::resolve(args.pinfo, self.wire_expr);
+        // A dissector configured by the user takes precedence over the ROS 2 decoding.
+        if let Some(key_expr) = &key_expr {
+            if !self.has_keyexpr_dissector(key_expr)
+                && ros2::dissect_payload(key_expr, self.kind, &bytes, &args)?
+            {
+                return Ok(());
+            }
+        }
+
+        unsafe {
+            let pinfo = args.pinfo;
+            let data = epan_sys::wmem_memdup((*pinfo).pool, bytes.as_ptr() as _, bytes.len());
+            let tvb = epan_sys::tvb_new_child_real_data(
+                args.tvb,
+                data as *const u8,
+                bytes.len() as _,
+                bytes.len() as _,
+            );
+            epan_sys::add_new_data_source(pinfo, tvb, c"Zenoh Payload".as_ptr());
+
+            // Keep the Zenoh protocol column and our own Info column summary.
+            epan_sys::col_set_writable((*pinfo).cinfo, -1, false);
+            let by_key_expr = key_expr
+                .as_deref()
+                .is_some_and(|key_expr| self.dissect_by_key_expr(key_expr, tvb, &args));
+            let by_encoding = !by_key_expr && self.dissect_by_encoding(&encoding, tvb, &args);
+            epan_sys::col_set_writable((*pinfo).cinfo, -1, true);
+
+            if !by_key_expr && !by_encoding && crate::ZENOH_EXT_HEURISTIC {
+                let args = TreeArgs {
+                    tvb,
+                    start: 0,
+                    length: bytes.len(),
+                    ..args
+                };
+                serialization::dissect(None, &bytes, &args)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Run `f` with the user-configured dissector matching a key expression, if any.
+    fn with_keyexpr_dissector<R>(
+        &self,
+        key_expr: &str,
+        f: impl FnOnce(&KeyExprDissector) -> R,
+    ) -> Option<R> {
+        let key_expr = keyexpr::new(key_expr).ok()?;
+        KEYEXPR_RULES.with_borrow(|rules| {
+            rules
+                .iter()
+                .find(|rule| rule.key_expr.intersects(key_expr))
+                .map(f)
+        })
+    }
+
+    fn has_keyexpr_dissector(&self, key_expr: &str) -> bool {
+        self.with_keyexpr_dissector(key_expr, |_| ()).is_some()
+    }
+
+    unsafe fn dissect_by_key_expr(
+        &self,
+        key_expr: &str,
+        tvb: *mut epan_sys::tvbuff,
+        args: &TreeArgs,
+    ) -> bool {
+        self.with_keyexpr_dissector(key_expr, |rule| {
+            let data = rule
+                .argument
+                .as_ref()
+                .map_or(std::ptr::null_mut(), |argument| argument.as_ptr() as *mut _);
+            epan_sys::call_dissector_with_data(rule.handle, tvb, args.pinfo, args.tree, data);
+        })
+        .is_some()
+    }
+
+    unsafe fn dissect_by_encoding(
+        &self,
+        encoding: &str,
+        tvb: *mut epan_sys::tvbuff,
+        args: &TreeArgs,
+    ) -> bool {
+        let (encoding_table, encoding_id_table) =
+            PROTOCOL_DATA.with_borrow(|data| (data.encoding_table, data.encoding_id_table));
+
+        // Try the full encoding with its schema first, then the bare mime name.
+        let mime = encoding.split(';').next().unwrap_or_default();
+        if let Some(table) = encoding_table {
+            for key in [encoding, mime] {
+                let Ok(key) = CString::new(key) else {
+                    continue;
+                };
+                if epan_sys::dissector_try_string_with_data(
+                    table,
+                    key.as_ptr(),
+                    tvb,
+                    args.pinfo,
+                    args.tree,
+                    true,
+                    std::ptr::null_mut(),
+                ) != 0
+                {
+                    return true;
+                }
+            }
+        }
+        if let Some(table) = encoding_id_table {
+            return epan_sys::dissector_try_uint_with_data(
+                table,
+                self.encoding.id as _,
+                tvb,
+                args.pinfo,
+                args.tree,
+                true,
+                std::ptr::null_mut(),
+            ) != 0;
+        }
+        false
+    }
+}
+
+/// Hand off the payloads of the network messages of a `Frame`, the ones of reassembled messages
+/// being handed off below their `Fragment`.
+pub(crate) fn dissect_payloads(msg: &TransportMessage, args: &TreeArgs) -> Result<()> {
+    for payload in frame_messages(msg)
+        .iter()
+        .filter_map(Payload::from_network_message)
+    {
+        payload.dissect(args)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,124 @@
+//! Decoding of the QoS extension into filterable priority, congestion control and express
+//! fields.
+//!
+//! See the `ext_qos` definitions in `zenoh_protocol::network::ext` and
+//! `zenoh_protocol::transport::ext`.
+
+use anyhow::Result;
+
+use crate::{
+    header_field::{FieldKind, HeaderFieldMap, Registration},
+    tree::TreeArgs,
+};
+
+pub const FIELD_QOS: &str = "zenoh.qos";
+pub const FIELD_PRIORITY: &str = "zenoh.qos.priority";
+pub const FIELD_CONGESTION_CONTROL: &str = "zenoh.qos.congestion_control";
+pub const FIELD_EXPRESS: &str = "zenoh.qos.express";
+
+pub const PRIORITY_NAMES: &[(u32, &str)] = &[
+    (0, "Control"),
+    (1, "RealTime"),
+    (2, "InteractiveHigh"),
+    (3, "InteractiveLow"),
+    (4, "DataHigh"),
+    (5, "Data"),
+    (6, "DataLow"),
+    (7, "Background"),
+];
+
+const CONGESTION_CONTROL_NAMES: &[(u32, &str)] = &[(0, "Drop"), (1, "Block"), (2, "BlockFirst")];
+
+const P_MASK: u64 = 0b0000_0111;
+const D_FLAG: u64 = 0b0000_1000;
+const E_FLAG: u64 = 0b0001_0000;
+const F_FLAG: u64 = 0b0010_0000;
+
+/// The QoS header fields, shared by every message carrying an `ext_qos`.
+pub struct QoS;
+
+impl Registration for QoS {
+    fn generate_hf_map(prefix: &str) -> HeaderFieldMap {
+        HeaderFieldMap::new()
+            .add(
+                format!("{prefix}.priority"),
+                "Priority",
+                FieldKind::Enum(PRIORITY_NAMES),
+            )
+            .add(
+                format!("{prefix}.congestion_control"),
+                "Congestion Control",
+                FieldKind::Enum(CONGESTION_CONTROL_NAMES),
+            )
+            .add(format!("{prefix}.express"), "Express", FieldKind::Bool)
+    }
+
+    fn generate_subtree_names(prefix: &str) -> Vec<String> {
+        vec![prefix.to_string()]
+    }
+}
+
+/// Raw QoS bits as sent on the wire.
+///
+/// The codec only exposes `BlockFirst` with the `unstable` feature, so the bits are decoded
+/// here rather than through the `QoSType` accessors.
+#[derive(Debug, Clone, Copy)]
+pub struct QoSBits {
+    pub priority: u8,
+    /// Only network messages carry congestion control and express flags.
+    pub congestion_control: Option<u8>,
+    pub express: Option<bool>,
+}
+
+impl QoSBits {
+    pub fn network(raw: u64) -> Self {
+        let congestion_control = match (raw & D_FLAG != 0, raw & F_FLAG != 0) {
+            (true, _) => 1,
+            (false, true) => 2,
+            (false, false) => 0,
+        };
+        Self {
+            priority: (raw & P_MASK) as u8,
+            congestion_control: Some(congestion_control),
+            express: Some(raw & E_FLAG != 0),
+        }
+    }
+
+    pub fn transport(raw: u64) -> Self {
+        Self {
+            priority: (raw & P_MASK) as u8,
+            congestion_control: None,
+            express: None,
+        }
+    }
+
+    pub fn priority_name(&self) -> &'static str {
+        PRIORITY_NAMES
+            .iter()
+            .find(|(value, _)| *value == self.priority as u32)
+            .map_or("Unknown", |(_, name)| name)
+    }
+
+    pub fn congestion_control_name(&self) -> &'static str {
+        self.congestion_control
+            .and_then(|cc| {
+                CONGESTION_CONTROL_NAMES
+                    .iter()
+                    .find(|(value, _)| *value == cc as u32)
+            })
+            .map_or("Unknown", |(_, name)| name)
+    }
+
+    /// Add the decoded QoS fields below the `ext_qos` text item.
+    pub fn add_to_tree(&self, item: *mut epan_sys::proto_item, args: &TreeArgs) -> Result<()> {
+        let args = args.item_subtree(item, FIELD_QOS)?;
+        args.add_enum(FIELD_PRIORITY, self.priority as u32)?;
+        if let Some(congestion_control) = self.congestion_control {
+            args.add_enum(FIELD_CONGESTION_CONTROL, congestion_control as u32)?;
+        }
+        if let Some(express) = self.express {
+            args.add_bool(FIELD_EXPRESS, express)?;
+        }
+        Ok(())
+    }
+}
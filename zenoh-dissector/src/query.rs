@@ -0,0 +1,408 @@
+//! Matching of requests with their responses, and service response time statistics of queries.
+//!
+//! Requests are recorded per requesting side of the session during the first pass, keyed by
+//! their id. `Response` and `ResponseFinal` messages carry that id as `rid` in the other
+//! direction. Every reply is queued on the `zenoh.query` tap with the time of its request,
+//! feeding `Statistics > Service Response Time > Zenoh` (`-z zenoh,srt` in tshark), grouped by
+//! key expression and by queryable.
+
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    ffi::{c_char, c_int, c_void, CStr},
+};
+
+use anyhow::Result;
+use zenoh_protocol::{
+    network::{Mapping, NetworkBody, RequestId},
+    transport::TransportMessage,
+};
+
+use crate::{
+    alias, conversation,
+    header_field::{FieldKind, HeaderFieldMap, Registration},
+    key_expr,
+    session::Side,
+    timestamp::{capture_time_nanos, to_nstime},
+    tree::{set_generated, TreeArgs},
+    utils::{network_messages, packet_str},
+};
+
+pub const FIELD_QUERY: &str = "zenoh.query";
+pub const FIELD_REPLIES: &str = "zenoh.query.replies";
+pub const FIELD_RESPONSE_IN: &str = "zenoh.query.response_in";
+pub const FIELD_FINAL_IN: &str = "zenoh.query.final_in";
+pub const FIELD_REQUEST_IN: &str = "zenoh.query.request_in";
+pub const FIELD_TIME: &str = "zenoh.query.time";
+
+const TAP_NAME: &CStr = c"zenoh.query";
+
+/// Index of the tables in the service response time statistics.
+const SRT_BY_KEY_EXPR: usize = 0;
+const SRT_BY_QUERYABLE: usize = 1;
+
+static mut TAP_ID: c_int = -1;
+
+/// The query header fields, added below requests and their responses.
+pub struct Queries;
+
+impl Registration for Queries {
+    fn generate_hf_map(prefix: &str) -> HeaderFieldMap {
+        HeaderFieldMap::new()
+            .add(prefix.to_string(), "Query", FieldKind::Branch)
+            .add(format!("{prefix}.replies"), "Replies", FieldKind::Number)
+            .add(
+                format!("{prefix}.response_in"),
+                "Response in",
+                FieldKind::FrameNum,
+            )
+            .add(
+                format!("{prefix}.final_in"),
+                "Response Final in",
+                FieldKind::FrameNum,
+            )
+            .add(
+                format!("{prefix}.request_in"),
+                "Request in",
+                FieldKind::FrameNum,
+            )
+            .add(
+                format!("{prefix}.time"),
+                "Response Time",
+                FieldKind::RelativeTime,
+            )
+    }
+
+    fn generate_subtree_names(prefix: &str) -> Vec<String> {
+        vec![prefix.to_string()]
+    }
+}
+
+/// Requesting side and request id.
+type RequestKey = (Side, RequestId);
+
+/// A request, with its frame number and capture time in nanoseconds.
+#[derive(Debug, Clone)]
+struct RequestState {
+    frame: u32,
+    time: i128,
+    key_expr: Option<String>,
+    /// Frames of the `Response` messages.
+    replies: Vec<u32>,
+    /// Frame of the `ResponseFinal`.
+    final_in: Option<u32>,
+}
+
+thread_local! {
+    static REQUESTS: RefCell<HashMap<RequestKey, Vec<RequestState>>> = RefCell::default();
+}
+
+/// Register the tap and the service response time statistics.
+pub unsafe fn register(proto_id: c_int) {
+    TAP_ID = epan_sys::register_tap(TAP_NAME.as_ptr());
+    epan_sys::register_srt_table(
+        proto_id,
+        TAP_NAME.as_ptr(),
+        2,
+        Some(srt_packet),
+        Some(srt_init),
+        None,
+    );
+}
+
+/// Clear the recorded requests.
+pub(crate) fn init_requests() {
+    REQUESTS.with_borrow_mut(|requests| requests.clear());
+}
+
+/// Record the requests and the responses of a transport message.
+pub(crate) unsafe fn update_state(pinfo: *mut epan_sys::_packet_info, msg: &TransportMessage) {
+    let Some(side) = key_expr::declaring_side(pinfo, Mapping::Sender) else {
+        return;
+    };
+    let Some(peer) = key_expr::declaring_side(pinfo, Mapping::Receiver) else {
+        return;
+    };
+    let frame = (*pinfo).num;
+
+    for msg in &network_messages(pinfo, msg) {
+        match &msg.body {
+            NetworkBody::Request(request) => {
+                let state = RequestState {
+                    frame,
+                    time: capture_time_nanos(pinfo),
+                    key_expr: key_expr::resolve(pinfo, &request.wire_expr),
+                    replies: Vec::new(),
+                    final_in: None,
+                };
+                REQUESTS.with_borrow_mut(|requests| {
+                    requests
+                        .entry((side.clone(), request.id))
+                        .or_default()
+                        .push(state)
+                });
+            }
+            NetworkBody::Response(response) => REQUESTS.with_borrow_mut(|requests| {
+                let key = (peer.clone(), response.rid);
+                if let Some(request) = requests.get_mut(&key).and_then(|r| r.last_mut()) {
+                    if request.replies.last() != Some(&frame) {
+                        request.replies.push(frame);
+                    }
+                }
+            }),
+            NetworkBody::ResponseFinal(response_final) => REQUESTS.with_borrow_mut(|requests| {
+                let key = (peer.clone(), response_final.rid);
+                if let Some(request) = requests.get_mut(&key).and_then(|r| r.last_mut()) {
+                    request.final_in.get_or_insert(frame);
+                }
+            }),
+            _ => {}
+        }
+    }
+}
+
+/// Returns the request a response of the frame being dissected answers.
+unsafe fn find_request(pinfo: *mut epan_sys::_packet_info, rid: RequestId) -> Option<RequestState> {
+    let peer = key_expr::declaring_side(pinfo, Mapping::Receiver)?;
+    let frame = (*pinfo).num;
+    REQUESTS.with_borrow(|requests| {
+        requests
+            .get(&(peer, rid))?
+            .iter()
+            .rev()
+            .find(|request| request.frame <= frame)
+            .cloned()
+    })
+}
+
+/// Add the query subtree below requests, linking to their responses, and below responses,
+/// linking back to their request with the response time.
+pub(crate) fn add_to_tree(msg: &TransportMessage, args: &TreeArgs) -> Result<()> {
+    let Some(side) = (unsafe { key_expr::declaring_side(args.pinfo, Mapping::Sender) }) else {
+        return Ok(());
+    };
+    let frame = unsafe { (*args.pinfo).num };
+
+    for msg in &network_messages(args.pinfo, msg) {
+        let rid = match &msg.body {
+            NetworkBody::Request(request) => {
+                let request = REQUESTS.with_borrow(|requests| {
+                    requests
+                        .get(&(side.clone(), request.id))?
+                        .iter()
+                        .find(|r| r.frame == frame)
+                        .cloned()
+                });
+                if let Some(request) = request {
+                    add_request(&request, args)?;
+                }
+                continue;
+            }
+            NetworkBody::Response(response) => response.rid,
+            NetworkBody::ResponseFinal(response_final) => response_final.rid,
+            _ => continue,
+        };
+        let Some(request) = (unsafe { find_request(args.pinfo, rid) }) else {
+            continue;
+        };
+        let time = unsafe { capture_time_nanos(args.pinfo) } - request.time;
+        let args = args.make_subtree(
+            FIELD_QUERY,
+            &format!("Query, Response Time: {:.3} ms", time as f64 / 1e6),
+        )?;
+        set_generated(args.add_frame(FIELD_REQUEST_IN, request.frame)?);
+        set_generated(args.add_time(FIELD_TIME, &to_nstime(time))?);
+    }
+    Ok(())
+}
+
+fn add_request(request: &RequestState, args: &TreeArgs) -> Result<()> {
+    let args = args.make_subtree(
+        FIELD_QUERY,
+        &format!("Query, Replies: {}", request.replies.len()),
+    )?;
+    set_generated(args.add_number(FIELD_REPLIES, request.replies.len() as u64)?);
+    for reply in &request.replies {
+        set_generated(args.add_frame(FIELD_RESPONSE_IN, *reply)?);
+    }
+    if let Some(final_in) = request.final_in {
+        set_generated(args.add_frame(FIELD_FINAL_IN, final_in)?);
+    }
+    Ok(())
+}
+
+/// Data queued on the tap for every reply to a request.
+#[repr(C)]
+struct ReplyEvent {
+    request_time: epan_sys::nstime_t,
+    /// Key expression of the request and ZID of the queryable, allocated in the packet scope.
+    key_expr: *const c_char,
+    queryable: *const c_char,
+}
+
+/// Queue the replies of a transport message along with the time of their request.
+pub(crate) unsafe fn tap_replies(pinfo: *mut epan_sys::_packet_info, msg: &TransportMessage) {
+    if !epan_sys::have_tap_listener(TAP_ID) {
+        return;
+    }
+
+    for msg in &network_messages(pinfo, msg) {
+        let NetworkBody::Response(response) = &msg.body else {
+            continue;
+        };
+        let Some(request) = find_request(pinfo, response.rid) else {
+            continue;
+        };
+        let queryable = match &response.ext_respid {
+            Some(respid) => Some(respid.zid.to_string()),
+            None => conversation::source_zid(pinfo),
+        }
+        .map(|zid| alias::name(&zid));
+
+        let event = epan_sys::wmem_alloc0((*pinfo).pool, std::mem::size_of::<ReplyEvent>())
+            as *mut ReplyEvent;
+        *event = ReplyEvent {
+            request_time: to_nstime(request.time),
+            key_expr: packet_str(pinfo, request.key_expr),
+            queryable: packet_str(pinfo, queryable),
+        };
+        epan_sys::tap_queue_packet(TAP_ID, pinfo, event as *const c_void);
+    }
+}
+
+unsafe extern "C" fn srt_init(_srt: *mut epan_sys::register_srt, srt_array: *mut epan_sys::GArray) {
+    // Rows are added as key expressions and queryables are seen.
+    epan_sys::init_srt_table(
+        c"Zenoh Queries by Key Expression".as_ptr(),
+        c"Key Expressions".as_ptr(),
+        srt_array,
+        0,
+        c"Key Expression".as_ptr(),
+        std::ptr::null(),
+        std::ptr::null_mut(),
+    );
+    epan_sys::init_srt_table(
+        c"Zenoh Queries by Queryable".as_ptr(),
+        c"Queryables".as_ptr(),
+        srt_array,
+        0,
+        c"Queryable".as_ptr(),
+        std::ptr::null(),
+        std::ptr::null_mut(),
+    );
+}
+
+/// Returns the row of a procedure of the table, adding it if needed.
+unsafe fn srt_row(table: *mut epan_sys::srt_stat_table, procedure: &CStr) -> c_int {
+    let num_procs = (*table).num_procs;
+    let existing = (0..num_procs).find(|&index| {
+        let name = (*(*table).procedures.add(index as usize)).procedure;
+        !name.is_null() && CStr::from_ptr(name) == procedure
+    });
+    existing.unwrap_or_else(|| {
+        epan_sys::init_srt_table_row(table, num_procs, procedure.as_ptr());
+        num_procs
+    })
+}
+
+unsafe extern "C" fn srt_packet(
+    tapdata: *mut c_void,
+    pinfo: *mut epan_sys::packet_info,
+    _edt: *mut epan_sys::epan_dissect_t,
+    data: *const c_void,
+    _flags: epan_sys::tap_flags_t,
+) -> epan_sys::tap_packet_status {
+    let srt_array = (*(tapdata as *mut epan_sys::srt_data_t)).srt_array;
+    if (*srt_array).len <= SRT_BY_QUERYABLE as u32 {
+        return epan_sys::tap_packet_status_TAP_PACKET_DONT_REDRAW;
+    }
+    let event = &*(data as *const ReplyEvent);
+    let tables = (*srt_array).data as *mut *mut epan_sys::srt_stat_table;
+
+    for (index, procedure) in [
+        (SRT_BY_KEY_EXPR, event.key_expr),
+        (SRT_BY_QUERYABLE, event.queryable),
+    ] {
+        let table = *tables.add(index);
+        let procedure = if procedure.is_null() {
+            c"Unknown"
+        } else {
+            CStr::from_ptr(procedure)
+        };
+        let row = srt_row(table, procedure);
+        epan_sys::add_srt_table_data(table, row, &event.request_time, pinfo);
+    }
+    epan_sys::tap_packet_status_TAP_PACKET_REDRAW
+}
+
+#[cfg(test)]
+mod tests {
+    use zenoh_buffers::{writer::HasWriter, ZBuf};
+    use zenoh_codec::{WCodec, Zenoh080};
+    use zenoh_protocol::{
+        core::Reliability,
+        network::{response, NetworkMessage, Response},
+        transport::Fragment,
+        zenoh::{PushBody, Put, Reply, ResponseBody},
+    };
+
+    use super::*;
+    use crate::fragment;
+
+    fn response(rid: RequestId) -> NetworkMessage {
+        NetworkBody::Response(Response {
+            rid,
+            wire_expr: "demo/example".into(),
+            payload: ResponseBody::Reply(Reply {
+                consolidation: Default::default(),
+                ext_unknown: vec![],
+                payload: PushBody::Put(Put {
+                    payload: ZBuf::from(vec![0; 64]),
+                    ..Default::default()
+                }),
+            }),
+            ext_qos: response::ext::QoSType::DEFAULT,
+            ext_tstamp: None,
+            ext_respid: None,
+        })
+        .into()
+    }
+
+    #[test]
+    fn fragmented_response() {
+        // A response split across fragments is matched from the frame of its last fragment,
+        // like the responses of a `Frame`.
+        let mut bytes = Vec::new();
+        Zenoh080::new()
+            .write(&mut bytes.writer(), &response(42))
+            .unwrap();
+        let (head, tail) = bytes.split_at(bytes.len() / 2);
+        let fragments = [(1, head, true), (2, tail, false)].map(|(sn, payload, more)| Fragment {
+            reliability: Reliability::Reliable,
+            more,
+            sn,
+            payload: payload.to_vec().into(),
+            ext_qos: Default::default(),
+            ext_first: None,
+            ext_drop: None,
+        });
+        let mut pinfo: epan_sys::_packet_info = unsafe { std::mem::zeroed() };
+        for fragment in &fragments {
+            let channel = (Side::Link(1, 7447), true, fragment.ext_qos.priority() as u8);
+            fragment::collect(channel, fragment.sn, fragment, fragment.sn + 1);
+        }
+
+        let mut rids = |frame: u32, fragment: &Fragment| {
+            pinfo.num = frame;
+            network_messages(&mut pinfo, &fragment.clone().into())
+                .iter()
+                .filter_map(|msg| match &msg.body {
+                    NetworkBody::Response(response) => Some(response.rid),
+                    _ => None,
+                })
+                .collect::<Vec<RequestId>>()
+        };
+        assert!(rids(1, &fragments[0]).is_empty());
+        assert_eq!(rids(2, &fragments[1]), [42]);
+    }
+}
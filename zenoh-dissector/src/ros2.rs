@@ -0,0 +1,535 @@
+//! ROS 2 over Zenoh, as implemented by `rmw_zenoh`.
+//!
+//! Topics and services are mapped to key expressions of the form
+//! `<domain_id>/<name>/<type>/<type_hash>`, and graph entities are advertised by liveliness
+//! tokens of the form `@ros2_lv/<domain_id>/<zid>/<node_id>/<entity_id>/<kind>/<enclave>/
+//! <namespace>/<node>[/<name>/<type>/<type_hash>/<qos>]`, with `/` mangled as `%`.
+//!
+//! Payloads are CDR-decoded with the definitions found in the "ROS 2 Type Definitions"
+//! directory preference.
+
+use std::{cell::RefCell, ffi::CStr, path::Path};
+
+use anyhow::Result;
+use zenoh_buffers::buffer::SplitBuffer;
+use zenoh_protocol::{
+    core::WireExpr,
+    network::{declare::DeclareBody, NetworkBody, NetworkMessage},
+    transport::TransportMessage,
+    zenoh::{PushBody, RequestBody, ResponseBody},
+};
+
+use crate::{
+    cdr::{TypeRegistry, Value},
+    header_field::{FieldKind, HeaderFieldMap, Registration},
+    key_expr,
+    serialization::Reader,
+    timestamp::to_nstime,
+    tree::TreeArgs,
+    utils::network_messages,
+    ws_log,
+};
+
+pub const FIELD_ROS2: &str = "zenoh.ros2";
+pub const FIELD_DOMAIN_ID: &str = "zenoh.ros2.domain_id";
+pub const FIELD_NAME: &str = "zenoh.ros2.name";
+pub const FIELD_TYPE: &str = "zenoh.ros2.type";
+pub const FIELD_TYPE_HASH: &str = "zenoh.ros2.type_hash";
+pub const FIELD_ENTITY: &str = "zenoh.ros2.entity";
+pub const FIELD_ZID: &str = "zenoh.ros2.zid";
+pub const FIELD_NODE_ID: &str = "zenoh.ros2.node_id";
+pub const FIELD_ENTITY_ID: &str = "zenoh.ros2.entity_id";
+pub const FIELD_ENCLAVE: &str = "zenoh.ros2.enclave";
+pub const FIELD_NAMESPACE: &str = "zenoh.ros2.namespace";
+pub const FIELD_NODE: &str = "zenoh.ros2.node";
+pub const FIELD_QOS: &str = "zenoh.ros2.qos";
+pub const FIELD_SEQUENCE_NUMBER: &str = "zenoh.ros2.sequence_number";
+pub const FIELD_SOURCE_TIMESTAMP: &str = "zenoh.ros2.source_timestamp";
+pub const FIELD_SOURCE_GID: &str = "zenoh.ros2.source_gid";
+pub const FIELD_MESSAGE: &str = "zenoh.ros2.message";
+pub const FIELD_MESSAGE_FIELD: &str = "zenoh.ros2.message.field";
+
+/// Prefix of the liveliness tokens advertising the ROS graph.
+pub const LIVELINESS_PREFIX: &str = "@ros2_lv";
+
+/// The ROS 2 header fields.
+pub struct Ros2;
+
+impl Registration for Ros2 {
+    fn generate_hf_map(prefix: &str) -> HeaderFieldMap {
+        HeaderFieldMap::new()
+            .add(prefix.to_string(), "ROS 2", FieldKind::Branch)
+            .add(
+                format!("{prefix}.domain_id"),
+                "Domain ID",
+                FieldKind::Number,
+            )
+            .add(format!("{prefix}.name"), "Name", FieldKind::Text)
+            .add(format!("{prefix}.type"), "Type", FieldKind::Text)
+            .add(format!("{prefix}.type_hash"), "Type Hash", FieldKind::Text)
+            .add(format!("{prefix}.entity"), "Entity", FieldKind::Text)
+            .add(format!("{prefix}.zid"), "Session ZID", FieldKind::Text)
+            .add(format!("{prefix}.node_id"), "Node ID", FieldKind::Number)
+            .add(
+                format!("{prefix}.entity_id"),
+                "Entity ID",
+                FieldKind::Number,
+            )
+            .add(format!("{prefix}.enclave"), "Enclave", FieldKind::Text)
+            .add(format!("{prefix}.namespace"), "Namespace", FieldKind::Text)
+            .add(format!("{prefix}.node"), "Node", FieldKind::Text)
+            .add(format!("{prefix}.qos"), "QoS", FieldKind::Text)
+            .add(
+                format!("{prefix}.sequence_number"),
+                "Sequence Number",
+                FieldKind::Number,
+            )
+            .add(
+                format!("{prefix}.source_timestamp"),
+                "Source Timestamp",
+                FieldKind::AbsoluteTime,
+            )
+            .add(
+                format!("{prefix}.source_gid"),
+                "Source GID",
+                FieldKind::Bytes,
+            )
+            .add(format!("{prefix}.message"), "Message", FieldKind::Branch)
+            .add(format!("{prefix}.message.field"), "Field", FieldKind::Text)
+    }
+
+    fn generate_subtree_names(prefix: &str) -> Vec<String> {
+        vec![prefix.to_string(), format!("{prefix}.message")]
+    }
+}
+
+/// A topic or service key expression.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TopicKey {
+    pub domain_id: u32,
+    /// Fully qualified name, e.g. `/chatter`.
+    pub name: String,
+    /// Normalized type name, e.g. `std_msgs/msg/String`.
+    pub type_name: String,
+    pub type_hash: String,
+}
+
+impl TopicKey {
+    pub fn parse(key_expr: &str) -> Option<Self> {
+        let chunks = key_expr.split('/').collect::<Vec<_>>();
+        let [domain_id, name @ .., type_name, type_hash] = chunks.as_slice() else {
+            return None;
+        };
+        if name.is_empty()
+            || !(type_hash.starts_with("RIHS") || *type_hash == "TypeHashNotSupported")
+        {
+            return None;
+        }
+        Some(Self {
+            domain_id: domain_id.parse().ok()?,
+            name: format!("/{}", name.join("/")),
+            type_name: demangle_type(type_name),
+            type_hash: type_hash.to_string(),
+        })
+    }
+
+    pub fn is_service(&self) -> bool {
+        self.type_name.contains("/srv/")
+    }
+}
+
+/// Normalize a DDS type name, e.g. `std_msgs::msg::dds_::String_` into `std_msgs/msg/String`.
+fn demangle_type(type_name: &str) -> String {
+    let type_name = type_name.replace("::dds_::", "::");
+    let type_name = type_name.strip_suffix('_').unwrap_or(&type_name);
+    type_name.replace("::", "/")
+}
+
+/// Restore a name mangled in a liveliness token, e.g. `%my_ns` into `/my_ns`.
+fn demangle_name(name: &str) -> String {
+    name.replace('%', "/")
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EntityKind {
+    Node,
+    Publisher,
+    Subscription,
+    ServiceServer,
+    ServiceClient,
+}
+
+impl EntityKind {
+    fn parse(kind: &str) -> Option<Self> {
+        Some(match kind {
+            "NN" => Self::Node,
+            "MP" => Self::Publisher,
+            "MS" => Self::Subscription,
+            "SS" => Self::ServiceServer,
+            "SC" => Self::ServiceClient,
+            _ => return None,
+        })
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Node => "Node",
+            Self::Publisher => "Publisher",
+            Self::Subscription => "Subscription",
+            Self::ServiceServer => "Service Server",
+            Self::ServiceClient => "Service Client",
+        }
+    }
+}
+
+/// The topic or service of an entity advertised by a liveliness token.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TokenTopic {
+    pub name: String,
+    pub type_name: String,
+    pub type_hash: String,
+    pub qos: String,
+}
+
+/// A graph entity advertised by a liveliness token.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LivelinessToken {
+    pub domain_id: u32,
+    pub zid: String,
+    pub node_id: u64,
+    pub entity_id: u64,
+    pub kind: EntityKind,
+    pub enclave: String,
+    pub namespace: String,
+    pub node: String,
+    /// Absent for nodes.
+    pub topic: Option<TokenTopic>,
+}
+
+impl LivelinessToken {
+    pub fn parse(key_expr: &str) -> Option<Self> {
+        let chunks = key_expr.split('/').collect::<Vec<_>>();
+        let [LIVELINESS_PREFIX, domain_id, zid, node_id, entity_id, kind, enclave, namespace, node, topic @ ..] =
+            chunks.as_slice()
+        else {
+            return None;
+        };
+        let topic = match topic {
+            [] => None,
+            [name, type_name, type_hash, qos] => Some(TokenTopic {
+                name: demangle_name(name),
+                type_name: demangle_type(type_name),
+                type_hash: type_hash.to_string(),
+                qos: qos.to_string(),
+            }),
+            _ => return None,
+        };
+        Some(Self {
+            domain_id: domain_id.parse().ok()?,
+            zid: zid.to_string(),
+            node_id: node_id.parse().ok()?,
+            entity_id: entity_id.parse().ok()?,
+            kind: EntityKind::parse(kind)?,
+            enclave: demangle_name(enclave),
+            namespace: demangle_name(namespace),
+            node: node.to_string(),
+            topic,
+        })
+    }
+
+    /// Fully qualified name of the node, e.g. `/my_ns/talker`.
+    pub fn node_name(&self) -> String {
+        match self.namespace.as_str() {
+            "/" => format!("/{}", self.node),
+            namespace => format!("{namespace}/{}", self.node),
+        }
+    }
+}
+
+/// Length of the GIDs of the ROS 2 entities.
+const GID_LEN: usize = 16;
+
+/// The attachment of rmw_zenoh publications, queries and replies, serialized with zenoh-ext.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Attachment {
+    pub sequence_number: i64,
+    /// Nanoseconds since the Unix epoch.
+    pub source_timestamp: i64,
+    pub source_gid: [u8; 16],
+}
+
+impl Attachment {
+    pub fn parse(bytes: &[u8]) -> Option<Self> {
+        let mut reader = Reader::new(bytes);
+        fn key(reader: &mut Reader, expected: &str) -> Option<()> {
+            (reader.read_str().ok()? == expected).then_some(())
+        }
+
+        key(&mut reader, "sequence_number")?;
+        let sequence_number = reader.read_int(8).ok()? as i64;
+        key(&mut reader, "source_timestamp")?;
+        let source_timestamp = reader.read_int(8).ok()? as i64;
+        key(&mut reader, "source_gid")?;
+        // Some serializers prefix fixed-size arrays with their length.
+        if reader.bytes.len() > GID_LEN && reader.read_len().ok()? != GID_LEN {
+            return None;
+        }
+        let source_gid = reader.take(GID_LEN).ok()?.try_into().ok()?;
+        Some(Self {
+            sequence_number,
+            source_timestamp,
+            source_gid,
+        })
+    }
+}
+
+thread_local! {
+    static TYPES: RefCell<TypeRegistry> = RefCell::default();
+}
+
+/// (Re)load the type definitions if the directory preference changed, called when the
+/// preferences are applied.
+pub unsafe fn load_types() {
+    let dir = crate::ROS2_TYPE_DIR;
+    let dir = (!dir.is_null())
+        .then(|| CStr::from_ptr(dir).to_str().ok())
+        .flatten()
+        .filter(|dir| !dir.is_empty());
+    TYPES.with_borrow_mut(|types| {
+        if types.dir.as_deref() == dir.map(Path::new) {
+            return;
+        }
+        match dir {
+            Some(dir) => {
+                *types = TypeRegistry::load(Path::new(dir));
+                ws_log::message!("Loaded {} ROS 2 type definitions from {dir}", types.len());
+            }
+            None => *types = TypeRegistry::default(),
+        }
+    });
+}
+
+/// Run `f` with the type definitions, if a directory is set.
+fn with_types<R>(f: impl FnOnce(&TypeRegistry) -> R) -> Option<R> {
+    TYPES.with_borrow(|types| types.dir.is_some().then(|| f(types)))
+}
+
+/// Returns the key expression carried by a network message, with the attachment of the
+/// publication, query or reply if any.
+fn wire_expr_of(msg: &NetworkMessage) -> Option<(&WireExpr<'static>, Option<&[u8]>)> {
+    fn attachment<const ID: u8>(
+        ext: &Option<zenoh_protocol::zenoh::ext::AttachmentType<ID>>,
+    ) -> Option<&[u8]> {
+        ext.as_ref()
+            .map(|ext| ext.buffer.contiguous())
+            .and_then(|cow| match cow {
+                std::borrow::Cow::Borrowed(bytes) => Some(bytes),
+                // Attachments spanning several slices are not decoded.
+                std::borrow::Cow::Owned(_) => None,
+            })
+    }
+
+    match &msg.body {
+        NetworkBody::Push(push) => match &push.payload {
+            PushBody::Put(put) => Some((&push.wire_expr, attachment(&put.ext_attachment))),
+            PushBody::Del(del) => Some((&push.wire_expr, attachment(&del.ext_attachment))),
+        },
+        NetworkBody::Request(request) => match &request.payload {
+            RequestBody::Query(query) => {
+                Some((&request.wire_expr, attachment(&query.ext_attachment)))
+            }
+        },
+        NetworkBody::Response(response) => match &response.payload {
+            ResponseBody::Reply(reply) => match &reply.payload {
+                PushBody::Put(put) => Some((&response.wire_expr, attachment(&put.ext_attachment))),
+                PushBody::Del(del) => Some((&response.wire_expr, attachment(&del.ext_attachment))),
+            },
+            ResponseBody::Err(_) => Some((&response.wire_expr, None)),
+        },
+        NetworkBody::Declare(declare) => match &declare.body {
+            DeclareBody::DeclareToken(token) => Some((&token.wire_expr, None)),
+            DeclareBody::DeclareSubscriber(subscriber) => Some((&subscriber.wire_expr, None)),
+            DeclareBody::DeclareQueryable(queryable) => Some((&queryable.wire_expr, None)),
+            _ => None,
+        },
+        NetworkBody::Interest(interest) => interest.wire_expr.as_ref().map(|w| (w, None)),
+        _ => None,
+    }
+}
+
+/// Add the ROS 2 details of every network message carried by a transport message.
+pub(crate) fn dissect_messages(msg: &TransportMessage, args: &TreeArgs) -> Result<()> {
+    for msg in &network_messages(args.pinfo, msg) {
+        let Some((wire_expr, attachment)) = wire_expr_of(msg) else {
+            continue;
+        };
+        let Some(key_expr) = key_expr::resolve(args.pinfo, wire_expr) else {
+            continue;
+        };
+        if let Some(token) = LivelinessToken::parse(&key_expr) {
+            add_token(&token, args)?;
+        } else if let Some(topic) = TopicKey::parse(&key_expr) {
+            add_topic(&topic, attachment.and_then(Attachment::parse), args)?;
+        }
+    }
+    Ok(())
+}
+
+fn add_token(token: &LivelinessToken, args: &TreeArgs) -> Result<()> {
+    let label = match &token.topic {
+        Some(topic) => format!(
+            "ROS 2 {}: {} [{}]",
+            token.kind.name(),
+            topic.name,
+            topic.type_name
+        ),
+        None => format!("ROS 2 {}: {}", token.kind.name(), token.node_name()),
+    };
+    let args = args.make_subtree(FIELD_ROS2, &label)?;
+    args.add_text(FIELD_ENTITY, token.kind.name())?;
+    args.add_number(FIELD_DOMAIN_ID, token.domain_id as _)?;
+    args.add_text(FIELD_ZID, &token.zid)?;
+    args.add_number(FIELD_NODE_ID, token.node_id)?;
+    args.add_number(FIELD_ENTITY_ID, token.entity_id)?;
+    args.add_text(FIELD_ENCLAVE, &token.enclave)?;
+    args.add_text(FIELD_NAMESPACE, &token.namespace)?;
+    args.add_text(FIELD_NODE, &token.node)?;
+    if let Some(topic) = &token.topic {
+        args.add_text(FIELD_NAME, &topic.name)?;
+        args.add_text(FIELD_TYPE, &topic.type_name)?;
+        args.add_text(FIELD_TYPE_HASH, &topic.type_hash)?;
+        args.add_text(FIELD_QOS, &topic.qos)?;
+    }
+    Ok(())
+}
+
+fn add_topic(topic: &TopicKey, attachment: Option<Attachment>, args: &TreeArgs) -> Result<()> {
+    let kind = if topic.is_service() {
+        "Service"
+    } else {
+        "Topic"
+    };
+    let args = args.make_subtree(
+        FIELD_ROS2,
+        &format!("ROS 2 {kind}: {} [{}]", topic.name, topic.type_name),
+    )?;
+    args.add_number(FIELD_DOMAIN_ID, topic.domain_id as _)?;
+    args.add_text(FIELD_NAME, &topic.name)?;
+    args.add_text(FIELD_TYPE, &topic.type_name)?;
+    args.add_text(FIELD_TYPE_HASH, &topic.type_hash)?;
+    if let Some(attachment) = attachment {
+        args.add_number(FIELD_SEQUENCE_NUMBER, attachment.sequence_number as _)?;
+        args.add_time(
+            FIELD_SOURCE_TIMESTAMP,
+            &to_nstime(attachment.source_timestamp as _),
+        )?;
+        args.add_bytes(FIELD_SOURCE_GID, &attachment.source_gid)?;
+    }
+    Ok(())
+}
+
+/// CDR-decode the payload of a publication, query or reply on a ROS 2 topic or service.
+///
+/// Returns `false` if the key expression is not a ROS 2 one or the type is unknown.
+pub(crate) fn dissect_payload(
+    key_expr: &str,
+    kind: &str,
+    bytes: &[u8],
+    args: &TreeArgs,
+) -> Result<bool> {
+    let Some(topic) = TopicKey::parse(key_expr) else {
+        return Ok(false);
+    };
+    let type_name = match (topic.is_service(), kind) {
+        (false, _) => topic.type_name,
+        (true, "Query") => format!("{}_Request", topic.type_name),
+        (true, "Reply") => format!("{}_Response", topic.type_name),
+        (true, _) => return Ok(false),
+    };
+    let Some(decoded) = with_types(|types| types.decode(&type_name, bytes)) else {
+        return Ok(false);
+    };
+    let value = match decoded {
+        Ok(value) => value,
+        Err(err) => {
+            ws_log::debug!("failed to decode {type_name}: {err}");
+            return Ok(false);
+        }
+    };
+
+    let args = args.make_subtree(FIELD_MESSAGE, &format!("ROS 2 Message: {type_name}"))?;
+    if let Value::Message(fields) = &value {
+        for (name, value) in fields {
+            args.add_value(FIELD_MESSAGE, FIELD_MESSAGE_FIELD, name, value)?;
+        }
+    }
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GID: [u8; GID_LEN] = [7; GID_LEN];
+
+    fn attachment(gid: &[u8]) -> Vec<u8> {
+        [
+            &b"\x0fsequence_number"[..],
+            &42i64.to_le_bytes(),
+            b"\x10source_timestamp",
+            &1_700_000_000_000_000_000i64.to_le_bytes(),
+            b"\x0asource_gid",
+            gid,
+        ]
+        .concat()
+    }
+
+    #[test]
+    fn parse_attachment() {
+        let expected = Attachment {
+            sequence_number: 42,
+            source_timestamp: 1_700_000_000_000_000_000,
+            source_gid: GID,
+        };
+        assert_eq!(Attachment::parse(&attachment(&GID)), Some(expected));
+        // The GID may be prefixed with its length.
+        let prefixed = [&[GID_LEN as u8][..], &GID].concat();
+        assert_eq!(Attachment::parse(&attachment(&prefixed)), Some(expected));
+    }
+
+    #[test]
+    fn parse_malformed_attachment() {
+        // Truncated GID, and a longer one whose first byte isn't its length
+        assert_eq!(Attachment::parse(&attachment(&GID[1..])), None);
+        let longer = [&[GID_LEN as u8 + 1][..], &GID, &[0]].concat();
+        assert_eq!(Attachment::parse(&attachment(&longer)), None);
+        // Unexpected key and truncated number
+        let mut renamed = attachment(&GID);
+        renamed[1] = b'S';
+        assert_eq!(Attachment::parse(&renamed), None);
+        assert_eq!(Attachment::parse(&attachment(&GID)[..20]), None);
+    }
+
+    #[test]
+    fn parse_topic_keys() {
+        assert_eq!(
+            TopicKey::parse("0/my_ns/chatter/std_msgs::msg::dds_::String_/RIHS01_abc"),
+            Some(TopicKey {
+                domain_id: 0,
+                name: "/my_ns/chatter".to_string(),
+                type_name: "std_msgs/msg/String".to_string(),
+                type_hash: "RIHS01_abc".to_string(),
+            })
+        );
+        assert!(
+            TopicKey::parse("0/add_two_ints/pkg::srv::dds_::AddTwoInts_/TypeHashNotSupported")
+                .unwrap()
+                .is_service()
+        );
+        assert_eq!(
+            TopicKey::parse("0/std_msgs::msg::dds_::String_/RIHS01_abc"),
+            None
+        );
+        assert_eq!(TopicKey::parse("demo/example/test"), None);
+    }
+}
@@ -0,0 +1,509 @@
+//! Reconstruction of the ROS 2 graph from the liveliness tokens of rmw_zenoh.
+//!
+//! Every `DeclareToken` and `UndeclareToken` on a `@ros2_lv` key expression is queued on the
+//! `zenoh.ros2` tap. The graph is shown by the `Zenoh/ROS 2 Graph` statistics tree
+//! (`-z zenoh.ros2.graph,tree` in tshark) and printed as DOT by `-z zenoh.ros2.dot`. Snapshots of
+//! the graph as of every frame with tokens are listed in `File > Export Objects > Zenoh`.
+//!
+//! `UndeclareToken` usually only carries the token id, so the key expressions of declared
+//! tokens are recorded per declaring side of the session during the first pass.
+
+use std::{
+    cell::RefCell,
+    collections::{BTreeMap, BTreeSet, HashMap},
+    ffi::{c_char, c_int, c_void, CStr, CString},
+    fmt::Write,
+};
+
+use zenoh_protocol::{
+    network::{
+        declare::{DeclareBody, TokenId, UndeclareToken},
+        Mapping, NetworkBody,
+    },
+    transport::TransportMessage,
+};
+
+use crate::{
+    key_expr,
+    ros2::{EntityKind, LivelinessToken, LIVELINESS_PREFIX},
+    session::Side,
+    utils::{network_messages, register_cli_listener, CliListener},
+};
+
+const TAP_NAME: &CStr = c"zenoh.ros2";
+const DOT_CLI: &CStr = c"zenoh.ros2.dot";
+
+static mut TAP_ID: c_int = -1;
+
+static mut DOT_UI: epan_sys::stat_tap_ui = epan_sys::stat_tap_ui {
+    group: epan_sys::register_stat_group_t_REGISTER_STAT_GROUP_GENERIC,
+    title: c"ROS 2 Graph (DOT)".as_ptr(),
+    cli_string: DOT_CLI.as_ptr(),
+    tap_init_cb: Some(dot_init),
+    nparams: 0,
+    params: std::ptr::null_mut(),
+};
+
+/// Declaring side and token id.
+type TokenKey = (Side, TokenId);
+
+thread_local! {
+    /// Key expressions of the declared tokens, with the frame they were declared in.
+    static TOKENS: RefCell<HashMap<TokenKey, Vec<(u32, String)>>> = RefCell::default();
+    /// ROS 2 graph of the export objects.
+    static GRAPH_OBJECTS: RefCell<Graph> = RefCell::default();
+}
+
+/// Data queued on the tap for every liveliness token of the ROS 2 graph.
+#[repr(C)]
+struct TokenEvent {
+    declared: bool,
+    /// Key expression of the token, allocated in the packet scope.
+    key_expr: *const c_char,
+}
+
+/// Register the tap, the statistics tree and the DOT output.
+pub unsafe fn register() {
+    TAP_ID = epan_sys::register_tap(TAP_NAME.as_ptr());
+    epan_sys::stats_tree_register_plugin(
+        TAP_NAME.as_ptr(),
+        c"zenoh.ros2.graph".as_ptr(),
+        c"Zenoh/ROS 2 Graph".as_ptr(),
+        0,
+        Some(stats_tree_packet),
+        Some(stats_tree_init),
+        None,
+    );
+    epan_sys::register_stat_tap_ui(&raw mut DOT_UI, std::ptr::null_mut());
+}
+
+/// Clear the recorded tokens.
+pub(crate) fn init_tokens() {
+    TOKENS.with_borrow_mut(|tokens| tokens.clear());
+}
+
+/// Record the tokens declared by a transport message.
+pub(crate) unsafe fn update_state(pinfo: *mut epan_sys::_packet_info, msg: &TransportMessage) {
+    let Some(side) = key_expr::declaring_side(pinfo, Mapping::Sender) else {
+        return;
+    };
+
+    for msg in &network_messages(pinfo, msg) {
+        let NetworkBody::Declare(declare) = &msg.body else {
+            continue;
+        };
+        let DeclareBody::DeclareToken(decl) = &declare.body else {
+            continue;
+        };
+        let Some(key_expr) = key_expr::resolve(pinfo, &decl.wire_expr) else {
+            continue;
+        };
+        TOKENS.with_borrow_mut(|tokens| {
+            tokens
+                .entry((side.clone(), decl.id))
+                .or_default()
+                .push(((*pinfo).num, key_expr))
+        });
+    }
+}
+
+/// Returns the key expression of an undeclared token, as of the frame being dissected.
+fn undeclared_key_expr(
+    pinfo: *mut epan_sys::_packet_info,
+    undecl: &UndeclareToken,
+) -> Option<String> {
+    if !undecl.ext_wire_expr.is_null() {
+        return key_expr::resolve(pinfo, &undecl.ext_wire_expr.wire_expr);
+    }
+    let side = unsafe { key_expr::declaring_side(pinfo, Mapping::Sender)? };
+    let frame = unsafe { (*pinfo).num };
+    TOKENS.with_borrow(|tokens| {
+        tokens
+            .get(&(side, undecl.id))?
+            .iter()
+            .rev()
+            .find(|(declared, _)| *declared <= frame)
+            .map(|(_, key_expr)| key_expr.clone())
+    })
+}
+
+/// Queue the ROS 2 liveliness tokens declared or undeclared by a transport message.
+pub(crate) unsafe fn tap_tokens(pinfo: *mut epan_sys::_packet_info, msg: &TransportMessage) {
+    if !epan_sys::have_tap_listener(TAP_ID) {
+        return;
+    }
+
+    for event in token_events(pinfo, msg) {
+        epan_sys::tap_queue_packet(TAP_ID, pinfo, event);
+    }
+}
+
+/// Returns the events of the ROS 2 liveliness tokens declared or undeclared by a transport
+/// message, allocated in the packet scope.
+pub(crate) unsafe fn token_events(
+    pinfo: *mut epan_sys::_packet_info,
+    msg: &TransportMessage,
+) -> Vec<*const c_void> {
+    let mut events = Vec::new();
+    for msg in &network_messages(pinfo, msg) {
+        let NetworkBody::Declare(declare) = &msg.body else {
+            continue;
+        };
+        let (declared, key_expr) = match &declare.body {
+            DeclareBody::DeclareToken(decl) => (true, key_expr::resolve(pinfo, &decl.wire_expr)),
+            DeclareBody::UndeclareToken(undecl) => (false, undeclared_key_expr(pinfo, undecl)),
+            _ => continue,
+        };
+        let Some(key_expr) = key_expr
+            .filter(|key_expr| key_expr.starts_with(LIVELINESS_PREFIX))
+            .and_then(|key_expr| CString::new(key_expr).ok())
+        else {
+            continue;
+        };
+
+        let scope = (*pinfo).pool;
+        let event =
+            epan_sys::wmem_alloc0(scope, std::mem::size_of::<TokenEvent>()) as *mut TokenEvent;
+        (*event).declared = declared;
+        (*event).key_expr = epan_sys::wmem_strdup(scope, key_expr.as_ptr());
+        events.push(event as *const c_void);
+    }
+    events
+}
+
+/// Returns the state, key expression and parsed token of a tapped token event.
+unsafe fn tapped_token<'a>(data: *const c_void) -> Option<(bool, &'a str, LivelinessToken)> {
+    let event = &*(data as *const TokenEvent);
+    let key_expr = CStr::from_ptr(event.key_expr).to_str().ok()?;
+    Some((event.declared, key_expr, LivelinessToken::parse(key_expr)?))
+}
+
+/// Frame number and time relative to the first frame of a token event.
+#[derive(Debug, Clone, Copy)]
+struct Seen {
+    frame: u32,
+    time: f64,
+}
+
+impl Seen {
+    unsafe fn of(pinfo: *const epan_sys::_packet_info) -> Self {
+        let rel_ts = (*pinfo).rel_ts;
+        Self {
+            frame: (*pinfo).num,
+            time: rel_ts.secs as f64 + rel_ts.nsecs as f64 / 1e9,
+        }
+    }
+}
+
+impl std::fmt::Display for Seen {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:.6} s (frame {})", self.time, self.frame)
+    }
+}
+
+unsafe extern "C" fn stats_tree_init(st: *mut epan_sys::stats_tree) {
+    for name in [c"Nodes", c"Topics", c"Services"] {
+        epan_sys::stats_tree_create_node(
+            st,
+            name.as_ptr(),
+            0,
+            epan_sys::_stat_node_datatype_STAT_DT_INT,
+            true,
+        );
+    }
+}
+
+/// Increment the counter of a node of the tree, creating it if needed, and returns its id.
+unsafe fn tick(st: *mut epan_sys::stats_tree, name: &str, parent: c_int, children: bool) -> c_int {
+    let name = CString::new(name).unwrap_or_default();
+    epan_sys::stats_tree_manip_node_int(
+        epan_sys::_manip_node_mode_MN_INCREASE,
+        st,
+        name.as_ptr(),
+        parent,
+        children,
+        1,
+    )
+}
+
+unsafe extern "C" fn stats_tree_packet(
+    st: *mut epan_sys::stats_tree,
+    pinfo: *mut epan_sys::packet_info,
+    _edt: *mut epan_sys::epan_dissect_t,
+    data: *const c_void,
+    _flags: epan_sys::tap_flags_t,
+) -> epan_sys::tap_packet_status {
+    let Some((declared, _, token)) = tapped_token(data) else {
+        return epan_sys::tap_packet_status_TAP_PACKET_DONT_REDRAW;
+    };
+    let node_name = token.node_name();
+
+    // Nodes > node > entity > appearances and disappearances
+    let nodes = tick(st, "Nodes", 0, true);
+    let node = tick(st, &node_name, nodes, true);
+    let entity = match &token.topic {
+        Some(topic) => tick(
+            st,
+            &format!("{} {} [{}]", token.kind.name(), topic.name, topic.type_name),
+            node,
+            true,
+        ),
+        None => node,
+    };
+    let state = if declared { "Appeared" } else { "Disappeared" };
+    tick(
+        st,
+        &format!("{state} at {}", Seen::of(pinfo)),
+        entity,
+        false,
+    );
+
+    // Topics or Services > topic > entity of node
+    if let Some(topic) = &token.topic {
+        let root = match token.kind {
+            EntityKind::ServiceServer | EntityKind::ServiceClient => "Services",
+            _ => "Topics",
+        };
+        let root = tick(st, root, 0, true);
+        let topic = tick(
+            st,
+            &format!("{} [{}]", topic.name, topic.type_name),
+            root,
+            true,
+        );
+        tick(
+            st,
+            &format!("{}: {node_name}", token.kind.name()),
+            topic,
+            false,
+        );
+    }
+
+    epan_sys::tap_packet_status_TAP_PACKET_REDRAW
+}
+
+/// A graph entity with the frames it appeared and disappeared in, when captured.
+#[derive(Debug)]
+struct Entity {
+    token: LivelinessToken,
+    appeared: Option<Seen>,
+    disappeared: Option<Seen>,
+}
+
+impl Entity {
+    fn lifetime(&self) -> String {
+        match (self.appeared, self.disappeared) {
+            (Some(appeared), Some(disappeared)) => {
+                format!("{:.3} s - {:.3} s", appeared.time, disappeared.time)
+            }
+            (Some(appeared), None) => format!("from {:.3} s", appeared.time),
+            (None, Some(disappeared)) => format!("until {:.3} s", disappeared.time),
+            (None, None) => String::new(),
+        }
+    }
+}
+
+/// The ROS 2 graph over the whole capture.
+#[derive(Debug, Default)]
+struct Graph {
+    entities: Vec<Entity>,
+    /// Index of the latest entity of every token key expression.
+    latest: HashMap<String, usize>,
+}
+
+impl Graph {
+    fn update(&mut self, declared: bool, key_expr: &str, token: LivelinessToken, seen: Seen) {
+        let latest = self
+            .latest
+            .get(key_expr)
+            .map(|&index| &mut self.entities[index])
+            .filter(|entity| entity.disappeared.is_none());
+        match (declared, latest) {
+            // The same token is seen over several sessions.
+            (true, Some(_)) => {}
+            (false, Some(entity)) => entity.disappeared = Some(seen),
+            (declared, None) => {
+                self.latest
+                    .insert(key_expr.to_string(), self.entities.len());
+                self.entities.push(Entity {
+                    token,
+                    appeared: declared.then_some(seen),
+                    disappeared: (!declared).then_some(seen),
+                });
+            }
+        }
+    }
+
+    fn to_dot(&self) -> String {
+        fn quote(s: &str) -> String {
+            let s = s.replace('\\', "\\\\").replace('"', "\\\"");
+            format!("\"{}\"", s.replace('\n', "\\n"))
+        }
+
+        let mut nodes = BTreeMap::new();
+        let mut topics = BTreeSet::new();
+        let mut edges = Vec::new();
+        for entity in &self.entities {
+            let token = &entity.token;
+            let node_id = format!("node:{}:{}", token.domain_id, token.node_name());
+            let node_label = nodes.entry(node_id.clone()).or_insert(token.node_name());
+            let Some(topic) = &token.topic else {
+                *node_label = format!("{}\n{}", token.node_name(), entity.lifetime());
+                continue;
+            };
+            let is_service = matches!(
+                token.kind,
+                EntityKind::ServiceServer | EntityKind::ServiceClient
+            );
+            let topic_id = format!(
+                "{}:{}:{}",
+                if is_service { "service" } else { "topic" },
+                token.domain_id,
+                topic.name
+            );
+            topics.insert((
+                topic_id.clone(),
+                format!("{}\n{}", topic.name, topic.type_name),
+                is_service,
+            ));
+            let (from, to) = match token.kind {
+                EntityKind::Publisher | EntityKind::ServiceClient => (node_id, topic_id),
+                _ => (topic_id, node_id),
+            };
+            edges.push((from, to, entity));
+        }
+
+        let mut dot = String::from("digraph ros2 {\n    rankdir=LR;\n");
+        for (id, label) in &nodes {
+            let _ = writeln!(
+                dot,
+                "    {} [shape=box, label={}];",
+                quote(id),
+                quote(label)
+            );
+        }
+        for (id, label, is_service) in &topics {
+            let shape = if *is_service { "diamond" } else { "ellipse" };
+            let _ = writeln!(
+                dot,
+                "    {} [shape={shape}, label={}];",
+                quote(id),
+                quote(label)
+            );
+        }
+        for (from, to, entity) in edges {
+            let style = if entity.disappeared.is_some() {
+                "dashed"
+            } else {
+                "solid"
+            };
+            let _ = writeln!(
+                dot,
+                "    {} -> {} [style={style}, label={}];",
+                quote(&from),
+                quote(&to),
+                quote(&entity.lifetime())
+            );
+        }
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+impl CliListener for Graph {
+    fn reset(&mut self) {
+        *self = Graph::default();
+    }
+
+    unsafe fn packet(&mut self, pinfo: *const epan_sys::_packet_info, data: *const c_void) -> bool {
+        let Some((declared, key_expr, token)) = tapped_token(data) else {
+            return false;
+        };
+        self.update(declared, key_expr, token, Seen::of(pinfo));
+        true
+    }
+
+    fn draw(&self) {
+        print!("{}", self.to_dot());
+    }
+}
+
+unsafe extern "C" fn dot_init(opt_arg: *const c_char, _userdata: *mut c_void) {
+    register_cli_listener(TAP_NAME, DOT_CLI, opt_arg, Graph::default());
+}
+
+/// Update the ROS 2 graph saved from `File > Export Objects > Zenoh` with the tokens tapped in a
+/// frame, and returns the file name, content type and content of its DOT snapshot as of that
+/// frame.
+pub(crate) unsafe fn graph_objects(
+    pinfo: *const epan_sys::_packet_info,
+    events: &[*const c_void],
+) -> [(String, &'static CStr, String); 1] {
+    GRAPH_OBJECTS.with_borrow_mut(|graph| {
+        for &event in events {
+            graph.packet(pinfo, event);
+        }
+        [(
+            format!("ros2-graph-{}.dot", (*pinfo).num),
+            c"text/vnd.graphviz",
+            graph.to_dot(),
+        )]
+    })
+}
+
+/// Clear the ROS 2 graph of the export objects, called whenever they are listed again.
+pub(crate) fn reset_graph_objects() {
+    GRAPH_OBJECTS.with_borrow_mut(|graph| graph.reset());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NODE: &CStr = c"@ros2_lv/0/aabb/0/0/NN/%/%/talker";
+    const PUBLISHER: &CStr =
+        c"@ros2_lv/0/aabb/0/10/MP/%/%/talker/%chatter/std_msgs::msg::dds_::String_/RIHS01_ab/::,7:,:,:,,";
+
+    fn snapshot(frame: u32, tokens: &[(bool, &CStr)]) -> (String, String) {
+        let events = tokens
+            .iter()
+            .map(|&(declared, key_expr)| TokenEvent {
+                declared,
+                key_expr: key_expr.as_ptr(),
+            })
+            .collect::<Vec<_>>();
+        let events = events
+            .iter()
+            .map(|event| event as *const TokenEvent as *const c_void)
+            .collect::<Vec<_>>();
+        let mut pinfo: epan_sys::_packet_info = unsafe { std::mem::zeroed() };
+        pinfo.num = frame;
+        let [(filename, content_type, dot)] = unsafe { graph_objects(&pinfo, &events) };
+        assert_eq!(content_type, c"text/vnd.graphviz");
+        (filename, dot)
+    }
+
+    #[test]
+    fn graph_snapshots() {
+        reset_graph_objects();
+
+        let (filename, dot) = snapshot(1, &[(true, NODE), (true, PUBLISHER)]);
+        assert_eq!(filename, "ros2-graph-1.dot");
+        assert!(dot.contains("\"node:0:/talker\" [shape=box"), "{dot}");
+        assert!(
+            dot.contains("label=\"/chatter\\nstd_msgs/msg/String\""),
+            "{dot}"
+        );
+        assert!(dot.contains("[style=solid"), "{dot}");
+
+        // Snapshots accumulate the tokens of the previous frames.
+        let (filename, dot) = snapshot(2, &[(false, PUBLISHER)]);
+        assert_eq!(filename, "ros2-graph-2.dot");
+        assert!(dot.contains("\"node:0:/talker\" [shape=box"), "{dot}");
+        assert!(dot.contains("[style=dashed"), "{dot}");
+
+        reset_graph_objects();
+        let (_, dot) = snapshot(3, &[]);
+        assert!(!dot.contains("talker"), "{dot}");
+    }
+}
@@ -0,0 +1,492 @@
+//! Parsing of query selectors: the key expression and `;`-separated parameters of a `Request`
+//! carrying a `Query`, along with the consolidation, target, budget and timeout of the query.
+//!
+//! The `_time` parameter holds a time range, e.g. `[now(-1h)..]` or
+//! `[2024-01-01T00:00:00Z..now()[`, whose bounds are either fixed RFC 3339 times or offsets from
+//! the time of the query. See `zenoh_util::time_range` and `zenoh_protocol::core::parameters`.
+
+use anyhow::{anyhow, bail, Result};
+use zenoh_protocol::{
+    network::{request::ext::QueryTarget, NetworkBody, Request},
+    transport::TransportMessage,
+    zenoh::{ConsolidationMode, RequestBody},
+};
+
+use crate::{
+    expert,
+    header_field::{FieldKind, HeaderFieldMap, Registration},
+    key_expr,
+    timestamp::{capture_time_nanos, to_nstime, NANOS_PER_SEC},
+    tree::{set_generated, TreeArgs},
+    utils::network_messages,
+};
+
+pub const FIELD_SELECTOR: &str = "zenoh.selector";
+pub const FIELD_KEY_EXPR: &str = "zenoh.selector.key_expr";
+pub const FIELD_PARAMETER: &str = "zenoh.selector.parameter";
+pub const FIELD_PARAMETER_NAME: &str = "zenoh.selector.parameter.name";
+pub const FIELD_PARAMETER_VALUE: &str = "zenoh.selector.parameter.value";
+pub const FIELD_TIME: &str = "zenoh.selector.time";
+pub const FIELD_TIME_START: &str = "zenoh.selector.time.start";
+pub const FIELD_TIME_START_OFFSET: &str = "zenoh.selector.time.start_offset";
+pub const FIELD_TIME_START_INCLUSIVE: &str = "zenoh.selector.time.start_inclusive";
+pub const FIELD_TIME_END: &str = "zenoh.selector.time.end";
+pub const FIELD_TIME_END_OFFSET: &str = "zenoh.selector.time.end_offset";
+pub const FIELD_TIME_END_INCLUSIVE: &str = "zenoh.selector.time.end_inclusive";
+pub const FIELD_ANYKE: &str = "zenoh.selector.anyke";
+pub const FIELD_CONSOLIDATION: &str = "zenoh.selector.consolidation";
+pub const FIELD_TARGET: &str = "zenoh.selector.target";
+pub const FIELD_BUDGET: &str = "zenoh.selector.budget";
+pub const FIELD_TIMEOUT: &str = "zenoh.selector.timeout";
+
+const CONSOLIDATION_NAMES: &[(u32, &str)] =
+    &[(0, "Auto"), (1, "None"), (2, "Monotonic"), (3, "Latest")];
+
+const TARGET_NAMES: &[(u32, &str)] = &[(0, "BestMatching"), (1, "All"), (2, "AllComplete")];
+
+const LIST_SEPARATOR: char = ';';
+const FIELD_SEPARATOR: char = '=';
+const TIME_RANGE_KEY: &str = "_time";
+const ANYKE_KEY: &str = "_anyke";
+
+/// The selector header fields, added below requests carrying a query.
+pub struct Selector;
+
+impl Registration for Selector {
+    fn generate_hf_map(prefix: &str) -> HeaderFieldMap {
+        HeaderFieldMap::new()
+            .add(prefix.to_string(), "Selector", FieldKind::Branch)
+            .add(
+                format!("{prefix}.key_expr"),
+                "Key Expression",
+                FieldKind::Text,
+            )
+            .add(
+                format!("{prefix}.parameter"),
+                "Parameter",
+                FieldKind::Branch,
+            )
+            .add(format!("{prefix}.parameter.name"), "Name", FieldKind::Text)
+            .add(
+                format!("{prefix}.parameter.value"),
+                "Value",
+                FieldKind::Text,
+            )
+            .add(format!("{prefix}.time"), "Time Range", FieldKind::Branch)
+            .add(
+                format!("{prefix}.time.start"),
+                "Start",
+                FieldKind::AbsoluteTime,
+            )
+            .add(
+                format!("{prefix}.time.start_offset"),
+                "Start Relative to Now",
+                FieldKind::RelativeTime,
+            )
+            .add(
+                format!("{prefix}.time.start_inclusive"),
+                "Start Inclusive",
+                FieldKind::Bool,
+            )
+            .add(format!("{prefix}.time.end"), "End", FieldKind::AbsoluteTime)
+            .add(
+                format!("{prefix}.time.end_offset"),
+                "End Relative to Now",
+                FieldKind::RelativeTime,
+            )
+            .add(
+                format!("{prefix}.time.end_inclusive"),
+                "End Inclusive",
+                FieldKind::Bool,
+            )
+            .add(format!("{prefix}.anyke"), "Any Key", FieldKind::Bool)
+            .add(
+                format!("{prefix}.consolidation"),
+                "Consolidation",
+                FieldKind::Enum(CONSOLIDATION_NAMES),
+            )
+            .add(
+                format!("{prefix}.target"),
+                "Target",
+                FieldKind::Enum(TARGET_NAMES),
+            )
+            .add(format!("{prefix}.budget"), "Budget", FieldKind::Number)
+            .add(
+                format!("{prefix}.timeout"),
+                "Timeout",
+                FieldKind::RelativeTime,
+            )
+    }
+
+    fn generate_subtree_names(prefix: &str) -> Vec<String> {
+        vec![
+            prefix.to_string(),
+            format!("{prefix}.parameter"),
+            format!("{prefix}.time"),
+        ]
+    }
+}
+
+/// A bound of a time range, in nanoseconds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TimeExpr {
+    /// Time since the Unix epoch.
+    Fixed(i128),
+    /// Offset from the time of the query.
+    Now(i128),
+}
+
+impl TimeExpr {
+    fn parse(s: &str) -> Result<Self> {
+        match s.strip_prefix("now(").and_then(|s| s.strip_suffix(')')) {
+            Some("") => Ok(Self::Now(0)),
+            Some(offset) => match offset.strip_prefix('-') {
+                Some(offset) => Ok(Self::Now(-parse_duration(offset)?)),
+                None => Ok(Self::Now(parse_duration(offset)?)),
+            },
+            None => parse_rfc3339(s).map(Self::Fixed),
+        }
+    }
+
+    fn checked_add(self, nanos: i128) -> Option<Self> {
+        match self {
+            Self::Fixed(time) => time.checked_add(nanos).map(Self::Fixed),
+            Self::Now(offset) => offset.checked_add(nanos).map(Self::Now),
+        }
+    }
+}
+
+/// A bound of a time range and whether it is inclusive, `None` if unbounded.
+type TimeBound = Option<(TimeExpr, bool)>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct TimeRange {
+    start: TimeBound,
+    end: TimeBound,
+}
+
+impl TimeRange {
+    fn parse(s: &str) -> Result<Self> {
+        let invalid = || anyhow!("invalid time range {s:?}");
+        let start_inclusive = match s.chars().next().ok_or_else(invalid)? {
+            '[' => true,
+            ']' => false,
+            _ => bail!("time range {s:?} must start with '[' or ']'"),
+        };
+        let end_inclusive = match s.chars().last().ok_or_else(invalid)? {
+            ']' => true,
+            '[' => false,
+            _ => bail!("time range {s:?} must end with '[' or ']'"),
+        };
+        let inner = s.get(1..s.len() - 1).ok_or_else(invalid)?;
+        let bound = |s: &str, inclusive: bool| -> Result<TimeBound> {
+            match s.trim() {
+                "" => Ok(None),
+                s => Ok(Some((TimeExpr::parse(s)?, inclusive))),
+            }
+        };
+        if let Some((start, end)) = inner.split_once("..") {
+            Ok(Self {
+                start: bound(start, start_inclusive)?,
+                end: bound(end, end_inclusive)?,
+            })
+        } else if let Some((start, duration)) = inner.split_once(';') {
+            let Some((start, _)) = bound(start, start_inclusive)? else {
+                bail!("time range {s:?} must have a start before ';'");
+            };
+            let end = start
+                .checked_add(parse_duration(duration.trim())?)
+                .ok_or_else(invalid)?;
+            Ok(Self {
+                start: Some((start, start_inclusive)),
+                end: Some((end, end_inclusive)),
+            })
+        } else {
+            bail!("time range {s:?} must contain '..' or ';'")
+        }
+    }
+}
+
+/// Parse a duration such as `1.5h` or `100ms` into nanoseconds, which must fit in 64 bits.
+fn parse_duration(s: &str) -> Result<i128> {
+    // Units ending with `s` come before the seconds.
+    let units: &[(&str, f64)] = &[
+        ("ms", 1e-3),
+        ("us", 1e-6),
+        ("µs", 1e-6),
+        ("u", 1e-6),
+        ("s", 1.0),
+        ("m", 60.0),
+        ("h", 3600.0),
+        ("d", 86400.0),
+        ("w", 604800.0),
+    ];
+    let (value, scale) = units
+        .iter()
+        .find_map(|(unit, scale)| Some((s.strip_suffix(unit)?, *scale)))
+        .unwrap_or((s, 1.0));
+    let invalid = || anyhow!("invalid duration {s:?}");
+    let nanos = value.parse::<f64>().map_err(|_| invalid())? * scale * NANOS_PER_SEC as f64;
+    // Also rejects NaN and infinities.
+    if !(0.0..=i64::MAX as f64).contains(&nanos) {
+        return Err(invalid());
+    }
+    Ok(nanos as i128)
+}
+
+/// Parse an RFC 3339 time such as `2024-01-01T12:00:00.5Z` into nanoseconds since the Unix
+/// epoch. As in zenoh, the `T` may be a space and the `Z` may be omitted.
+fn parse_rfc3339(s: &str) -> Result<i128> {
+    let invalid = || anyhow!("invalid time {s:?}");
+    let s = s.strip_suffix('Z').unwrap_or(s);
+    let (date, time) = s.split_once(['T', ' ']).ok_or_else(invalid)?;
+    // Only digits, without the sign `parse` accepts.
+    let number = |s: &str| match s.bytes().all(|b| b.is_ascii_digit()) {
+        true => s.parse::<i64>().map_err(|_| invalid()),
+        false => Err(invalid()),
+    };
+
+    let mut date = date.splitn(3, '-');
+    let mut next = || date.next().ok_or_else(invalid);
+    let (year, month, day) = (number(next()?)?, number(next()?)?, number(next()?)?);
+    let (time, fraction) = time.split_once('.').unwrap_or((time, ""));
+    let mut time = time.splitn(3, ':');
+    let mut next = || time.next().ok_or_else(invalid);
+    let (hour, minute, second) = (number(next()?)?, number(next()?)?, number(next()?)?);
+    if !(0..=9999).contains(&year)
+        || !(1..=12).contains(&month)
+        || !(1..=31).contains(&day)
+        || hour > 23
+        || minute > 59
+        || second > 60
+    {
+        return Err(invalid());
+    }
+    let nanos = match fraction {
+        "" => 0,
+        fraction if fraction.len() <= 9 => {
+            number(fraction)? as i128 * 10i128.pow(9 - fraction.len() as u32)
+        }
+        _ => return Err(invalid()),
+    };
+
+    // Days since the epoch of a date of the proleptic Gregorian calendar.
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    let days = era * 146097 + day_of_era - 719468;
+
+    let secs = days * 86400 + hour * 3600 + minute * 60 + second;
+    Ok(secs as i128 * NANOS_PER_SEC + nanos)
+}
+
+/// Add the selector subtree of every request carrying a query in a transport message.
+pub(crate) fn add_to_tree(msg: &TransportMessage, args: &TreeArgs) -> Result<()> {
+    for msg in &network_messages(args.pinfo, msg) {
+        let NetworkBody::Request(request) = &msg.body else {
+            continue;
+        };
+        let RequestBody::Query(query) = &request.payload;
+        add_selector(request, &query.parameters, query.consolidation, args)?;
+    }
+    Ok(())
+}
+
+fn add_selector(
+    request: &Request,
+    parameters: &str,
+    consolidation: ConsolidationMode,
+    args: &TreeArgs,
+) -> Result<()> {
+    let key_expr = key_expr::resolve(args.pinfo, &request.wire_expr);
+    let label = match (&key_expr, parameters) {
+        (Some(key_expr), "") => format!("Selector: {key_expr}"),
+        (Some(key_expr), parameters) => format!("Selector: {key_expr}?{parameters}"),
+        (None, parameters) => format!("Selector: ?{parameters}"),
+    };
+    let args = args.make_subtree(FIELD_SELECTOR, &label)?;
+    if let Some(key_expr) = &key_expr {
+        set_generated(args.add_text(FIELD_KEY_EXPR, key_expr)?);
+    }
+
+    let mut anyke = false;
+    for parameter in parameters.split(LIST_SEPARATOR).filter(|p| !p.is_empty()) {
+        let (name, value) = parameter
+            .split_once(FIELD_SEPARATOR)
+            .unwrap_or((parameter, ""));
+        let param_args = args.make_subtree(FIELD_PARAMETER, parameter)?;
+        param_args.add_text(FIELD_PARAMETER_NAME, name)?;
+        param_args.add_text(FIELD_PARAMETER_VALUE, value)?;
+        match name {
+            TIME_RANGE_KEY => match TimeRange::parse(value) {
+                Ok(time_range) => add_time_range(&time_range, value, &param_args)?,
+                Err(err) => {
+                    expert::add(
+                        args.pinfo,
+                        param_args.tree,
+                        expert::EI_INVALID_TIME_RANGE,
+                        &err.to_string(),
+                    )?;
+                }
+            },
+            ANYKE_KEY => anyke = true,
+            _ => {}
+        }
+    }
+    args.add_bool(FIELD_ANYKE, anyke)?;
+
+    let consolidation = match consolidation {
+        ConsolidationMode::Auto => 0,
+        ConsolidationMode::None => 1,
+        ConsolidationMode::Monotonic => 2,
+        ConsolidationMode::Latest => 3,
+    };
+    args.add_enum(FIELD_CONSOLIDATION, consolidation)?;
+    let target = match request.ext_target {
+        QueryTarget::BestMatching => 0,
+        QueryTarget::All => 1,
+        QueryTarget::AllComplete => 2,
+    };
+    args.add_enum(FIELD_TARGET, target)?;
+    if let Some(budget) = request.ext_budget {
+        args.add_number(FIELD_BUDGET, budget.get() as u64)?;
+    }
+    if let Some(timeout) = request.ext_timeout {
+        args.add_time(FIELD_TIMEOUT, &to_nstime(timeout.as_nanos() as i128))?;
+    }
+    Ok(())
+}
+
+/// Add the bounds of a time range, resolving the offsets from now against the capture time.
+fn add_time_range(time_range: &TimeRange, value: &str, args: &TreeArgs) -> Result<()> {
+    let args = args.make_subtree(FIELD_TIME, &format!("Time Range: {value}"))?;
+    let now = unsafe { capture_time_nanos(args.pinfo) };
+    let bounds = [
+        (
+            time_range.start,
+            FIELD_TIME_START,
+            FIELD_TIME_START_OFFSET,
+            FIELD_TIME_START_INCLUSIVE,
+        ),
+        (
+            time_range.end,
+            FIELD_TIME_END,
+            FIELD_TIME_END_OFFSET,
+            FIELD_TIME_END_INCLUSIVE,
+        ),
+    ];
+    for (bound, time_field, offset_field, inclusive_field) in bounds {
+        let Some((time, inclusive)) = bound else {
+            continue;
+        };
+        match time {
+            TimeExpr::Fixed(time) => {
+                args.add_time(time_field, &to_nstime(time))?;
+            }
+            TimeExpr::Now(offset) => {
+                args.add_time(offset_field, &to_nstime(offset))?;
+                if let Some(time) = now.checked_add(offset) {
+                    set_generated(args.add_time(time_field, &to_nstime(time))?);
+                }
+            }
+        }
+        args.add_bool(inclusive_field, inclusive)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NANOS_PER_MS: i128 = 1_000_000;
+
+    #[test]
+    fn parse_durations() {
+        assert_eq!(parse_duration("100ms").unwrap(), 100 * NANOS_PER_MS);
+        assert_eq!(parse_duration("1.5h").unwrap(), 5400 * NANOS_PER_SEC);
+        assert_eq!(parse_duration("250us").unwrap(), 250_000);
+        assert_eq!(parse_duration("250µs").unwrap(), 250_000);
+        assert_eq!(parse_duration("2").unwrap(), 2 * NANOS_PER_SEC);
+        assert_eq!(parse_duration("1w").unwrap(), 604800 * NANOS_PER_SEC);
+    }
+
+    #[test]
+    fn parse_invalid_durations() {
+        for duration in ["", "ms", "-1s", "1x", "NaN", "infs", "1e300d"] {
+            assert!(parse_duration(duration).is_err(), "{duration}");
+        }
+    }
+
+    #[test]
+    fn parse_times() {
+        assert_eq!(parse_rfc3339("1970-01-01T00:00:00Z").unwrap(), 0);
+        assert_eq!(
+            parse_rfc3339("2024-01-01T12:00:00.5Z").unwrap(),
+            1704110400 * NANOS_PER_SEC + 500 * NANOS_PER_MS
+        );
+        assert_eq!(
+            parse_rfc3339("2000-02-29 00:00:01").unwrap(),
+            951782401 * NANOS_PER_SEC
+        );
+    }
+
+    #[test]
+    fn parse_invalid_times() {
+        for time in [
+            "",
+            "2024-01-01",
+            "2024-13-01T00:00:00Z",
+            "2024-01-01T24:00:00Z",
+            "2024-01-01T-1:00:00Z",
+            "2024-01-01T00:00:00.-5Z",
+            "2024-01-01T00:00:00.1234567890Z",
+            "10000-01-01T00:00:00Z",
+            "2024-01-01T00:00Z",
+        ] {
+            assert!(parse_rfc3339(time).is_err(), "{time}");
+        }
+    }
+
+    #[test]
+    fn parse_time_ranges() {
+        assert_eq!(
+            TimeRange::parse("[now(-1h)..now()]").unwrap(),
+            TimeRange {
+                start: Some((TimeExpr::Now(-3600 * NANOS_PER_SEC), true)),
+                end: Some((TimeExpr::Now(0), true)),
+            }
+        );
+        assert_eq!(
+            TimeRange::parse("]1970-01-01T00:00:00Z;10s[").unwrap(),
+            TimeRange {
+                start: Some((TimeExpr::Fixed(0), false)),
+                end: Some((TimeExpr::Fixed(10 * NANOS_PER_SEC), false)),
+            }
+        );
+        assert_eq!(
+            TimeRange::parse("[..]").unwrap(),
+            TimeRange {
+                start: None,
+                end: None,
+            }
+        );
+    }
+
+    #[test]
+    fn parse_invalid_time_ranges() {
+        for time_range in [
+            "",
+            "[",
+            "now()..now()",
+            "[now()]",
+            "[;1s]",
+            "[now(1x)..]",
+            "[now()..tomorrow]",
+        ] {
+            assert!(TimeRange::parse(time_range).is_err(), "{time_range}");
+        }
+    }
+}
@@ -0,0 +1,629 @@
+//! Decoding of the zenoh-ext serialization format, as produced by `zenoh_ext::z_serialize`.
+//!
+//! The format carries no type information: numbers are little-endian, the lengths of strings,
+//! sequences and maps are LEB128-encoded, and tuples and arrays are simply concatenated. Data
+//! is decoded against the Rust type it was serialized from, e.g. `HashMap<String, Vec<f64>>`,
+//! given as the argument of the `zenoh-ext` dissector, or else against a few common types.
+
+use std::{
+    ffi::{c_char, c_int, c_void, CStr},
+    str::FromStr,
+    sync::LazyLock,
+};
+
+use anyhow::{bail, Result};
+use zenoh_buffers::{buffer::SplitBuffer, ZBuf};
+use zenoh_protocol::{
+    network::{NetworkBody, NetworkMessage},
+    transport::TransportMessage,
+    zenoh::{PushBody, RequestBody, ResponseBody},
+};
+
+use crate::{
+    cdr::Value,
+    display::bytes_preview,
+    header_field::{FieldKind, HeaderFieldMap, Registration},
+    tree::TreeArgs,
+    utils::network_messages,
+    ws_log, PROTOCOL_DATA,
+};
+
+pub const FIELD_SERIALIZED: &str = "zenoh.serialized";
+pub const FIELD_SERIALIZED_VALUE: &str = "zenoh.serialized.value";
+
+/// Name of the dissector, whose optional argument is the serialized type.
+pub const DISSECTOR_NAME: &CStr = c"zenoh-ext";
+
+/// Nesting limit of parsed types, guarding against deeply nested arguments.
+const MAX_DEPTH: usize = 32;
+
+/// Types tried in order when data is decoded without a given type.
+const GUESSES: &[&str] = &[
+    "HashMap<String, String>",
+    "HashMap<String, ZBytes>",
+    "Vec<String>",
+    "String",
+];
+
+static GUESSED_TYPES: LazyLock<Vec<(&str, Type)>> = LazyLock::new(|| {
+    GUESSES
+        .iter()
+        .map(|name| (*name, name.parse().expect("invalid guessed type")))
+        .collect()
+});
+
+/// The header fields of decoded data.
+pub struct Serialized;
+
+impl Registration for Serialized {
+    fn generate_hf_map(prefix: &str) -> HeaderFieldMap {
+        HeaderFieldMap::new()
+            .add(prefix.to_string(), "zenoh-ext", FieldKind::Branch)
+            .add(format!("{prefix}.value"), "Value", FieldKind::Text)
+    }
+
+    fn generate_subtree_names(prefix: &str) -> Vec<String> {
+        vec![prefix.to_string()]
+    }
+}
+
+/// A serializable type.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Type {
+    Bool,
+    /// Unsigned integer of the given size in bytes.
+    Unsigned(usize),
+    /// Signed integer of the given size in bytes.
+    Signed(usize),
+    Float32,
+    Float64,
+    String,
+    Bytes,
+    Array(Box<Type>, usize),
+    Sequence(Box<Type>),
+    Map(Box<Type>, Box<Type>),
+    Tuple(Vec<Type>),
+}
+
+impl Type {
+    /// Minimum size of the serialized data, e.g. a single byte for an empty sequence.
+    fn min_size(&self) -> usize {
+        match self {
+            Type::Bool => 1,
+            Type::Unsigned(size) | Type::Signed(size) => *size,
+            Type::Float32 => 4,
+            Type::Float64 => 8,
+            Type::String | Type::Bytes | Type::Sequence(_) | Type::Map(..) => 1,
+            Type::Array(item, len) => item.min_size().saturating_mul(*len),
+            Type::Tuple(items) => items
+                .iter()
+                .fold(0, |size, item| size.saturating_add(item.min_size())),
+        }
+    }
+}
+
+impl FromStr for Type {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let mut parser = TypeParser {
+            tokens: tokenize(s),
+            pos: 0,
+        };
+        let ty = parser.parse(0)?;
+        if let Some(token) = parser.tokens.get(parser.pos) {
+            bail!("unexpected `{token}`");
+        }
+        Ok(ty)
+    }
+}
+
+/// Split a type into identifiers, possibly qualified by a path, and punctuation.
+fn tokenize(s: &str) -> Vec<&str> {
+    let mut tokens = Vec::new();
+    let mut start = None;
+    for (index, c) in s.char_indices() {
+        if c.is_alphanumeric() || c == '_' || c == ':' {
+            start.get_or_insert(index);
+            continue;
+        }
+        if let Some(start) = start.take() {
+            tokens.push(&s[start..index]);
+        }
+        if !c.is_whitespace() {
+            tokens.push(&s[index..index + c.len_utf8()]);
+        }
+    }
+    if let Some(start) = start {
+        tokens.push(&s[start..]);
+    }
+    tokens
+}
+
+struct TypeParser<'a> {
+    tokens: Vec<&'a str>,
+    pos: usize,
+}
+
+impl TypeParser<'_> {
+    fn next(&mut self) -> Result<&str> {
+        let Some(token) = self.tokens.get(self.pos) else {
+            bail!("unexpected end of type");
+        };
+        self.pos += 1;
+        Ok(token)
+    }
+
+    fn expect(&mut self, expected: &str) -> Result<()> {
+        match self.next()? {
+            token if token == expected => Ok(()),
+            token => bail!("expected `{expected}`, found `{token}`"),
+        }
+    }
+
+    fn parse(&mut self, depth: usize) -> Result<Type> {
+        if depth > MAX_DEPTH {
+            bail!("type nesting exceeds {MAX_DEPTH}");
+        }
+        let depth = depth + 1;
+        Ok(match self.next()? {
+            "&" => self.parse(depth)?,
+            "(" => {
+                let mut items = Vec::new();
+                while self.tokens.get(self.pos) != Some(&")") {
+                    items.push(self.parse(depth)?);
+                    if self.tokens.get(self.pos) == Some(&",") {
+                        self.pos += 1;
+                    } else {
+                        break;
+                    }
+                }
+                self.expect(")")?;
+                Type::Tuple(items)
+            }
+            "[" => {
+                let item = self.parse(depth)?;
+                match self.next()? {
+                    "]" => Type::Sequence(item.into()),
+                    ";" => {
+                        let len = self.next()?.parse()?;
+                        self.expect("]")?;
+                        if item.min_size() == 0 {
+                            bail!("zero-size array items");
+                        }
+                        Type::Array(item.into(), len)
+                    }
+                    token => bail!("expected `]` or `;`, found `{token}`"),
+                }
+            }
+            path => match path.rsplit("::").next().unwrap_or(path) {
+                "bool" => Type::Bool,
+                "u8" => Type::Unsigned(1),
+                "u16" => Type::Unsigned(2),
+                "u32" => Type::Unsigned(4),
+                "u64" => Type::Unsigned(8),
+                "u128" => Type::Unsigned(16),
+                "i8" => Type::Signed(1),
+                "i16" => Type::Signed(2),
+                "i32" => Type::Signed(4),
+                "i64" => Type::Signed(8),
+                "i128" => Type::Signed(16),
+                "f32" => Type::Float32,
+                "f64" => Type::Float64,
+                "String" | "str" => Type::String,
+                "ZBytes" => Type::Bytes,
+                "Vec" | "VecDeque" | "HashSet" | "BTreeSet" => {
+                    self.expect("<")?;
+                    let item = self.parse(depth)?;
+                    self.expect(">")?;
+                    Type::Sequence(item.into())
+                }
+                "HashMap" | "BTreeMap" => {
+                    self.expect("<")?;
+                    let key = self.parse(depth)?;
+                    self.expect(",")?;
+                    let value = self.parse(depth)?;
+                    self.expect(">")?;
+                    Type::Map(key.into(), value.into())
+                }
+                name => bail!("unsupported type `{name}`"),
+            },
+        })
+    }
+}
+
+/// A reader of serialized data, also used to decode data of a known layout, e.g. the
+/// attachments of rmw_zenoh.
+pub(crate) struct Reader<'a> {
+    pub bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    pub fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes }
+    }
+
+    pub fn take(&mut self, len: usize) -> Result<&'a [u8]> {
+        let Some((head, tail)) = self.bytes.split_at_checked(len) else {
+            bail!("{len} bytes exceed the remaining {}", self.bytes.len());
+        };
+        self.bytes = tail;
+        Ok(head)
+    }
+
+    /// Read a LEB128-encoded length.
+    pub fn read_len(&mut self) -> Result<usize> {
+        let mut len = 0u64;
+        for shift in (0..64).step_by(7) {
+            let byte = self.take(1)?[0];
+            len |= ((byte & 0x7f) as u64) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(len.try_into()?);
+            }
+        }
+        bail!("length overflows 64 bits")
+    }
+
+    /// Read a length that must fit in the remaining data, where each item takes a byte at least.
+    fn read_count(&mut self) -> Result<usize> {
+        let len = self.read_len()?;
+        if len > self.bytes.len() {
+            bail!(
+                "{len} items exceed the remaining {} bytes",
+                self.bytes.len()
+            );
+        }
+        Ok(len)
+    }
+
+    /// Read a little-endian integer of `size` bytes, widened to 128 bits.
+    pub fn read_int(&mut self, size: usize) -> Result<u128> {
+        let mut buf = [0; 16];
+        buf[..size].copy_from_slice(self.take(size)?);
+        Ok(u128::from_le_bytes(buf))
+    }
+
+    /// Read a UTF-8 string prefixed by its length.
+    pub fn read_str(&mut self) -> Result<&'a str> {
+        let len = self.read_len()?;
+        Ok(std::str::from_utf8(self.take(len)?)?)
+    }
+
+    fn read(&mut self, ty: &Type) -> Result<Value> {
+        let scalar = match ty {
+            Type::Bool => match self.take(1)?[0] {
+                0 => "false".to_string(),
+                1 => "true".to_string(),
+                byte => bail!("invalid boolean {byte}"),
+            },
+            Type::Unsigned(size) => self.read_int(*size)?.to_string(),
+            Type::Signed(size) => {
+                // Sign-extend from the top bit of the integer.
+                let shift = 128 - 8 * size;
+                (((self.read_int(*size)? << shift) as i128) >> shift).to_string()
+            }
+            Type::Float32 => f32::from_le_bytes(self.take(4)?.try_into()?).to_string(),
+            Type::Float64 => f64::from_le_bytes(self.take(8)?.try_into()?).to_string(),
+            Type::String => format!("{:?}", self.read_str()?),
+            // Byte sequences are shown as bytes rather than as arrays of numbers.
+            Type::Bytes => {
+                let len = self.read_len()?;
+                return Ok(Value::Bytes(self.take(len)?.to_vec()));
+            }
+            Type::Sequence(item) if **item == Type::Unsigned(1) => {
+                let len = self.read_len()?;
+                return Ok(Value::Bytes(self.take(len)?.to_vec()));
+            }
+            Type::Array(item, len) if **item == Type::Unsigned(1) => {
+                return Ok(Value::Bytes(self.take(*len)?.to_vec()));
+            }
+            Type::Array(item, len) => {
+                if ty.min_size() > self.bytes.len() {
+                    bail!(
+                        "{len} items exceed the remaining {} bytes",
+                        self.bytes.len()
+                    );
+                }
+                return Ok(Value::Array(
+                    (0..*len).map(|_| self.read(item)).collect::<Result<_>>()?,
+                ));
+            }
+            Type::Sequence(item) => {
+                let len = self.read_count()?;
+                return Ok(Value::Array(
+                    (0..len).map(|_| self.read(item)).collect::<Result<_>>()?,
+                ));
+            }
+            Type::Map(key, value) => {
+                let len = self.read_count()?;
+                let entries = (0..len)
+                    .map(|index| {
+                        let key = self.read(key)?;
+                        let value = self.read(value)?;
+                        Ok(match key {
+                            Value::Scalar(key) => (key, value),
+                            Value::Bytes(key) => (bytes_preview(&key), value),
+                            key => (
+                                format!("[{index}]"),
+                                Value::Message(vec![
+                                    ("key".to_string(), key),
+                                    ("value".to_string(), value),
+                                ]),
+                            ),
+                        })
+                    })
+                    .collect::<Result<_>>()?;
+                return Ok(Value::Message(entries));
+            }
+            Type::Tuple(items) => {
+                return Ok(Value::Message(
+                    items
+                        .iter()
+                        .enumerate()
+                        .map(|(index, item)| Ok((index.to_string(), self.read(item)?)))
+                        .collect::<Result<_>>()?,
+                ));
+            }
+        };
+        Ok(Value::Scalar(scalar))
+    }
+}
+
+/// Decode serialized data as the given type, which must span all of it.
+pub fn decode(ty: &Type, bytes: &[u8]) -> Result<Value> {
+    let mut reader = Reader::new(bytes);
+    let value = reader.read(ty)?;
+    if !reader.bytes.is_empty() {
+        bail!("{} trailing bytes", reader.bytes.len());
+    }
+    Ok(value)
+}
+
+/// Decode serialized data as the first of the common types it matches.
+pub fn guess(bytes: &[u8]) -> Option<(&'static str, Value)> {
+    GUESSED_TYPES
+        .iter()
+        .find_map(|(name, ty)| Some((*name, decode(ty, bytes).ok()?)))
+}
+
+fn add_to_tree(title: &str, type_name: &str, value: &Value, args: &TreeArgs) -> Result<()> {
+    let args = args.make_subtree(FIELD_SERIALIZED, &format!("{title}: {type_name}"))?;
+    match value {
+        Value::Message(fields) => {
+            for (name, value) in fields {
+                args.add_value(FIELD_SERIALIZED, FIELD_SERIALIZED_VALUE, name, value)?;
+            }
+        }
+        value => args.add_value(FIELD_SERIALIZED, FIELD_SERIALIZED_VALUE, "Value", value)?,
+    }
+    Ok(())
+}
+
+/// Decode serialized data as the given type, or else guess it, and returns whether it could
+/// be decoded.
+pub(crate) fn dissect(type_name: Option<&str>, bytes: &[u8], args: &TreeArgs) -> Result<bool> {
+    let (type_name, value) = match type_name {
+        Some(type_name) => match type_name.parse::<Type>().and_then(|ty| decode(&ty, bytes)) {
+            Ok(value) => (type_name, value),
+            Err(err) => {
+                args.make_subtree(
+                    FIELD_SERIALIZED,
+                    &format!("zenoh-ext: failed to decode {type_name}: {err}"),
+                )?;
+                return Ok(false);
+            }
+        },
+        None => match guess(bytes) {
+            Some(guessed) => guessed,
+            None => return Ok(false),
+        },
+    };
+    add_to_tree("zenoh-ext", type_name, &value, args)?;
+    Ok(true)
+}
+
+/// Register the `zenoh-ext` dissector, see [`DISSECTOR_NAME`].
+pub unsafe fn register(proto_id: c_int) {
+    epan_sys::register_dissector(DISSECTOR_NAME.as_ptr(), Some(dissect_serialized), proto_id);
+}
+
+unsafe extern "C" fn dissect_serialized(
+    tvb: *mut epan_sys::tvbuff,
+    pinfo: *mut epan_sys::_packet_info,
+    tree: *mut epan_sys::_proto_node,
+    data: *mut c_void,
+) -> c_int {
+    let len = epan_sys::tvb_captured_length(tvb) as usize;
+    let bytes = match len {
+        0 => &[],
+        len => std::slice::from_raw_parts(epan_sys::tvb_get_ptr(tvb, 0, len as _), len),
+    };
+    // The argument of the key expression dissectors table, if any.
+    let type_name = (!data.is_null())
+        .then(|| CStr::from_ptr(data as *const c_char).to_string_lossy())
+        .filter(|type_name| !type_name.is_empty());
+
+    PROTOCOL_DATA.with_borrow(|protocol| {
+        let args = TreeArgs {
+            tree,
+            tvb,
+            pinfo,
+            hf_map: &protocol.hf_map,
+            st_map: &protocol.st_map,
+            start: 0,
+            length: len,
+        };
+        match dissect(type_name.as_deref(), bytes, &args) {
+            Ok(true) => len as c_int,
+            Ok(false) => 0,
+            Err(err) => {
+                ws_log::debug!("zenoh-ext: {err}");
+                0
+            }
+        }
+    })
+}
+
+/// Returns the name of the zenoh message carrying an attachment and the attachment.
+fn attachment_of(msg: &NetworkMessage) -> Option<(&'static str, &ZBuf)> {
+    Some(match &msg.body {
+        NetworkBody::Push(push) => match &push.payload {
+            PushBody::Put(put) => ("Put", &put.ext_attachment.as_ref()?.buffer),
+            PushBody::Del(del) => ("Del", &del.ext_attachment.as_ref()?.buffer),
+        },
+        NetworkBody::Request(request) => match &request.payload {
+            RequestBody::Query(query) => ("Query", &query.ext_attachment.as_ref()?.buffer),
+        },
+        NetworkBody::Response(response) => match &response.payload {
+            ResponseBody::Reply(reply) => match &reply.payload {
+                PushBody::Put(put) => ("Reply", &put.ext_attachment.as_ref()?.buffer),
+                PushBody::Del(del) => ("Reply", &del.ext_attachment.as_ref()?.buffer),
+            },
+            ResponseBody::Err(_) => return None,
+        },
+        _ => return None,
+    })
+}
+
+/// Decode the attachments of every network message carried by a transport message.
+///
+/// Attachments are nearly always key-value maps, so they are decoded with the guessed types.
+pub(crate) fn dissect_attachments(msg: &TransportMessage, args: &TreeArgs) -> Result<()> {
+    let msgs = network_messages(args.pinfo, msg);
+    for (kind, attachment) in msgs.iter().filter_map(|msg| attachment_of(msg)) {
+        if let Some((type_name, value)) = guess(&attachment.contiguous()) {
+            add_to_tree(&format!("{kind} Attachment"), type_name, &value, args)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scalar(s: &str) -> Value {
+        Value::Scalar(s.to_string())
+    }
+
+    #[test]
+    fn parse_types() {
+        assert_eq!("u16".parse::<Type>().unwrap(), Type::Unsigned(2));
+        assert_eq!(
+            "std::collections::HashMap<String, Vec<i32>>"
+                .parse::<Type>()
+                .unwrap(),
+            Type::Map(
+                Type::String.into(),
+                Type::Sequence(Type::Signed(4).into()).into()
+            )
+        );
+        assert_eq!(
+            "([u8; 4], &str, bool)".parse::<Type>().unwrap(),
+            Type::Tuple(vec![
+                Type::Array(Type::Unsigned(1).into(), 4),
+                Type::String,
+                Type::Bool
+            ])
+        );
+    }
+
+    #[test]
+    fn parse_invalid_types() {
+        for ty in [
+            "",
+            "Vec<u8",
+            "HashMap<String>",
+            "[u8; x]",
+            "u8 u8",
+            "Option<u8>",
+            "[(); 18446744073709551615]",
+            "[[u8; 0]; 4]",
+        ] {
+            assert!(ty.parse::<Type>().is_err(), "{ty}");
+        }
+        let nested = format!("{}u8{}", "Vec<".repeat(64), ">".repeat(64));
+        assert!(nested.parse::<Type>().is_err());
+        let nested = format!("{}u8{}", "Vec<".repeat(16), ">".repeat(16));
+        assert!(nested.parse::<Type>().is_ok());
+    }
+
+    #[test]
+    fn decode_oversized_array() {
+        let ty = "[[u16; 1000000]; 1000000]".parse::<Type>().unwrap();
+        assert!(decode(&ty, &[0; 8]).is_err());
+        let ty = "[(u8, bool); 2]".parse::<Type>().unwrap();
+        assert!(decode(&ty, &[7, 1, 8]).is_err());
+        assert!(decode(&ty, &[7, 1, 8, 0]).is_ok());
+    }
+
+    #[test]
+    fn decode_scalars() {
+        assert_eq!(decode(&Type::Bool, &[1]).unwrap(), scalar("true"));
+        assert_eq!(
+            decode(&Type::Unsigned(2), &[0x34, 0x12]).unwrap(),
+            scalar("4660")
+        );
+        assert_eq!(
+            decode(&Type::Signed(2), &[0xfe, 0xff]).unwrap(),
+            scalar("-2")
+        );
+        assert_eq!(
+            decode(&Type::Float64, &1.5f64.to_le_bytes()).unwrap(),
+            scalar("1.5")
+        );
+        assert_eq!(
+            decode(&Type::String, b"\x05hello").unwrap(),
+            scalar("\"hello\"")
+        );
+    }
+
+    #[test]
+    fn decode_collections() {
+        let ty = "HashMap<String, u8>".parse().unwrap();
+        assert_eq!(
+            decode(&ty, b"\x02\x01a\x01\x01b\x02").unwrap(),
+            Value::Message(vec![
+                ("\"a\"".to_string(), scalar("1")),
+                ("\"b\"".to_string(), scalar("2")),
+            ])
+        );
+        let ty = "Vec<u16>".parse().unwrap();
+        assert_eq!(
+            decode(&ty, &[2, 1, 0, 2, 0]).unwrap(),
+            Value::Array(vec![scalar("1"), scalar("2")])
+        );
+        let ty = "Vec<u8>".parse().unwrap();
+        assert_eq!(
+            decode(&ty, &[3, 1, 2, 3]).unwrap(),
+            Value::Bytes(vec![1, 2, 3])
+        );
+    }
+
+    #[test]
+    fn decode_malformed() {
+        // Invalid boolean, truncated integer and string, invalid UTF-8 and trailing bytes
+        assert!(decode(&Type::Bool, &[2]).is_err());
+        assert!(decode(&Type::Unsigned(4), &[1, 2]).is_err());
+        assert!(decode(&Type::String, b"\x05hel").is_err());
+        assert!(decode(&Type::String, b"\x01\xff").is_err());
+        assert!(decode(&Type::Unsigned(1), &[1, 2]).is_err());
+        // Sequence lengths exceeding the data or 64 bits
+        let ty = "Vec<u32>".parse().unwrap();
+        assert!(decode(&ty, &[0xff, 0xff, 0xff, 0x0f]).is_err());
+        assert!(decode(&Type::Bytes, &[0xff; 11]).is_err());
+    }
+
+    #[test]
+    fn guess_types() {
+        assert_eq!(
+            guess(b"\x01\x01a\x01b"),
+            Some((
+                "HashMap<String, String>",
+                Value::Message(vec![("\"a\"".to_string(), scalar("\"b\""))])
+            ))
+        );
+        assert_eq!(guess(b"\x02hi").map(|(name, _)| name), Some("String"));
+        assert_eq!(guess(&[0xff]), None);
+    }
+}
@@ -0,0 +1,410 @@
+//! Session lifecycle: the reason of each `Close` and the links between the frames opening and
+//! closing a session.
+//!
+//! Sessions are recorded per conversation during the first pass. A session opens with the
+//! `OpenAck` and ends with a `Close`, or with a TCP FIN or RST when the link is torn down
+//! without one. See the `close::reason` constants of `zenoh_protocol::transport`.
+//!
+//! Sessions are numbered in capture order (`zenoh.session`). The links of a multilink session,
+//! whose `OpenAck` carries the `ext_mlink` extension, share the index of the session between
+//! the same pair of ZIDs, and so do the declarations, requests and sequence numbers tracked per
+//! [`Side`].
+//!
+//! A session uses the lowlatency transport when the `InitAck` accepts the `ext_lowlatency`
+//! extension offered by the `InitSyn`, which is how the PDUs following its `OpenAck` are
+//! decoded, starting with the next one of the same TCP segment.
+
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    ffi::{c_void, CStr, CString},
+};
+
+use anyhow::Result;
+use zenoh_protocol::transport::{TransportBody, TransportMessage};
+
+use crate::{
+    conversation, expert,
+    header_field::{FieldKind, HeaderFieldMap, Registration},
+    timestamp::{capture_time_nanos, to_nstime},
+    tree::{set_generated, TreeArgs},
+    ws_log, PROTOCOL_DATA, PROTO_DATA_KEY_LOWLATENCY,
+};
+
+pub const FIELD_SESSION: &str = "zenoh.session";
+pub const FIELD_CLOSE_REASON: &str = "zenoh.session.close_reason";
+pub const FIELD_OPENED_IN: &str = "zenoh.session.opened_in";
+pub const FIELD_CLOSED_IN: &str = "zenoh.session.closed_in";
+pub const FIELD_TORN_DOWN_IN: &str = "zenoh.session.torn_down_in";
+pub const FIELD_DURATION: &str = "zenoh.session.duration";
+pub const FIELD_LOWLATENCY: &str = "zenoh.session.lowlatency";
+
+pub const CLOSE_REASON_NAMES: &[(u32, &str)] = &[
+    (0x00, "Generic"),
+    (0x01, "Unsupported"),
+    (0x02, "Invalid"),
+    (0x03, "MaxSessions"),
+    (0x04, "MaxLinks"),
+    (0x05, "Expired"),
+    (0x06, "Unresponsive"),
+    (0x07, "ConnectionToSelf"),
+];
+
+/// The session header fields, added below `OpenAck` and `Close` messages.
+pub struct Session;
+
+impl Registration for Session {
+    fn generate_hf_map(prefix: &str) -> HeaderFieldMap {
+        HeaderFieldMap::new()
+            .add(prefix.to_string(), "Session", FieldKind::Number)
+            .add(
+                format!("{prefix}.close_reason"),
+                "Close Reason",
+                FieldKind::Enum(CLOSE_REASON_NAMES),
+            )
+            .add(
+                format!("{prefix}.opened_in"),
+                "Opened in",
+                FieldKind::FrameNum,
+            )
+            .add(
+                format!("{prefix}.closed_in"),
+                "Closed in",
+                FieldKind::FrameNum,
+            )
+            .add(
+                format!("{prefix}.torn_down_in"),
+                "Torn down in",
+                FieldKind::FrameNum,
+            )
+            .add(
+                format!("{prefix}.duration"),
+                "Duration",
+                FieldKind::RelativeTime,
+            )
+            .add(
+                format!("{prefix}.lowlatency"),
+                "Low Latency",
+                FieldKind::Bool,
+            )
+    }
+
+    fn generate_subtree_names(prefix: &str) -> Vec<String> {
+        vec![prefix.to_string()]
+    }
+}
+
+/// A session of a conversation, with frame numbers and capture times in nanoseconds.
+#[derive(Debug, Clone, Copy)]
+struct SessionState {
+    /// Index of the session, shared by the links of a multilink session.
+    index: u32,
+    /// Frame and time of the `OpenAck`.
+    opened: (u32, i128),
+    /// Frame, time and reason of the `Close`.
+    closed: Option<(u32, i128, u8)>,
+    /// First TCP FIN or RST of the conversation while the session was not closed.
+    torn_down: Option<u32>,
+    /// Whether the session uses the lowlatency transport.
+    lowlatency: bool,
+}
+
+thread_local! {
+    static SESSIONS: RefCell<HashMap<u32, Vec<SessionState>>> = RefCell::default();
+    /// Whether the `InitAck` of the pending handshake of each conversation accepted the
+    /// lowlatency transport.
+    static LOWLATENCY: RefCell<HashMap<u32, bool>> = RefCell::default();
+    /// Index of the last multilink session between each pair of ZIDs, in ascending order.
+    static MULTILINK: RefCell<HashMap<(String, String), u32>> = RefCell::default();
+}
+
+/// One side of a session, which scopes the ids it declares and the sequence numbers it sends.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub(crate) enum Side {
+    /// A node by session index and ZID, on any link of the session.
+    Session(u32, String),
+    /// A side of a conversation outside of any known session, by conversation index and port.
+    Link(u32, u32),
+}
+
+/// Clear the recorded sessions.
+pub(crate) fn init_sessions() {
+    SESSIONS.with_borrow_mut(|sessions| sessions.clear());
+    LOWLATENCY.with_borrow_mut(|lowlatency| lowlatency.clear());
+    MULTILINK.with_borrow_mut(|multilink| multilink.clear());
+}
+
+pub fn close_reason_name(reason: u8) -> &'static str {
+    CLOSE_REASON_NAMES
+        .iter()
+        .find(|(value, _)| *value == reason as u32)
+        .map_or("Unknown", |(_, name)| name)
+}
+
+pub(crate) unsafe fn conversation_index(pinfo: *mut epan_sys::_packet_info) -> Option<u32> {
+    let conv = epan_sys::find_conversation_pinfo(pinfo, 0);
+    (!conv.is_null()).then(|| (*conv).conv_index)
+}
+
+fn find_session(conv_index: u32, pred: impl Fn(&SessionState) -> bool) -> Option<SessionState> {
+    SESSIONS.with_borrow(|sessions| sessions.get(&conv_index)?.iter().copied().find(pred))
+}
+
+/// Returns the index of the session a frame belongs to, from its `OpenAck` to its end.
+pub(crate) unsafe fn session_index(pinfo: *mut epan_sys::_packet_info) -> Option<u32> {
+    let conv_index = conversation_index(pinfo)?;
+    let frame = (*pinfo).num;
+    SESSIONS.with_borrow(|sessions| {
+        let session = sessions
+            .get(&conv_index)?
+            .iter()
+            .rev()
+            .find(|s| s.opened.0 <= frame)?;
+        let ended = session.closed.map(|(f, _, _)| f).or(session.torn_down);
+        ended.is_none_or(|f| frame <= f).then_some(session.index)
+    })
+}
+
+/// Returns whether the PDU being dissected belongs to a session using the lowlatency transport,
+/// i.e. follows the `OpenAck` of such a session, in a later frame or in the one of the `OpenAck`.
+pub(crate) unsafe fn is_lowlatency(pinfo: *mut epan_sys::_packet_info) -> bool {
+    let proto_id = PROTOCOL_DATA.with_borrow(|d| d.id);
+    if !epan_sys::p_get_proto_data((*pinfo).pool, pinfo, proto_id, PROTO_DATA_KEY_LOWLATENCY)
+        .is_null()
+    {
+        return true;
+    }
+    let Some(conv_index) = conversation_index(pinfo) else {
+        return false;
+    };
+    let frame = (*pinfo).num;
+    SESSIONS.with_borrow(|sessions| {
+        sessions
+            .get(&conv_index)
+            .and_then(|s| s.iter().rev().find(|s| s.opened.0 < frame))
+            .is_some_and(|s| s.lowlatency && s.closed.is_none_or(|(f, _, _)| f >= frame))
+    })
+}
+
+/// Switch the PDUs following the `OpenAck` of a session using the lowlatency transport to it,
+/// including the ones in the rest of the frame of the `OpenAck`.
+pub(crate) unsafe fn switch_framing(pinfo: *mut epan_sys::_packet_info, msg: &TransportMessage) {
+    if !matches!(msg.body, TransportBody::OpenAck(_)) {
+        return;
+    }
+    let Some(conv_index) = conversation_index(pinfo) else {
+        return;
+    };
+    let frame = (*pinfo).num;
+    if find_session(conv_index, |s| s.opened.0 == frame && s.lowlatency).is_some() {
+        // Any non-null pointer marks the frame, the packet scoped data being freed with it.
+        let proto_id = PROTOCOL_DATA.with_borrow(|d| d.id);
+        let key = PROTO_DATA_KEY_LOWLATENCY;
+        epan_sys::p_add_proto_data((*pinfo).pool, pinfo, proto_id, key, pinfo as *mut _);
+    }
+}
+
+/// Record the sessions opened and closed by a transport message.
+pub(crate) unsafe fn update_state(pinfo: *mut epan_sys::_packet_info, msg: &TransportMessage) {
+    let Some(conv_index) = conversation_index(pinfo) else {
+        return;
+    };
+    let now = ((*pinfo).num, capture_time_nanos(pinfo));
+
+    SESSIONS.with_borrow_mut(|sessions| match &msg.body {
+        // The acceptor only accepts the extension offered by the initiator.
+        TransportBody::InitAck(init_ack) => {
+            LOWLATENCY.with_borrow_mut(|lowlatency| {
+                lowlatency.insert(conv_index, init_ack.ext_lowlatency.is_some())
+            });
+        }
+        TransportBody::OpenAck(open_ack) => {
+            let zids = open_ack.ext_mlink.as_ref().and_then(|_| {
+                let (a, b) = (
+                    conversation::source_zid(pinfo)?,
+                    conversation::destination_zid(pinfo)?,
+                );
+                Some(if a <= b { (a, b) } else { (b, a) })
+            });
+            let index = new_session_index(sessions, zids);
+            sessions.entry(conv_index).or_default().push(SessionState {
+                index,
+                opened: now,
+                closed: None,
+                torn_down: None,
+                lowlatency: LOWLATENCY
+                    .with_borrow_mut(|lowlatency| lowlatency.remove(&conv_index))
+                    .unwrap_or(false),
+            });
+        }
+        TransportBody::Close(close) => {
+            if let Some(session) = sessions.get_mut(&conv_index).and_then(|s| s.last_mut()) {
+                session.closed.get_or_insert((now.0, now.1, close.reason));
+            }
+        }
+        _ => {}
+    });
+}
+
+/// Returns the index of a new session: the one of the open multilink session between its ZIDs,
+/// if any, or the next one.
+fn new_session_index(
+    sessions: &HashMap<u32, Vec<SessionState>>,
+    multilink_zids: Option<(String, String)>,
+) -> u32 {
+    let is_open = |index: u32| {
+        sessions
+            .values()
+            .flatten()
+            .any(|s| s.index == index && s.closed.is_none() && s.torn_down.is_none())
+    };
+    let next = || {
+        sessions
+            .values()
+            .flatten()
+            .map(|s| s.index)
+            .max()
+            .unwrap_or(0)
+            + 1
+    };
+    let Some(zids) = multilink_zids else {
+        return next();
+    };
+    MULTILINK.with_borrow_mut(|multilink| {
+        let index = match multilink.get(&zids) {
+            Some(&index) if is_open(index) => index,
+            _ => next(),
+        };
+        multilink.insert(zids, index);
+        index
+    })
+}
+
+/// Add the index of the session of a frame to the protocol subtree.
+pub(crate) unsafe fn add_index(
+    tvb: *mut epan_sys::tvbuff,
+    pinfo: *mut epan_sys::_packet_info,
+    tree: *mut epan_sys::_proto_node,
+) {
+    let Some(index) = session_index(pinfo) else {
+        return;
+    };
+    set_generated(epan_sys::proto_tree_add_uint64(
+        tree,
+        PROTOCOL_DATA.with_borrow(|d| d.hf_map[FIELD_SESSION]),
+        tvb,
+        0,
+        0,
+        index as u64,
+    ));
+}
+
+/// Add the session subtree below `OpenAck` and `Close` messages.
+pub(crate) fn add_to_tree(msg: &TransportMessage, args: &TreeArgs) -> Result<()> {
+    let (conv_index, frame) = unsafe {
+        let Some(conv_index) = conversation_index(args.pinfo) else {
+            return Ok(());
+        };
+        (conv_index, (*args.pinfo).num)
+    };
+    match &msg.body {
+        TransportBody::OpenAck(_) => {
+            let Some(session) = find_session(conv_index, |s| s.opened.0 == frame) else {
+                return Ok(());
+            };
+            let (_, opened_at) = session.opened;
+            let item = args.add_number(FIELD_SESSION, session.index as u64)?;
+            set_generated(item);
+            let args = args.item_subtree(item, FIELD_SESSION)?;
+            set_generated(args.add_bool(FIELD_LOWLATENCY, session.lowlatency)?);
+            if let Some((closed_in, closed_at, reason)) = session.closed {
+                set_generated(args.add_frame(FIELD_CLOSED_IN, closed_in)?);
+                set_generated(args.add_time(FIELD_DURATION, &to_nstime(closed_at - opened_at))?);
+                set_generated(args.add_enum(FIELD_CLOSE_REASON, reason as u32)?);
+            } else if let Some(torn_down_in) = session.torn_down {
+                let item = args.add_frame(FIELD_TORN_DOWN_IN, torn_down_in)?;
+                set_generated(item);
+                expert::add(
+                    args.pinfo,
+                    item,
+                    expert::EI_SESSION_WITHOUT_CLOSE,
+                    &format!(
+                        "Session ended by a TCP FIN or RST in frame {torn_down_in} without a Close"
+                    ),
+                )?;
+            }
+        }
+        TransportBody::Close(close) => {
+            let closed_here = |s: &SessionState| s.closed.is_some_and(|(f, _, _)| f == frame);
+            // Without the `OpenAck`, there's no session index to attach the subtree to.
+            let Some(SessionState {
+                index,
+                opened: (opened_in, opened_at),
+                closed: Some((_, closed_at, _)),
+                ..
+            }) = find_session(conv_index, closed_here)
+            else {
+                args.add_enum(FIELD_CLOSE_REASON, close.reason as u32)?;
+                return Ok(());
+            };
+            let item = args.add_number(FIELD_SESSION, index as u64)?;
+            set_generated(item);
+            let text = CString::new(format!(", Close: {}", close_reason_name(close.reason)))?;
+            unsafe { epan_sys::proto_item_append_text(item, c"%s".as_ptr(), text.as_ptr()) };
+            let args = args.item_subtree(item, FIELD_SESSION)?;
+            args.add_enum(FIELD_CLOSE_REASON, close.reason as u32)?;
+            set_generated(args.add_frame(FIELD_OPENED_IN, opened_in)?);
+            set_generated(args.add_time(FIELD_DURATION, &to_nstime(closed_at - opened_at))?);
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Listen to TCP FIN and RST segments, to find the sessions torn down without a `Close`.
+///
+/// The segments are picked from the flags of the TCP tap data rather than by a filter, which
+/// would require a tree for every packet of the capture. Must be called once the TCP tap is
+/// registered, i.e. on handoff.
+pub unsafe fn register_teardown_listener() {
+    let err = epan_sys::register_tap_listener(
+        c"tcp".as_ptr(),
+        std::ptr::null_mut(),
+        std::ptr::null(),
+        epan_sys::TL_REQUIRES_NOTHING,
+        None,
+        Some(teardown_packet),
+        None,
+        None,
+    );
+    if !err.is_null() {
+        ws_log::critical!(
+            "Couldn't register the TCP teardown listener: {}",
+            CStr::from_ptr((*err).str_).to_string_lossy()
+        );
+        epan_sys::g_string_free(err, 1);
+    }
+}
+
+unsafe extern "C" fn teardown_packet(
+    _tapdata: *mut c_void,
+    pinfo: *mut epan_sys::packet_info,
+    _edt: *mut epan_sys::epan_dissect_t,
+    data: *const c_void,
+    _flags: epan_sys::tap_flags_t,
+) -> epan_sys::tap_packet_status {
+    let tcph = data as *const epan_sys::tcpheader;
+    let teardown = (epan_sys::TH_FIN | epan_sys::TH_RST) as u16;
+    if (*(*pinfo).fd).visited() == 0 && !tcph.is_null() && (*tcph).th_flags & teardown != 0 {
+        if let Some(conv_index) = conversation_index(pinfo) {
+            SESSIONS.with_borrow_mut(|sessions| {
+                if let Some(session) = sessions.get_mut(&conv_index).and_then(|s| s.last_mut()) {
+                    if session.closed.is_none() {
+                        session.torn_down.get_or_insert((*pinfo).num);
+                    }
+                }
+            });
+        }
+    }
+    epan_sys::tap_packet_status_TAP_PACKET_DONT_REDRAW
+}
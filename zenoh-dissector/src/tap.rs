@@ -0,0 +1,273 @@
+//! The `zenoh` tap, queuing every decoded network message for other plugins and scripts.
+//!
+//! Network messages of `Frame`s and the ones reassembled from `Fragment`s are queued as a
+//! [`TapInfo`], whose layout is kept stable so that C plugins can build their own statistics
+//! without parsing the display text. Fields are only ever appended, and strings are allocated
+//! in the packet scope.
+//!
+//! The tap also feeds the Zenoh conversation and endpoint tables, whose nodes are named after
+//! their alias, or else their ZID.
+
+use std::{
+    ffi::{c_char, c_int, c_void, CStr},
+    ptr,
+};
+
+use zenoh_buffers::buffer::Buffer;
+use zenoh_protocol::{
+    core::Reliability,
+    network::{NetworkBody, NetworkMessage},
+    transport::{TransportBody, TransportMessage},
+    zenoh::{PushBody, ResponseBody},
+};
+
+use crate::{
+    alias, conversation, key_expr,
+    payload::Payload,
+    utils::{network_message_qos, network_message_target, network_messages, packet_str},
+};
+
+const TAP_NAME: &CStr = c"zenoh";
+
+static mut TAP_ID: c_int = -1;
+
+static mut CONVERSATION_INFO: epan_sys::ct_dissector_info_t = epan_sys::ct_dissector_info_t {
+    get_filter_type: Some(conversation_filter_type),
+};
+static mut ENDPOINT_INFO: epan_sys::et_dissector_info_t = epan_sys::et_dissector_info_t {
+    get_filter_type: Some(endpoint_filter_type),
+};
+
+/// Kind of the network message of a [`TapInfo`].
+#[repr(u32)]
+#[derive(Debug, Clone, Copy)]
+pub enum MessageKind {
+    Put = 1,
+    Del = 2,
+    Query = 3,
+    Reply = 4,
+    ReplyDel = 5,
+    Err = 6,
+    ResponseFinal = 7,
+    Interest = 8,
+    Declare = 9,
+    Oam = 10,
+}
+
+/// Data queued on the `zenoh` tap for every network message.
+///
+/// ```c
+/// typedef struct {
+///     uint32_t kind;
+///     const char *key_expr;
+///     const char *src_zid;
+///     const char *dst_zid;
+///     bool has_request_id;
+///     uint32_t request_id;
+///     uint64_t payload_len;
+///     uint8_t priority;
+///     uint8_t congestion_control;
+///     bool express;
+///     bool reliable;
+///     bool reassembled;
+/// } zenoh_tap_info_t;
+/// ```
+#[repr(C)]
+#[derive(Debug)]
+pub struct TapInfo {
+    pub kind: MessageKind,
+    /// Resolved key expression, null if unknown.
+    pub key_expr: *const c_char,
+    /// ZIDs of the sender and the receiver, null if the handshake wasn't captured.
+    pub src_zid: *const c_char,
+    pub dst_zid: *const c_char,
+    /// Only requests and their responses carry a request id.
+    pub has_request_id: bool,
+    pub request_id: u32,
+    /// Zero for messages without payload.
+    pub payload_len: u64,
+    pub priority: u8,
+    pub congestion_control: u8,
+    pub express: bool,
+    pub reliable: bool,
+    /// Whether the message was reassembled from `Fragment` messages.
+    pub reassembled: bool,
+}
+
+/// Register the `zenoh` tap, along with the conversation and endpoint tables listening to it.
+pub unsafe fn register(proto_id: c_int) {
+    TAP_ID = epan_sys::register_tap(TAP_NAME.as_ptr());
+    // The tables listen to the tap named after the protocol, i.e. this one.
+    epan_sys::register_conversation_table(
+        proto_id,
+        true,
+        Some(conversation_packet),
+        Some(endpoint_packet),
+    );
+}
+
+fn message_kind(msg: &NetworkMessage) -> MessageKind {
+    match &msg.body {
+        NetworkBody::Push(push) => match &push.payload {
+            PushBody::Put(_) => MessageKind::Put,
+            PushBody::Del(_) => MessageKind::Del,
+        },
+        NetworkBody::Request(_) => MessageKind::Query,
+        NetworkBody::Response(response) => match &response.payload {
+            ResponseBody::Reply(reply) => match &reply.payload {
+                PushBody::Put(_) => MessageKind::Reply,
+                PushBody::Del(_) => MessageKind::ReplyDel,
+            },
+            ResponseBody::Err(_) => MessageKind::Err,
+        },
+        NetworkBody::ResponseFinal(_) => MessageKind::ResponseFinal,
+        NetworkBody::Interest(_) => MessageKind::Interest,
+        NetworkBody::Declare(_) => MessageKind::Declare,
+        NetworkBody::OAM(_) => MessageKind::Oam,
+    }
+}
+
+/// Queue the network messages of a transport message, including the one reassembled from its
+/// fragment.
+pub(crate) unsafe fn tap_messages(pinfo: *mut epan_sys::_packet_info, msg: &TransportMessage) {
+    if !epan_sys::have_tap_listener(TAP_ID) {
+        return;
+    }
+
+    // The network message carried by a `Fragment` is the reassembled one.
+    let reassembled = matches!(msg.body, TransportBody::Fragment(_));
+    for msg in &network_messages(pinfo, msg) {
+        let (wire_expr, request_id) = network_message_target(msg);
+        let key_expr = wire_expr.and_then(|wire_expr| key_expr::resolve(pinfo, wire_expr));
+        let payload_len =
+            Payload::from_network_message(msg).map_or(0, |payload| payload.bytes.len() as u64);
+        let qos = network_message_qos(msg);
+
+        let info =
+            epan_sys::wmem_alloc0((*pinfo).pool, std::mem::size_of::<TapInfo>()) as *mut TapInfo;
+        *info = TapInfo {
+            kind: message_kind(msg),
+            key_expr: packet_str(pinfo, key_expr),
+            src_zid: packet_str(pinfo, conversation::source_zid(pinfo)),
+            dst_zid: packet_str(pinfo, conversation::destination_zid(pinfo)),
+            has_request_id: request_id.is_some(),
+            request_id: request_id.unwrap_or_default(),
+            payload_len,
+            priority: qos.priority,
+            congestion_control: qos.congestion_control.unwrap_or_default(),
+            express: qos.express.unwrap_or_default(),
+            reliable: msg.reliability == Reliability::Reliable,
+            reassembled,
+        };
+        epan_sys::tap_queue_packet(TAP_ID, pinfo, info as *const c_void);
+    }
+}
+
+/// Returns the string address of a node, its alias or else its ZID.
+unsafe fn node_address(
+    pinfo: *mut epan_sys::packet_info,
+    zid: *const c_char,
+) -> Option<epan_sys::address> {
+    if zid.is_null() {
+        return None;
+    }
+    let name = alias::name(&CStr::from_ptr(zid).to_string_lossy());
+    let name = packet_str(pinfo, Some(name));
+    if name.is_null() {
+        return None;
+    }
+    // The C inline function `set_address`, the tables copy the address.
+    Some(epan_sys::address {
+        type_: epan_sys::address_type_AT_STRINGZ as _,
+        len: (CStr::from_ptr(name).count_bytes() + 1) as _,
+        data: name as *const c_void,
+        priv_: ptr::null_mut(),
+    })
+}
+
+unsafe extern "C" fn conversation_filter_type(
+    _item: *mut epan_sys::conv_item_t,
+    filter: epan_sys::conv_filter_type_e,
+) -> *const c_char {
+    match filter {
+        epan_sys::conv_filter_type_e_CONV_FT_SRC_ADDRESS => c"zenoh.srcname".as_ptr(),
+        epan_sys::conv_filter_type_e_CONV_FT_DST_ADDRESS => c"zenoh.dstname".as_ptr(),
+        epan_sys::conv_filter_type_e_CONV_FT_ANY_ADDRESS => c"zenoh.name".as_ptr(),
+        _ => epan_sys::CONV_FILTER_INVALID.as_ptr() as *const c_char,
+    }
+}
+
+unsafe extern "C" fn endpoint_filter_type(
+    _item: *mut epan_sys::endpoint_item_t,
+    filter: epan_sys::conv_filter_type_e,
+) -> *const c_char {
+    match filter {
+        epan_sys::conv_filter_type_e_CONV_FT_ANY_ADDRESS => c"zenoh.name".as_ptr(),
+        _ => epan_sys::CONV_FILTER_INVALID.as_ptr() as *const c_char,
+    }
+}
+
+/// Count a network message in the conversation of its sender and receiver, over its payload
+/// bytes.
+unsafe extern "C" fn conversation_packet(
+    tapdata: *mut c_void,
+    pinfo: *mut epan_sys::packet_info,
+    _edt: *mut epan_sys::epan_dissect_t,
+    data: *const c_void,
+    flags: epan_sys::tap_flags_t,
+) -> epan_sys::tap_packet_status {
+    let hash = tapdata as *mut epan_sys::conv_hash_t;
+    (*hash).flags = flags;
+    let info = &*(data as *const TapInfo);
+    let (Some(src), Some(dst)) = (
+        node_address(pinfo, info.src_zid),
+        node_address(pinfo, info.dst_zid),
+    ) else {
+        return epan_sys::tap_packet_status_TAP_PACKET_DONT_REDRAW;
+    };
+    epan_sys::add_conversation_table_data(
+        hash,
+        &src,
+        &dst,
+        0,
+        0,
+        1,
+        info.payload_len as c_int,
+        &mut (*pinfo).rel_ts,
+        &mut (*pinfo).abs_ts,
+        &raw mut CONVERSATION_INFO,
+        epan_sys::conversation_type_CONVERSATION_NONE,
+    );
+    epan_sys::tap_packet_status_TAP_PACKET_REDRAW
+}
+
+/// Count a network message for both its sender and receiver, over its payload bytes.
+unsafe extern "C" fn endpoint_packet(
+    tapdata: *mut c_void,
+    pinfo: *mut epan_sys::packet_info,
+    _edt: *mut epan_sys::epan_dissect_t,
+    data: *const c_void,
+    flags: epan_sys::tap_flags_t,
+) -> epan_sys::tap_packet_status {
+    let hash = tapdata as *mut epan_sys::conv_hash_t;
+    (*hash).flags = flags;
+    let info = &*(data as *const TapInfo);
+    let mut status = epan_sys::tap_packet_status_TAP_PACKET_DONT_REDRAW;
+    for (zid, sender) in [(info.src_zid, true), (info.dst_zid, false)] {
+        let Some(address) = node_address(pinfo, zid) else {
+            continue;
+        };
+        epan_sys::add_endpoint_table_data(
+            hash,
+            &address,
+            0,
+            sender,
+            1,
+            info.payload_len as c_int,
+            &raw mut ENDPOINT_INFO,
+            epan_sys::conversation_type_CONVERSATION_NONE,
+        );
+        status = epan_sys::tap_packet_status_TAP_PACKET_REDRAW;
+    }
+    status
+}
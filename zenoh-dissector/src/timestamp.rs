@@ -0,0 +1,148 @@
+//! Decoding of Zenoh HLC timestamps with a clock-skew analysis against the capture time.
+//!
+//! The skew of a timestamp is its time minus the capture time of the frame carrying it.
+//! Skews are aggregated per HLC id (i.e. per ZID) over the first pass of the capture, so a
+//! drifting node shows up as a growing or consistently offset skew.
+
+use std::{cell::RefCell, collections::HashMap};
+
+use anyhow::Result;
+use zenoh_protocol::core::Timestamp;
+
+use crate::{
+    header_field::{FieldKind, HeaderFieldMap, Registration},
+    tree::{set_generated, TreeArgs},
+};
+
+pub const FIELD_TIMESTAMP: &str = "zenoh.timestamp";
+pub const FIELD_TIME: &str = "zenoh.timestamp.time";
+pub const FIELD_ID: &str = "zenoh.timestamp.id";
+pub const FIELD_DELTA: &str = "zenoh.timestamp.delta";
+pub const FIELD_SKEW: &str = "zenoh.timestamp.skew";
+
+pub(crate) const NANOS_PER_SEC: i128 = 1_000_000_000;
+
+/// The timestamp header fields, shared by every message carrying an HLC timestamp.
+pub struct HlcTimestamp;
+
+impl Registration for HlcTimestamp {
+    fn generate_hf_map(prefix: &str) -> HeaderFieldMap {
+        HeaderFieldMap::new()
+            .add(format!("{prefix}.time"), "Time", FieldKind::AbsoluteTime)
+            .add(format!("{prefix}.id"), "ID", FieldKind::Text)
+            .add(
+                format!("{prefix}.delta"),
+                "Timestamp minus Capture Time",
+                FieldKind::RelativeTime,
+            )
+            .add(format!("{prefix}.skew"), "Skew of ID", FieldKind::Text)
+    }
+
+    fn generate_subtree_names(prefix: &str) -> Vec<String> {
+        vec![prefix.to_string()]
+    }
+}
+
+/// Skew statistics of a single HLC id, in nanoseconds.
+#[derive(Debug, Clone, Copy)]
+struct SkewStats {
+    count: u64,
+    min: i128,
+    max: i128,
+    sum: i128,
+}
+
+impl SkewStats {
+    fn new(skew: i128) -> Self {
+        Self {
+            count: 1,
+            min: skew,
+            max: skew,
+            sum: skew,
+        }
+    }
+
+    fn update(&mut self, skew: i128) {
+        self.count += 1;
+        self.min = self.min.min(skew);
+        self.max = self.max.max(skew);
+        self.sum += skew;
+    }
+}
+
+impl std::fmt::Display for SkewStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let ms = |ns: i128| ns as f64 / 1e6;
+        write!(
+            f,
+            "n={}, min={:.3} ms, avg={:.3} ms, max={:.3} ms",
+            self.count,
+            ms(self.min),
+            ms(self.sum / self.count as i128),
+            ms(self.max)
+        )
+    }
+}
+
+thread_local! {
+    static SKEW_STATS: RefCell<HashMap<String, SkewStats>> = RefCell::default();
+}
+
+/// Clear the skew statistics.
+pub(crate) fn init_skew_stats() {
+    SKEW_STATS.with_borrow_mut(|stats| stats.clear());
+}
+
+/// Returns the capture time of the current frame, in nanoseconds since the epoch.
+pub(crate) unsafe fn capture_time_nanos(pinfo: *mut epan_sys::_packet_info) -> i128 {
+    let abs_ts = (*pinfo).abs_ts;
+    abs_ts.secs as i128 * NANOS_PER_SEC + abs_ts.nsecs as i128
+}
+
+pub(crate) fn to_nstime(nanos: i128) -> epan_sys::nstime_t {
+    // Wireshark represents negative times with both components negative.
+    epan_sys::nstime_t {
+        secs: (nanos / NANOS_PER_SEC) as _,
+        nsecs: (nanos % NANOS_PER_SEC) as _,
+    }
+}
+
+/// Add the decoded timestamp fields below the timestamp text item.
+pub fn add_to_tree(
+    timestamp: &Timestamp,
+    item: *mut epan_sys::proto_item,
+    args: &TreeArgs,
+) -> Result<()> {
+    let args = args.item_subtree(item, FIELD_TIMESTAMP)?;
+    let time = timestamp.get_time();
+    let id = timestamp.get_id().to_string();
+    let time_nanos = time.as_secs() as i128 * NANOS_PER_SEC + time.subsec_nanos() as i128;
+
+    args.add_time(FIELD_TIME, &to_nstime(time_nanos))?;
+    args.add_text(FIELD_ID, &id)?;
+
+    let (capture_nanos, visited) = unsafe {
+        (
+            capture_time_nanos(args.pinfo),
+            (*(*args.pinfo).fd).visited() != 0,
+        )
+    };
+    let skew = time_nanos - capture_nanos;
+    set_generated(args.add_time(FIELD_DELTA, &to_nstime(skew))?);
+
+    let summary = SKEW_STATS.with_borrow_mut(|stats| {
+        // Only account for each frame once, re-dissections see the final statistics.
+        if !visited {
+            stats
+                .entry(id.clone())
+                .and_modify(|s| s.update(skew))
+                .or_insert_with(|| SkewStats::new(skew));
+        }
+        stats.get(&id).map(ToString::to_string)
+    });
+    if let Some(summary) = summary {
+        set_generated(args.add_text(FIELD_SKEW, &summary)?);
+    }
+
+    Ok(())
+}
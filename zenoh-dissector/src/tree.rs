@@ -1,15 +1,21 @@
 use anyhow::{bail, Result};
-use std::{collections::HashMap, ffi::CString};
+use std::collections::HashMap;
+
+use crate::{cdr::Value, display::bytes_preview, utils::lossy_c_string};
 
 // Pointer HashMap of Header Feild
 type HFPointerMap = HashMap<String, std::ffi::c_int>;
 // Pointer HashMap of Subtree
 type STPointerMap = HashMap<String, std::ffi::c_int>;
 
+/// Max number of scalar array elements rendered on a single line.
+const INLINE_ARRAY_LIMIT: usize = 16;
+
 #[derive(Debug, Clone, Copy)]
 pub struct TreeArgs<'a> {
     pub tree: *mut epan_sys::proto_tree,
     pub tvb: *mut epan_sys::tvbuff,
+    pub pinfo: *mut epan_sys::_packet_info,
     pub hf_map: &'a HFPointerMap,
     pub st_map: &'a STPointerMap,
     pub start: usize,
@@ -35,7 +41,7 @@ impl TreeArgs<'_> {
 
     pub fn make_subtree(&self, key: &str, name: &str) -> Result<Self> {
         let mut new_args = *self;
-        let name_c_str = CString::new(name).unwrap();
+        let name_c_str = lossy_c_string(name);
         new_args.tree = unsafe {
             let ti = epan_sys::proto_tree_add_none_format(
                 self.tree,
@@ -43,6 +49,7 @@ impl TreeArgs<'_> {
                 self.tvb,
                 self.start as _,
                 self.length as _,
+                c"%s".as_ptr(),
                 name_c_str.as_ptr(),
             );
             epan_sys::proto_item_add_subtree(ti, self.get_st(key)?)
@@ -50,6 +57,199 @@ impl TreeArgs<'_> {
 
         Ok(new_args)
     }
+
+    /// Attach a subtree to an existing item, e.g. to break a text field down into details.
+    pub fn item_subtree(&self, item: *mut epan_sys::proto_item, key: &str) -> Result<Self> {
+        let mut new_args = *self;
+        new_args.tree = unsafe { epan_sys::proto_item_add_subtree(item, self.get_st(key)?) };
+        Ok(new_args)
+    }
+
+    // The codec doesn't expose per-field byte offsets, so the typed fields below are added
+    // with a length of 0 to prevent wireshark from highlighting unrelated bytes.
+
+    pub fn add_text(&self, key: &str, value: &str) -> Result<*mut epan_sys::proto_item> {
+        let value_c_str = lossy_c_string(value);
+        Ok(unsafe {
+            epan_sys::proto_tree_add_string(
+                self.tree,
+                self.get_hf(key)?,
+                self.tvb,
+                self.start as _,
+                0,
+                value_c_str.as_ptr(),
+            )
+        })
+    }
+
+    /// Add a text field with a custom label, e.g. `name: value` for dynamically typed data.
+    pub fn add_labeled_text(
+        &self,
+        key: &str,
+        value: &str,
+        label: &str,
+    ) -> Result<*mut epan_sys::proto_item> {
+        let value_c_str = lossy_c_string(value);
+        let label_c_str = lossy_c_string(label);
+        Ok(unsafe {
+            epan_sys::proto_tree_add_string_format(
+                self.tree,
+                self.get_hf(key)?,
+                self.tvb,
+                self.start as _,
+                0,
+                value_c_str.as_ptr(),
+                c"%s".as_ptr(),
+                label_c_str.as_ptr(),
+            )
+        })
+    }
+
+    /// Add a decoded value as `name: value` text fields, with subtrees for its structure.
+    ///
+    /// `branch` is the key of the subtrees and `field` the key of the text fields.
+    pub fn add_value(&self, branch: &str, field: &str, name: &str, value: &Value) -> Result<()> {
+        match value {
+            Value::Scalar(scalar) => {
+                self.add_labeled_text(field, scalar, &format!("{name}: {scalar}"))?;
+            }
+            Value::Bytes(bytes) => {
+                let hex = bytes.iter().map(|b| format!("{b:02x}")).collect::<String>();
+                self.add_labeled_text(field, &hex, &format!("{name}: {}", bytes_preview(bytes)))?;
+            }
+            Value::Array(items)
+                if items.len() <= INLINE_ARRAY_LIMIT
+                    && items.iter().all(|item| matches!(item, Value::Scalar(_))) =>
+            {
+                let scalars = items
+                    .iter()
+                    .filter_map(|item| match item {
+                        Value::Scalar(scalar) => Some(scalar.as_str()),
+                        _ => None,
+                    })
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                let scalars = format!("[{scalars}]");
+                self.add_labeled_text(field, &scalars, &format!("{name}: {scalars}"))?;
+            }
+            Value::Array(items) => {
+                let args = self.make_subtree(branch, &format!("{name} [{}]", items.len()))?;
+                for (index, item) in items.iter().enumerate() {
+                    args.add_value(branch, field, &format!("[{index}]"), item)?;
+                }
+            }
+            Value::Message(fields) => {
+                let args = self.make_subtree(branch, name)?;
+                for (name, value) in fields {
+                    args.add_value(branch, field, name, value)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub fn add_number(&self, key: &str, value: u64) -> Result<*mut epan_sys::proto_item> {
+        Ok(unsafe {
+            epan_sys::proto_tree_add_uint64(
+                self.tree,
+                self.get_hf(key)?,
+                self.tvb,
+                self.start as _,
+                0,
+                value,
+            )
+        })
+    }
+
+    pub fn add_bytes(&self, key: &str, value: &[u8]) -> Result<*mut epan_sys::proto_item> {
+        Ok(unsafe {
+            epan_sys::proto_tree_add_bytes_with_length(
+                self.tree,
+                self.get_hf(key)?,
+                self.tvb,
+                self.start as _,
+                0,
+                value.as_ptr(),
+                value.len() as _,
+            )
+        })
+    }
+
+    pub fn add_time(
+        &self,
+        key: &str,
+        value: &epan_sys::nstime_t,
+    ) -> Result<*mut epan_sys::proto_item> {
+        Ok(unsafe {
+            epan_sys::proto_tree_add_time(
+                self.tree,
+                self.get_hf(key)?,
+                self.tvb,
+                self.start as _,
+                0,
+                value,
+            )
+        })
+    }
+
+    pub fn add_enum(&self, key: &str, value: u32) -> Result<*mut epan_sys::proto_item> {
+        Ok(unsafe {
+            epan_sys::proto_tree_add_uint(
+                self.tree,
+                self.get_hf(key)?,
+                self.tvb,
+                self.start as _,
+                0,
+                value,
+            )
+        })
+    }
+
+    pub fn add_frame(&self, key: &str, frame: u32) -> Result<*mut epan_sys::proto_item> {
+        Ok(unsafe {
+            epan_sys::proto_tree_add_uint(
+                self.tree,
+                self.get_hf(key)?,
+                self.tvb,
+                self.start as _,
+                0,
+                frame,
+            )
+        })
+    }
+
+    pub fn add_bool(&self, key: &str, value: bool) -> Result<*mut epan_sys::proto_item> {
+        Ok(unsafe {
+            epan_sys::proto_tree_add_boolean(
+                self.tree,
+                self.get_hf(key)?,
+                self.tvb,
+                self.start as _,
+                0,
+                value as u64,
+            )
+        })
+    }
+}
+
+/// Mark an item as generated by the dissector rather than read from the packet,
+/// i.e. the C macro `proto_item_set_generated`.
+pub fn set_generated(item: *mut epan_sys::proto_item) {
+    unsafe {
+        if !item.is_null() && !(*item).finfo.is_null() {
+            (*(*item).finfo).flags |= epan_sys::FI_GENERATED;
+        }
+    }
+}
+
+/// Hide an item from the tree while keeping it filterable, i.e. the C macro
+/// `proto_item_set_hidden`.
+pub fn set_hidden(item: *mut epan_sys::proto_item) {
+    unsafe {
+        if !item.is_null() && !(*item).finfo.is_null() {
+            (*(*item).finfo).flags |= epan_sys::FI_HIDDEN;
+        }
+    }
 }
 
 pub trait AddToTree {
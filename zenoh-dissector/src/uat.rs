@@ -0,0 +1,189 @@
+//! User Accessible Tables (UAT) whose columns are all strings.
+//!
+//! Wireshark owns the records and manages them through the callbacks below, the plugin only
+//! reads them back with [`records`] once the table has been loaded or edited.
+
+use std::ffi::{c_char, c_uint, c_void, CStr, CString};
+
+/// Validates the value of a column, returning the error to show to the user.
+pub type Check = fn(&str) -> Result<(), String>;
+
+/// A record of a table with `N` columns, each one a glib string (possibly null).
+#[repr(C)]
+pub struct StringRecord<const N: usize> {
+    fields: [*mut c_char; N],
+}
+
+pub struct Column {
+    pub name: &'static CStr,
+    pub title: &'static CStr,
+    pub desc: &'static CStr,
+    pub mode: epan_sys::uat_text_mode_t,
+    pub check: Option<Check>,
+}
+
+/// Register a string table as a preference of the given module.
+///
+/// `data` and `count` are the statics wireshark keeps the records in, `post_update` is called
+/// whenever they change.
+#[allow(clippy::too_many_arguments)]
+pub unsafe fn register<const N: usize>(
+    module: *mut epan_sys::module_t,
+    name: &CStr,
+    title: &CStr,
+    desc: &CStr,
+    filename: &CStr,
+    columns: [Column; N],
+    data: *mut *mut StringRecord<N>,
+    count: *mut c_uint,
+    post_update: epan_sys::uat_post_update_cb_t,
+) {
+    let mut fields = columns
+        .iter()
+        .enumerate()
+        .map(|(index, column)| epan_sys::uat_field_t {
+            name: column.name.as_ptr(),
+            title: column.title.as_ptr(),
+            mode: column.mode,
+            cb: epan_sys::_uat_field_t__bindgen_ty_1 {
+                chk: Some(check_cb),
+                set: Some(set_cb::<N>),
+                tostr: Some(tostr_cb::<N>),
+            },
+            // The callbacks are shared by all columns, so they receive the column index
+            // and checker through their data pointers.
+            cbdata: epan_sys::_uat_field_t__bindgen_ty_2 {
+                chk: column
+                    .check
+                    .map_or(std::ptr::null(), |check| check as *const c_void),
+                set: index as *const c_void,
+                tostr: index as *const c_void,
+            },
+            fld_data: std::ptr::null(),
+            desc: column.desc.as_ptr(),
+            priv_: std::ptr::null_mut(),
+        })
+        .collect::<Vec<_>>();
+    // Terminated by an empty field, i.e. the C macro `UAT_END_FIELDS`.
+    fields.push(std::mem::zeroed());
+
+    let uat = epan_sys::uat_new(
+        title.as_ptr(),
+        std::mem::size_of::<StringRecord<N>>(),
+        filename.as_ptr(),
+        true,
+        data as *mut c_void,
+        count,
+        epan_sys::UAT_AFFECTS_DISSECTION,
+        std::ptr::null(),
+        Some(copy_cb::<N>),
+        None,
+        Some(free_cb::<N>),
+        post_update,
+        None,
+        Box::leak(fields.into_boxed_slice()).as_mut_ptr(),
+    );
+    epan_sys::prefs_register_uat_preference(
+        module,
+        name.as_ptr(),
+        title.as_ptr(),
+        desc.as_ptr(),
+        uat,
+    );
+}
+
+/// Returns the records of a table, with empty strings for unset columns.
+pub unsafe fn records<const N: usize>(
+    data: *const StringRecord<N>,
+    count: c_uint,
+) -> Vec<[String; N]> {
+    if data.is_null() {
+        return Vec::new();
+    }
+    std::slice::from_raw_parts(data, count as usize)
+        .iter()
+        .map(|record| {
+            record.fields.map(|field| {
+                if field.is_null() {
+                    String::new()
+                } else {
+                    CStr::from_ptr(field).to_string_lossy().into_owned()
+                }
+            })
+        })
+        .collect()
+}
+
+unsafe fn field_str<'a>(ptr: *const c_char, len: c_uint) -> &'a str {
+    if ptr.is_null() {
+        return "";
+    }
+    let bytes = std::slice::from_raw_parts(ptr as *const u8, len as usize);
+    std::str::from_utf8(bytes).unwrap_or_default()
+}
+
+unsafe extern "C" fn check_cb(
+    _record: *mut c_void,
+    ptr: *const c_char,
+    len: c_uint,
+    chk_data: *const c_void,
+    _fld_data: *const c_void,
+    error: *mut *mut c_char,
+) -> bool {
+    if chk_data.is_null() {
+        return true;
+    }
+    let check: Check = std::mem::transmute::<*const c_void, Check>(chk_data);
+    match check(field_str(ptr, len)) {
+        Ok(()) => true,
+        Err(err) => {
+            // Freed by wireshark.
+            let err = CString::new(err).unwrap_or_default();
+            *error = epan_sys::g_strdup(err.as_ptr());
+            false
+        }
+    }
+}
+
+unsafe extern "C" fn set_cb<const N: usize>(
+    record: *mut c_void,
+    ptr: *const c_char,
+    len: c_uint,
+    set_data: *const c_void,
+    _fld_data: *const c_void,
+) {
+    let field = &mut (*(record as *mut StringRecord<N>)).fields[set_data as usize];
+    epan_sys::g_free(*field as _);
+    *field = epan_sys::g_strndup(ptr, len as _);
+}
+
+unsafe extern "C" fn tostr_cb<const N: usize>(
+    record: *mut c_void,
+    out_ptr: *mut *mut c_char,
+    out_len: *mut c_uint,
+    tostr_data: *const c_void,
+    _fld_data: *const c_void,
+) {
+    let field = (*(record as *mut StringRecord<N>)).fields[tostr_data as usize];
+    *out_ptr = epan_sys::g_strdup(if field.is_null() { c"".as_ptr() } else { field });
+    *out_len = CStr::from_ptr(*out_ptr).count_bytes() as _;
+}
+
+unsafe extern "C" fn copy_cb<const N: usize>(
+    dest: *mut c_void,
+    source: *const c_void,
+    _len: usize,
+) -> *mut c_void {
+    let source = &*(source as *const StringRecord<N>);
+    let dest_record = &mut *(dest as *mut StringRecord<N>);
+    for (dest_field, source_field) in dest_record.fields.iter_mut().zip(source.fields) {
+        *dest_field = epan_sys::g_strdup(source_field);
+    }
+    dest
+}
+
+unsafe extern "C" fn free_cb<const N: usize>(record: *mut c_void) {
+    for field in (*(record as *mut StringRecord<N>)).fields {
+        epan_sys::g_free(field as _);
+    }
+}
@@ -1,21 +1,112 @@
 use anyhow::Result;
 use std::{
+    borrow::Cow,
     error::Error,
-    ffi::{c_char, CString},
+    ffi::{c_char, c_void, CStr, CString},
+    fmt::Write,
 };
-use zenoh_buffers::ZSlice;
+use zenoh_buffers::{buffer::Buffer, ZSlice};
 use zenoh_protocol::{
-    network::{NetworkBody, NetworkMessage},
-    transport::{BatchSize, TransportMessage},
+    common::ZExtZ64,
+    core::WireExpr,
+    network::{NetworkBody, NetworkMessage, RequestId},
+    transport::{BatchSize, TransportBody, TransportMessage},
 };
 use zenoh_transport::common::batch::{BatchConfig, RBatch};
 
+use crate::{fragment, key_expr, payload::Payload, qos::QoSBits, session, ws_log, PROTOCOL_DATA};
+
+/// Convert text decoded from the wire to a C string, replacing the NUL bytes it may contain.
+pub fn lossy_c_string(s: impl Into<String>) -> CString {
+    let s = s.into();
+    CString::new(s.replace('\0', "\u{FFFD}")).unwrap_or_default()
+}
+
+/// Copy text to the packet scope as a C string, e.g. for a tap event, or null for `None`.
+pub(crate) unsafe fn packet_str(
+    pinfo: *mut epan_sys::_packet_info,
+    s: Option<impl Into<String>>,
+) -> *const c_char {
+    match s {
+        Some(s) => epan_sys::wmem_strdup((*pinfo).pool, lossy_c_string(s).as_ptr()),
+        None => std::ptr::null(),
+    }
+}
+
 pub fn leak_nul_terminated_str(s: &str) -> Result<*const c_char> {
     Ok(Box::leak(CString::new(s)?.into_boxed_c_str()).as_ptr())
 }
 
+/// Returns whether this is the first call with `key` while dissecting the current frame, e.g. to
+/// add the fields of a frame once even if it carries several PDUs or messages.
+pub unsafe fn first_in_frame(pinfo: *mut epan_sys::_packet_info, key: u32) -> bool {
+    let proto_id = PROTOCOL_DATA.with_borrow(|d| d.id);
+    let scope = (*pinfo).pool;
+    if !epan_sys::p_get_proto_data(scope, pinfo, proto_id, key).is_null() {
+        return false;
+    }
+    // Any non-null pointer marks the key, the packet scoped data being freed with the frame.
+    epan_sys::p_add_proto_data(scope, pinfo, proto_id, key, pinfo as *mut _);
+    true
+}
+
+/// Leak a null-terminated `value_string` array built from `(value, name)` pairs.
+pub fn leak_value_strings(names: &[(u32, &str)]) -> Result<*const epan_sys::value_string> {
+    let mut value_strings = names
+        .iter()
+        .map(|(value, name)| {
+            Ok(epan_sys::value_string {
+                value: *value,
+                strptr: leak_nul_terminated_str(name)?,
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+    value_strings.push(epan_sys::value_string {
+        value: 0,
+        strptr: std::ptr::null(),
+    });
+    Ok(Box::leak(value_strings.into_boxed_slice()).as_ptr())
+}
+
+/// Returns the network messages of a `Frame`.
+pub(crate) fn frame_messages(msg: &TransportMessage) -> &[NetworkMessage] {
+    match &msg.body {
+        TransportBody::Frame(frame) => &frame.payload,
+        _ => &[],
+    }
+}
+
+/// Returns the network messages carried by a transport message: the ones of a `Frame`, or the
+/// one reassembled from the fragments completed by a `Fragment`.
+pub(crate) fn network_messages(
+    pinfo: *mut epan_sys::_packet_info,
+    msg: &TransportMessage,
+) -> Vec<Cow<'_, NetworkMessage>> {
+    let reassembled = fragment::reassembled(pinfo, msg).and_then(|reassembled| reassembled.msg);
+    frame_messages(msg)
+        .iter()
+        .map(Cow::Borrowed)
+        .chain(reassembled.map(Cow::Owned))
+        .collect()
+}
+
+/// Returns the wire expression and request id of a network message, if it has them.
+pub(crate) fn network_message_target(
+    msg: &NetworkMessage,
+) -> (Option<&WireExpr<'static>>, Option<RequestId>) {
+    match &msg.body {
+        NetworkBody::Push(push) => (Some(&push.wire_expr), None),
+        NetworkBody::Request(request) => (Some(&request.wire_expr), Some(request.id)),
+        NetworkBody::Response(response) => (Some(&response.wire_expr), Some(response.rid)),
+        NetworkBody::ResponseFinal(response_final) => (None, Some(response_final.rid)),
+        NetworkBody::Interest(interest) => (interest.wire_expr.as_ref(), None),
+        _ => (None, None),
+    }
+}
+
 pub struct SizedSummary {
     is_full: bool,
+    limit: usize,
     data: Vec<String>,
 }
 
@@ -23,7 +114,8 @@ impl SizedSummary {
     pub fn new(limit: usize) -> Self {
         Self {
             is_full: false,
-            data: Vec::with_capacity(limit),
+            limit,
+            data: Vec::new(),
         }
     }
 
@@ -36,7 +128,7 @@ impl SizedSummary {
         F: FnOnce() -> String,
     {
         if !self.is_full {
-            if self.data.len() >= self.data.capacity() {
+            if self.data.len() >= self.limit {
                 self.is_full = true;
             } else {
                 self.data.push(generate_msg());
@@ -61,44 +153,126 @@ impl std::fmt::Display for SizedSummary {
     }
 }
 
-pub(crate) fn new_rbatch(batch: &[u8], compression: bool) -> Result<RBatch, Box<dyn Error>> {
-    let zslice = ZSlice::from(batch.to_vec());
+/// Flag of the batch header signaling a compressed batch.
+const BATCH_HEADER_COMPRESSION: u8 = 1;
+
+/// Returns the transport messages of a batch, decompressed if needed, with whether the batch was
+/// actually compressed.
+pub(crate) fn batch_payload(batch: &[u8], compression: bool) -> (Cow<'_, [u8]>, bool) {
+    // With compression enabled, each batch starts with a header telling if it's compressed.
+    let (header, payload) = match batch.split_first() {
+        Some((&header, payload)) if compression => (header, payload),
+        _ => return (batch.into(), false),
+    };
+    if header & BATCH_HEADER_COMPRESSION == 0 {
+        return (payload.into(), false);
+    }
+    let mut bytes = vec![0; BatchSize::MAX as usize];
+    match lz4_flex::block::decompress_into(payload, &mut bytes) {
+        Ok(len) => {
+            bytes.truncate(len);
+            (bytes.into(), true)
+        }
+        // In case TransportMessage like InitAck are not compressed, read without assuming
+        // compression
+        Err(_) => (batch.into(), false),
+    }
+}
+
+/// Returns a reader of the transport messages of a batch payload.
+pub(crate) fn new_rbatch(payload: &[u8]) -> Result<RBatch, Box<dyn Error>> {
     let config = BatchConfig {
         mtu: BatchSize::MAX,
         is_streamed: false,
-        is_compression: compression,
+        is_compression: false,
     };
-    let mut rbatch = RBatch::new(config, zslice.clone());
-    if rbatch.initialize(|| vec![0; config.mtu as usize]).is_err() {
-        // In case TransportMessage like InitAck are not compressed, try to read without assuming
-        // compression
-        let config = BatchConfig {
-            mtu: BatchSize::MAX,
-            is_streamed: false,
-            is_compression: false,
-        };
-        rbatch = RBatch::new(config, zslice);
-        rbatch
-            .initialize(|| vec![0; config.mtu as usize])
-            .map_err(|err| err.to_string())?;
-    }
+    let mut rbatch = RBatch::new(config, ZSlice::from(payload.to_vec()));
+    rbatch
+        .initialize(|| vec![0; config.mtu as usize])
+        .map_err(|err| err.to_string())?;
     Ok(rbatch)
 }
 
-pub(crate) fn network_message_summary(msg: &NetworkMessage) -> String {
+/// Returns the decoded `ext_qos` of a network message.
+pub(crate) fn network_message_qos(msg: &NetworkMessage) -> QoSBits {
     use NetworkBody::*;
-    match &msg.body {
-        OAM(_) => "OAM".to_string(),
-        Push(_) => "Push".to_string(),
-        Request(_) => "Request".to_string(),
-        Response(_) => "Response".to_string(),
-        ResponseFinal(_) => "ResponseFinal".to_string(),
-        Interest(_) => "Interest".to_string(),
-        Declare(_) => "Declare".to_string(),
+    let raw = match &msg.body {
+        OAM(m) => ZExtZ64::from(m.ext_qos).value,
+        Push(m) => ZExtZ64::from(m.ext_qos).value,
+        Request(m) => ZExtZ64::from(m.ext_qos).value,
+        Response(m) => ZExtZ64::from(m.ext_qos).value,
+        ResponseFinal(m) => ZExtZ64::from(m.ext_qos).value,
+        Interest(m) => ZExtZ64::from(m.ext_qos).value,
+        Declare(m) => ZExtZ64::from(m.ext_qos).value,
+    };
+    QoSBits::network(raw)
+}
+
+/// What the Info column shows of every message.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct SummaryOptions {
+    /// Whether to show the QoS priority of network messages.
+    pub priority: bool,
+    /// Whether to show the resolved key expression, request id and payload size of network
+    /// messages, e.g. `Push demo/a (42B)`.
+    pub details: bool,
+}
+
+pub(crate) fn network_message_summary(
+    pinfo: *mut epan_sys::_packet_info,
+    msg: &NetworkMessage,
+    options: SummaryOptions,
+) -> String {
+    use NetworkBody::*;
+    let kind = match &msg.body {
+        OAM(_) => "OAM",
+        Push(_) => "Push",
+        Request(_) => "Request",
+        Response(_) => "Response",
+        ResponseFinal(_) => "ResponseFinal",
+        Interest(_) => "Interest",
+        Declare(_) => "Declare",
+    };
+    let mut summary = if options.priority {
+        format!("{kind}({})", network_message_qos(msg).priority_name())
+    } else {
+        kind.to_string()
+    };
+    if options.details {
+        let (wire_expr, request_id) = network_message_target(msg);
+        if let Some(request_id) = request_id {
+            let _ = write!(summary, " #{request_id}");
+        }
+        if let Some(wire_expr) = wire_expr {
+            let key_expr =
+                key_expr::resolve(pinfo, wire_expr).unwrap_or_else(|| wire_expr.to_string());
+            let _ = write!(summary, " {key_expr}");
+        }
+        if let Some(payload) = Payload::from_network_message(msg) {
+            let _ = write!(summary, " ({}B)", payload.bytes.len());
+        }
     }
+    summary
+}
+
+/// Returns the summaries of network messages, separated by commas.
+pub(crate) fn network_messages_summary(
+    pinfo: *mut epan_sys::_packet_info,
+    msgs: &[NetworkMessage],
+    options: SummaryOptions,
+) -> String {
+    let separator = if options.details { ", " } else { "," };
+    msgs.iter()
+        .map(|m| network_message_summary(pinfo, m, options))
+        .reduce(|acc, s| acc + separator + &s)
+        .unwrap_or_default()
 }
 
-pub(crate) fn transport_message_summary(msg: &TransportMessage) -> String {
+pub(crate) fn transport_message_summary(
+    pinfo: *mut epan_sys::_packet_info,
+    msg: &TransportMessage,
+    options: SummaryOptions,
+) -> String {
     use zenoh_protocol::transport::TransportBody::*;
     match &msg.body {
         OAM(_) => "OAM".to_string(),
@@ -106,19 +280,109 @@ pub(crate) fn transport_message_summary(msg: &TransportMessage) -> String {
         InitAck(_) => "InitAck".to_string(),
         OpenSyn(_) => "OpenSyn".to_string(),
         OpenAck(_) => "OpenAck".to_string(),
-        Close(_) => "Close".to_string(),
+        Close(close) => format!("Close({})", session::close_reason_name(close.reason)),
         KeepAlive(_) => "KeepAlive".to_string(),
-        Frame(frame) => {
-            "Frame[".to_string()
-                + &frame
-                    .payload
-                    .iter()
-                    .map(network_message_summary)
-                    .reduce(|acc, s| acc + "," + &s)
-                    .unwrap_or_default()
-                + "]"
-        }
+        Frame(frame) => format!(
+            "Frame[{}]",
+            network_messages_summary(pinfo, &frame.payload, options)
+        ),
         Fragment(_) => "Fragment".to_string(),
         Join(_) => "Join".to_string(),
     }
 }
+
+/// Data of a tap listener registered by a `-z` argument of tshark, e.g. a graph printed once the
+/// capture is read.
+pub(crate) trait CliListener {
+    /// Clear the data, e.g. before the capture is read again.
+    fn reset(&mut self);
+
+    /// Update the data with an event queued on the tap, returning whether it changed.
+    unsafe fn packet(&mut self, pinfo: *const epan_sys::_packet_info, data: *const c_void) -> bool;
+
+    fn draw(&self);
+}
+
+/// Register a listener of a tap for the `cli` command, which may be followed by a display
+/// filter, e.g. `zenoh.ros2.dot,ip.addr==10.0.0.1`.
+pub(crate) unsafe fn register_cli_listener<T: CliListener>(
+    tap: &CStr,
+    cli: &CStr,
+    opt_arg: *const c_char,
+    listener: T,
+) {
+    let prefix_len = cli.count_bytes();
+    let filter = if CStr::from_ptr(opt_arg).count_bytes() > prefix_len + 1 {
+        opt_arg.add(prefix_len + 1)
+    } else {
+        std::ptr::null()
+    };
+
+    let listener = Box::into_raw(Box::new(listener));
+    let err = epan_sys::register_tap_listener(
+        tap.as_ptr(),
+        listener as *mut c_void,
+        filter,
+        epan_sys::TL_REQUIRES_NOTHING,
+        Some(cli_reset::<T>),
+        Some(cli_packet::<T>),
+        Some(cli_draw::<T>),
+        Some(cli_finish::<T>),
+    );
+    if !err.is_null() {
+        ws_log::critical!(
+            "Couldn't register {} tap: {}",
+            cli.to_string_lossy(),
+            CStr::from_ptr((*err).str_).to_string_lossy()
+        );
+        epan_sys::g_string_free(err, 1);
+        drop(Box::from_raw(listener));
+    }
+}
+
+unsafe extern "C" fn cli_reset<T: CliListener>(tapdata: *mut c_void) {
+    (*(tapdata as *mut T)).reset();
+}
+
+unsafe extern "C" fn cli_packet<T: CliListener>(
+    tapdata: *mut c_void,
+    pinfo: *mut epan_sys::packet_info,
+    _edt: *mut epan_sys::epan_dissect_t,
+    data: *const c_void,
+    _flags: epan_sys::tap_flags_t,
+) -> epan_sys::tap_packet_status {
+    match (*(tapdata as *mut T)).packet(pinfo, data) {
+        true => epan_sys::tap_packet_status_TAP_PACKET_REDRAW,
+        false => epan_sys::tap_packet_status_TAP_PACKET_DONT_REDRAW,
+    }
+}
+
+unsafe extern "C" fn cli_draw<T: CliListener>(tapdata: *mut c_void) {
+    (*(tapdata as *const T)).draw();
+}
+
+unsafe extern "C" fn cli_finish<T: CliListener>(tapdata: *mut c_void) {
+    drop(Box::from_raw(tapdata as *mut T));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn batch_payloads() {
+        let payload = b"\x04\x05messages".as_slice();
+        assert_eq!(batch_payload(payload, false), (payload.into(), false));
+        let batch = [&[0][..], payload].concat();
+        assert_eq!(batch_payload(&batch, true), (payload.into(), false));
+        let batch = [
+            &[BATCH_HEADER_COMPRESSION][..],
+            &lz4_flex::block::compress(payload),
+        ]
+        .concat();
+        assert_eq!(batch_payload(&batch, true), (payload.into(), true));
+        // Handshake messages aren't preceded by the header, so they don't decompress.
+        let handshake = b"\x01\xf0\x01".as_slice();
+        assert_eq!(batch_payload(handshake, true), (handshake.into(), false));
+    }
+}
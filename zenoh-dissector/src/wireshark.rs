@@ -1,4 +1,7 @@
-use crate::{header_field::FieldKind, utils::leak_nul_terminated_str};
+use crate::{
+    header_field::FieldKind,
+    utils::{leak_nul_terminated_str, leak_value_strings},
+};
 use anyhow::Result;
 use epan_sys::{field_display_e, ftenum};
 
@@ -13,14 +16,34 @@ impl FieldKind {
                 epan_sys::field_display_e_BASE_NONE,
                 epan_sys::ftenum_FT_NONE,
             ),
-            // Self::Number => (
-            //     epan_sys::field_display_e_BASE_DEC,
-            //     epan_sys::ftenum_FT_UINT8,
-            // ),
-            // Self::Bytes => (
-            //     epan_sys::field_display_e_SEP_SPACE,
-            //     epan_sys::ftenum_FT_BYTES,
-            // ),
+            Self::Number => (
+                epan_sys::field_display_e_BASE_DEC,
+                epan_sys::ftenum_FT_UINT64,
+            ),
+            Self::Bool => (
+                epan_sys::field_display_e_BASE_NONE,
+                epan_sys::ftenum_FT_BOOLEAN,
+            ),
+            Self::Bytes => (
+                epan_sys::field_display_e_SEP_SPACE,
+                epan_sys::ftenum_FT_BYTES,
+            ),
+            Self::AbsoluteTime => (
+                epan_sys::field_display_e_ABSOLUTE_TIME_UTC,
+                epan_sys::ftenum_FT_ABSOLUTE_TIME,
+            ),
+            Self::RelativeTime => (
+                epan_sys::field_display_e_BASE_NONE,
+                epan_sys::ftenum_FT_RELATIVE_TIME,
+            ),
+            Self::FrameNum => (
+                epan_sys::field_display_e_BASE_NONE,
+                epan_sys::ftenum_FT_FRAMENUM,
+            ),
+            Self::Enum(_) => (
+                epan_sys::field_display_e_BASE_DEC,
+                epan_sys::ftenum_FT_UINT32,
+            ),
         }
     }
 }
@@ -34,6 +57,10 @@ pub fn register_header_field(
     let hf_index_ptr = Box::leak(Box::new(-1)) as *mut _;
 
     let (field_display, field_type) = field_kind.convert();
+    let strings = match field_kind {
+        FieldKind::Enum(names) => leak_value_strings(names)? as *const _,
+        _ => std::ptr::null(),
+    };
     let hf_register_info = epan_sys::hf_register_info {
         p_id: hf_index_ptr,
         // We have no choice but to leak the strings here,
@@ -44,7 +71,7 @@ pub fn register_header_field(
             abbrev: leak_nul_terminated_str(filter_name)?,
             type_: field_type,
             display: field_display as _,
-            strings: std::ptr::null(),
+            strings,
             bitmask: 0,
             blurb: std::ptr::null(),
             id: -1,
@@ -68,6 +68,7 @@ macro_rules! debug {
         )
     }
 }
+pub(crate) use debug;
 
 /// Emits a log message with "noisy" severity, typically used for verbose or low-level diagnostic output.
 macro_rules! noisy {
@@ -80,12 +81,12 @@ macro_rules! noisy {
 }
 
 pub(crate) mod __private {
-    use std::{ffi::CString, panic::Location, ptr};
+    use std::{panic::Location, ptr};
 
     #[track_caller]
     pub(crate) fn with_level(level: epan_sys::ws_log_level, s: String) {
         unsafe {
-            let c_str = CString::new(s).unwrap();
+            let c_str = crate::utils::lossy_c_string(s);
             epan_sys::ws_log_full(
                 c"Zenoh".as_ptr(),
                 level,
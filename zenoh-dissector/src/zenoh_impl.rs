@@ -8,21 +8,111 @@ pub struct ZenohProtocol;
 
 mod impl_for_zenoh_protocol {
     use super::ZenohProtocol;
+    use crate::auth::Auth;
+    use crate::exported_pdu::ExportedPdu;
+    use crate::ext::{Attachment, Shm, SourceInfo, UnknownExt};
+    use crate::fragment::Fragments;
+    use crate::handshake::Handshake;
     use crate::header_field::{FieldKind, HeaderFieldMap, Registration};
+    use crate::lease::Lease;
+    use crate::linkstate::LinkStates;
+    use crate::lowlatency::LowLatency;
+    use crate::qos::QoS;
+    use crate::query::Queries;
+    use crate::ros2::Ros2;
+    use crate::selector::Selector;
+    use crate::serialization::Serialized;
+    use crate::session::Session;
+    use crate::timestamp::HlcTimestamp;
     use zenoh_protocol::transport::TransportMessage;
 
     impl Registration for ZenohProtocol {
         fn generate_hf_map(prefix: &str) -> HeaderFieldMap {
             let mut hf_map = HeaderFieldMap::new()
                 .add(prefix.to_string(), "Zenoh Protocol", FieldKind::Branch)
-                .add(format!("{prefix}.batch"), "Batch", FieldKind::Branch);
+                .add(format!("{prefix}.batch"), "Batch", FieldKind::Branch)
+                .add(format!("{prefix}.payload"), "Payload", FieldKind::Branch)
+                .add(
+                    format!("{prefix}.key_expr"),
+                    "Key Expression",
+                    FieldKind::Text,
+                );
             hf_map.extend(TransportMessage::generate_hf_map(prefix));
+            hf_map.extend(QoS::generate_hf_map(&format!("{prefix}.qos")));
+            hf_map.extend(HlcTimestamp::generate_hf_map(&format!(
+                "{prefix}.timestamp"
+            )));
+            hf_map.extend(Ros2::generate_hf_map(&format!("{prefix}.ros2")));
+            hf_map.extend(Serialized::generate_hf_map(&format!("{prefix}.serialized")));
+            hf_map.extend(SourceInfo::generate_hf_map(&format!("{prefix}.sinfo")));
+            hf_map.extend(Attachment::generate_hf_map(&format!("{prefix}.attachment")));
+            hf_map.extend(Shm::generate_hf_map(&format!("{prefix}.shm")));
+            hf_map.extend(UnknownExt::generate_hf_map(&format!(
+                "{prefix}.ext_unknown"
+            )));
+            hf_map.extend(Session::generate_hf_map(&format!("{prefix}.session")));
+            hf_map.extend(Handshake::generate_hf_map(&format!("{prefix}.handshake")));
+            hf_map.extend(Auth::generate_hf_map(&format!("{prefix}.auth")));
+            hf_map.extend(Lease::generate_hf_map(&format!("{prefix}.lease")));
+            hf_map.extend(LinkStates::generate_hf_map(&format!("{prefix}.linkstate")));
+            hf_map.extend(Selector::generate_hf_map(&format!("{prefix}.selector")));
+            hf_map.extend(Queries::generate_hf_map(&format!("{prefix}.query")));
+            hf_map.extend(Fragments::generate_hf_map(&format!("{prefix}.fragment")));
+            hf_map.extend(LowLatency::generate_hf_map(&format!("{prefix}.lowlatency")));
+            hf_map.extend(ExportedPdu::generate_hf_map(&format!("{prefix}.exported")));
             hf_map
         }
 
         fn generate_subtree_names(prefix: &str) -> Vec<String> {
-            let mut names = vec![prefix.to_string(), format!("{prefix}.batch")];
+            let mut names = vec![
+                prefix.to_string(),
+                format!("{prefix}.batch"),
+                format!("{prefix}.payload"),
+                format!("{prefix}.wire_expr"),
+            ];
             names.extend(TransportMessage::generate_subtree_names(prefix));
+            names.extend(QoS::generate_subtree_names(&format!("{prefix}.qos")));
+            names.extend(HlcTimestamp::generate_subtree_names(&format!(
+                "{prefix}.timestamp"
+            )));
+            names.extend(Ros2::generate_subtree_names(&format!("{prefix}.ros2")));
+            names.extend(Serialized::generate_subtree_names(&format!(
+                "{prefix}.serialized"
+            )));
+            names.extend(SourceInfo::generate_subtree_names(&format!(
+                "{prefix}.sinfo"
+            )));
+            names.extend(Attachment::generate_subtree_names(&format!(
+                "{prefix}.attachment"
+            )));
+            names.extend(Shm::generate_subtree_names(&format!("{prefix}.shm")));
+            names.extend(UnknownExt::generate_subtree_names(&format!(
+                "{prefix}.ext_unknown"
+            )));
+            names.extend(Session::generate_subtree_names(&format!(
+                "{prefix}.session"
+            )));
+            names.extend(Handshake::generate_subtree_names(&format!(
+                "{prefix}.handshake"
+            )));
+            names.extend(Auth::generate_subtree_names(&format!("{prefix}.auth")));
+            names.extend(Lease::generate_subtree_names(&format!("{prefix}.lease")));
+            names.extend(LinkStates::generate_subtree_names(&format!(
+                "{prefix}.linkstate"
+            )));
+            names.extend(Selector::generate_subtree_names(&format!(
+                "{prefix}.selector"
+            )));
+            names.extend(Queries::generate_subtree_names(&format!("{prefix}.query")));
+            names.extend(Fragments::generate_subtree_names(&format!(
+                "{prefix}.fragment"
+            )));
+            names.extend(LowLatency::generate_subtree_names(&format!(
+                "{prefix}.lowlatency"
+            )));
+            names.extend(ExportedPdu::generate_subtree_names(&format!(
+                "{prefix}.exported"
+            )));
             names
         }
     }